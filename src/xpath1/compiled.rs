@@ -0,0 +1,415 @@
+/*!
+The type `XPath` is an expression that has been parsed and checked once, so that it may be
+evaluated many times, against many documents, without further parsing.
+*/
+
+use crate::xpath1::evaluate::{evaluate_expr, EvaluationContext, EvaluationError, NodeIter};
+use crate::xpath1::model::{Expr, LocationPath};
+use crate::xpath1::parser::{read_expr_with_options, ParseError, ParserOptions};
+use crate::xpath1::{Error, XPathObject};
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+// ------------------------------------------------------------------------------------------------
+// Public Types
+// ------------------------------------------------------------------------------------------------
+
+///
+/// A compiled XPath expression; either parsed from a string with
+/// [`compile`](struct.XPath.html#method.compile), or built from a model value with
+/// [`from_path`](struct.XPath.html#method.from_path) or
+/// [`from_expr`](struct.XPath.html#method.from_expr).
+///
+/// When compiled every function call is checked against the function registry, and every
+/// namespace prefix against the namespace declarations, of an evaluation context; the expression
+/// should then be evaluated with a context that has the same functions and declarations.
+///
+/// # Example
+///
+/// ```rust
+/// use xml_dom_xpath::xpath1::evaluate::EvaluationContext;
+/// use xml_dom_xpath::xpath1::{XPath, XPathObject};
+/// use xml_dom::parser::read_xml;
+///
+/// let xpath = XPath::compile("count(//item[@quantity > 1])").unwrap();
+///
+/// for xml in &[
+///     r#"<order><item quantity="2"/><item quantity="1"/></order>"#,
+///     r#"<order><item quantity="3"/><item quantity="5"/></order>"#,
+/// ] {
+///     let document_node = read_xml(xml).unwrap();
+///     let result = xpath.evaluate(&EvaluationContext::from(document_node)).unwrap();
+///     assert!(matches!(result, XPathObject::Number(count) if count >= 1.0));
+/// }
+/// ```
+///
+#[derive(Clone, Debug, PartialEq)]
+pub struct XPath {
+    expr: Expr,
+}
+
+// ------------------------------------------------------------------------------------------------
+// Implementations
+// ------------------------------------------------------------------------------------------------
+
+impl Display for XPath {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.expr)
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+
+impl FromStr for XPath {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::compile(s)
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+
+impl XPath {
+    ///
+    /// Parse and check the expression `xpath` using a default evaluation context; that is, only
+    /// functions in the core function library may be called and only the `xml` prefix may be
+    /// used. Use [`compile_with`](struct.XPath.html#method.compile_with) for expressions that
+    /// use namespace prefixes or extension functions.
+    ///
+    pub fn compile(xpath: &str) -> Result<Self, Error> {
+        Self::compile_with(xpath, &EvaluationContext::default())
+    }
+
+    ///
+    /// Parse and check the expression `xpath` using the functions and namespace declarations of
+    /// `context`, within the default [`ParserOptions`](parser/struct.ParserOptions.html) limits.
+    ///
+    pub fn compile_with(xpath: &str, context: &EvaluationContext) -> Result<Self, Error> {
+        Self::compile_with_options(xpath, context, &ParserOptions::default())
+    }
+
+    ///
+    /// Parse and check the expression `xpath` using the functions and namespace declarations of
+    /// `context`, where the expression must be within the limits in `options`.
+    ///
+    pub fn compile_with_options(
+        xpath: &str,
+        context: &EvaluationContext,
+        options: &ParserOptions,
+    ) -> Result<Self, Error> {
+        Self::from_expr(
+            read_expr_with_options(xpath, context.functions(), options)?,
+            context,
+        )
+    }
+
+    ///
+    /// Check the location path `path`, using the functions and namespace declarations of
+    /// `context`.
+    ///
+    pub fn from_path(path: LocationPath, context: &EvaluationContext) -> Result<Self, Error> {
+        Self::from_expr(Expr::from(path), context)
+    }
+
+    ///
+    /// Check the expression `expr`, using the functions and namespace declarations of `context`.
+    ///
+    pub fn from_expr(expr: Expr, context: &EvaluationContext) -> Result<Self, Error> {
+        check_function_calls(&expr, context)?;
+        if let Some(prefix) = expr
+            .prefixes()
+            .into_iter()
+            .find(|prefix| context.namespace_uri(prefix).is_none())
+        {
+            return Err(EvaluationError::unknown_prefix(&prefix, &expr).into());
+        }
+        Ok(Self { expr })
+    }
+
+    ///
+    /// Evaluate this expression within `context`; this may be called any number of times, with
+    /// any number of contexts.
+    ///
+    pub fn evaluate(&self, context: &EvaluationContext) -> Result<XPathObject, EvaluationError> {
+        evaluate_expr(context, &self.expr)
+    }
+
+    ///
+    /// Return an iterator over the nodes selected by this expression within `context`, in
+    /// document order. If the expression is a location path the nodes are produced lazily, as
+    /// described for [`iter_path`](evaluate/fn.iter_path.html), so that taking only the first
+    /// few nodes avoids walking the rest of the document. Any other expression is evaluated when
+    /// the first node is requested, and if it does not evaluate to a node-set the only item is a
+    /// [`TypeError`](evaluate/enum.EvaluationError.html#variant.TypeError) error.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use xml_dom_xpath::xpath1::evaluate::EvaluationContext;
+    /// use xml_dom_xpath::xpath1::XPath;
+    /// use xml_dom::level2::Element;
+    /// use xml_dom::parser::read_xml;
+    ///
+    /// let document_node = read_xml(
+    ///     r#"<order><item sku="A-1"/><item sku="B-2"/><item sku="C-3"/></order>"#,
+    /// ).unwrap();
+    /// let xpath = XPath::compile("//item").unwrap();
+    ///
+    /// let skus: Vec<String> = xpath
+    ///     .iter_nodes(&EvaluationContext::from(document_node))
+    ///     .take(2)
+    ///     .map(|node| node.unwrap().get_attribute("sku").unwrap())
+    ///     .collect();
+    /// assert_eq!(skus, vec!["A-1", "B-2"]);
+    /// ```
+    ///
+    pub fn iter_nodes(&self, context: &EvaluationContext) -> NodeIter {
+        NodeIter::from_expr(context, &self.expr)
+    }
+
+    ///
+    /// Return the model form of this expression.
+    ///
+    pub fn expr(&self) -> &Expr {
+        &self.expr
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+// Private Functions
+// ------------------------------------------------------------------------------------------------
+
+//
+// The parser has already checked calls to unprefixed function names, but this is repeated for
+// expressions built from the model; calls to prefixed names are checked using the expanded name.
+//
+fn check_function_calls(expr: &Expr, context: &EvaluationContext) -> Result<(), Error> {
+    for call in expr.function_calls() {
+        let name = call.name();
+        let namespace_uri = match name.prefix() {
+            None => None,
+            Some(prefix) => Some(
+                context
+                    .namespace_uri(prefix)
+                    .ok_or_else(|| EvaluationError::unknown_prefix(prefix, call))?,
+            ),
+        };
+        match context
+            .functions()
+            .signature(namespace_uri.as_deref(), name.local_name())
+        {
+            None => return Err(ParseError::UnknownFunction(name.to_string()).into()),
+            Some(signature) if !signature.accepts_arguments(call.arguments().count()) => {
+                return Err(ParseError::WrongArity(call.to_string()).into())
+            }
+            Some(_) => {}
+        }
+    }
+    Ok(())
+}
+
+// ------------------------------------------------------------------------------------------------
+// Unit Tests
+// ------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::xpath1::evaluate::string_value;
+    use crate::xpath1::model::{Argument, DataType, Function, Predicate, Step};
+    use xml_dom::parser::read_xml;
+
+    const SOAP_NS: &str = "http://schemas.xmlsoap.org/soap/envelope/";
+
+    const SOAP_XML: &str = r#"<?xml version="1.0"?>
+<soap:Envelope xmlns:soap="http://schemas.xmlsoap.org/soap/envelope/">
+  <soap:Body><order id="ord-1001"/></soap:Body>
+</soap:Envelope>"#;
+
+    fn soap_context() -> EvaluationContext {
+        let mut context = EvaluationContext::default();
+        context.add_namespace("s", SOAP_NS);
+        context
+    }
+
+    fn count(result: Result<XPathObject, EvaluationError>) -> usize {
+        result.unwrap().into_node_set().unwrap().len()
+    }
+
+    #[test]
+    fn test_compile_and_evaluate_repeatedly() {
+        let xpath = XPath::compile("//order/@id").unwrap();
+        for _ in 0..3 {
+            let document_node = read_xml(SOAP_XML).unwrap();
+            let result = xpath.evaluate(&EvaluationContext::from(document_node));
+            assert_eq!(result.unwrap().to_string_value(), "ord-1001");
+        }
+        assert_eq!(
+            xpath.to_string(),
+            "/descendant-or-self::node()/child::order/attribute::id"
+        );
+        assert_eq!("//order/@id".parse::<XPath>().unwrap(), xpath);
+    }
+
+    #[test]
+    fn test_compile_checks_functions() {
+        assert_eq!(
+            XPath::compile("upper-case('a')").err(),
+            Some(Error::Parse(ParseError::UnknownFunction(
+                "upper-case".to_string()
+            )))
+        );
+        assert_eq!(
+            XPath::compile("count()").err(),
+            Some(Error::Parse(ParseError::WrongArity("count()".to_string())))
+        );
+        assert!(matches!(
+            XPath::compile("count(").err(),
+            Some(Error::Parse(ParseError::Syntax(_)))
+        ));
+    }
+
+    #[test]
+    fn test_compile_checks_prefixes() {
+        assert_eq!(
+            XPath::compile("//s:Body").err(),
+            Some(Error::Evaluate(EvaluationError::UnknownNamespacePrefix {
+                prefix: "s".to_string(),
+                expr: "/descendant-or-self::node()/child::s:Body".to_string(),
+            }))
+        );
+        assert_eq!(
+            XPath::compile("$s:order").err(),
+            Some(Error::Evaluate(EvaluationError::UnknownNamespacePrefix {
+                prefix: "s".to_string(),
+                expr: "$s:order".to_string(),
+            }))
+        );
+        assert!(XPath::compile("//@xml:lang").is_ok());
+        for (expr, hint) in &[
+            ("child:para", "did you mean 'child::'?"),
+            ("following-sibling:x", "did you mean 'following-sibling::'?"),
+        ] {
+            let message = XPath::compile(expr).err().unwrap().to_string();
+            assert!(message.ends_with(hint), "{}", message);
+        }
+
+        let context = soap_context();
+        let xpath = XPath::compile_with("/s:Envelope/s:*/order", &context).unwrap();
+
+        let mut evaluation_context = EvaluationContext::from(read_xml(SOAP_XML).unwrap());
+        evaluation_context.add_namespace("s", SOAP_NS);
+        assert_eq!(count(xpath.evaluate(&evaluation_context)), 1);
+    }
+
+    #[test]
+    fn test_compile_checks_extension_functions() {
+        let mut context = soap_context();
+        assert_eq!(
+            XPath::compile_with("s:twice(1)", &context).err(),
+            Some(Error::Parse(ParseError::UnknownFunction(
+                "s:twice".to_string()
+            )))
+        );
+        assert!(matches!(
+            XPath::compile_with("t:twice(1)", &context).err(),
+            Some(Error::Evaluate(
+                EvaluationError::UnknownNamespacePrefix { .. }
+            ))
+        ));
+
+        context.register_function(
+            Some(SOAP_NS),
+            Function::with(
+                "twice",
+                &[Argument::with("value", DataType::Number, true)],
+                DataType::Number,
+            ),
+            |_, arguments| Ok(XPathObject::Number(arguments[0].to_number() * 2.0)),
+        );
+        let xpath = XPath::compile_with("s:twice(21)", &context).unwrap();
+        assert_eq!(xpath.evaluate(&context).unwrap(), XPathObject::Number(42.0));
+        assert_eq!(
+            XPath::compile_with("s:twice()", &context).err(),
+            Some(Error::Parse(ParseError::WrongArity(
+                "s:twice()".to_string()
+            )))
+        );
+    }
+
+    #[test]
+    fn test_compile_with_options() {
+        let context = EvaluationContext::default();
+        let mut options = ParserOptions::default();
+        let _ = options.set_max_steps(2);
+        assert!(XPath::compile_with_options("chapter/para", &context, &options).is_ok());
+        assert_eq!(
+            XPath::compile_with_options("chapter//para", &context, &options).err(),
+            Some(Error::Parse(ParseError::StepLimitExceeded {
+                steps: 3,
+                limit: 2
+            }))
+        );
+        assert!(matches!(
+            XPath::compile(&"(".repeat(10_000)).err(),
+            Some(Error::Parse(ParseError::DepthLimitExceeded { .. }))
+        ));
+        let long_path = vec!["para"; 2_000].join("/");
+        assert!(XPath::compile(&long_path).is_err());
+        assert!(
+            XPath::compile_with_options(&long_path, &context, &ParserOptions::unlimited()).is_ok()
+        );
+    }
+
+    #[test]
+    fn test_iter_nodes() {
+        let context = EvaluationContext::from(read_xml(SOAP_XML).unwrap());
+        let ids = |xpath: &str| {
+            XPath::compile(xpath)
+                .unwrap()
+                .iter_nodes(&context)
+                .map(|node| node.map(|node| string_value(&node)))
+                .collect::<Result<Vec<String>, EvaluationError>>()
+        };
+        assert_eq!(ids("//order/@id"), Ok(vec!["ord-1001".to_string()]));
+        assert_eq!(
+            ids("(//order | //order)/@id"),
+            Ok(vec!["ord-1001".to_string()])
+        );
+        assert_eq!(ids("//order[2]/@id"), Ok(vec![]));
+        assert_eq!(
+            ids("count(//order)"),
+            Err(EvaluationError::TypeError {
+                expr: "count(/descendant-or-self::node()/child::order)".to_string(),
+                expected: DataType::NodeSet,
+                found: DataType::Number,
+            })
+        );
+    }
+
+    #[test]
+    fn test_from_path() {
+        let mut path = LocationPath::absolute();
+        path.append(Step::child_elements("s:Envelope"))
+            .append(Step::child_elements("s:Body"));
+
+        assert!(XPath::from_path(path.clone(), &EvaluationContext::default()).is_err());
+
+        let xpath = XPath::from_path(path, &soap_context()).unwrap();
+        let mut context = EvaluationContext::from(read_xml(SOAP_XML).unwrap());
+        context.add_namespace("s", SOAP_NS);
+        assert_eq!(count(xpath.evaluate(&context)), 1);
+
+        let mut path = LocationPath::absolute();
+        path.append({
+            let mut step = Step::child_elements("order");
+            step.append(Predicate::function("not"));
+            step
+        });
+        assert_eq!(
+            XPath::from_path(path, &EvaluationContext::default()).err(),
+            Some(Error::Parse(ParseError::WrongArity("not()".to_string())))
+        );
+    }
+}
@@ -0,0 +1,137 @@
+/*!
+Lazy walkers over the forward axes of a single node, each returning nodes in document order.
+*/
+
+use crate::xpath1::evaluate::node_set::parent_of;
+use std::vec::IntoIter;
+use xml_dom::level2::{Node, NodeType, RefNode};
+
+// ------------------------------------------------------------------------------------------------
+// Public Types
+// ------------------------------------------------------------------------------------------------
+
+///
+/// A pre-order walk of the descendants of a node, optionally including the node itself; only the
+/// children of the nodes on the path from the node to the current position are held at any time.
+///
+#[derive(Debug)]
+pub(crate) struct Descendants {
+    first: Option<RefNode>,
+    stack: Vec<IntoIter<RefNode>>,
+}
+
+///
+/// A walk of the `following` axis of a node; the following siblings of the node and of each of
+/// its ancestors, in turn, with their descendants.
+///
+#[derive(Debug)]
+pub(crate) struct Following {
+    next_ancestor: Option<RefNode>,
+    siblings: IntoIter<RefNode>,
+    descendants: Option<Descendants>,
+}
+
+// ------------------------------------------------------------------------------------------------
+// Public Functions
+// ------------------------------------------------------------------------------------------------
+
+///
+/// The following siblings of `node`, in document order.
+///
+pub(crate) fn following_siblings(node: &RefNode) -> IntoIter<RefNode> {
+    match node.parent_node() {
+        None => Vec::new().into_iter(),
+        Some(parent) => {
+            let mut siblings = parent.child_nodes().into_iter();
+            for sibling in siblings.by_ref() {
+                if &sibling == node {
+                    break;
+                }
+            }
+            siblings
+        }
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+// Implementations
+// ------------------------------------------------------------------------------------------------
+
+impl Iterator for Descendants {
+    type Item = RefNode;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(node) = self.first.take() {
+            self.stack.push(node.child_nodes().into_iter());
+            return Some(node);
+        }
+        while let Some(children) = self.stack.last_mut() {
+            match children.next() {
+                None => {
+                    let _ = self.stack.pop();
+                }
+                Some(child) => {
+                    self.stack.push(child.child_nodes().into_iter());
+                    return Some(child);
+                }
+            }
+        }
+        None
+    }
+}
+
+impl Descendants {
+    pub(crate) fn new(node: &RefNode, include_self: bool) -> Self {
+        if include_self {
+            Self {
+                first: Some(node.clone()),
+                stack: Vec::new(),
+            }
+        } else {
+            Self {
+                first: None,
+                stack: vec![node.child_nodes().into_iter()],
+            }
+        }
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+
+impl Iterator for Following {
+    type Item = RefNode;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(node) = self.descendants.as_mut().and_then(Iterator::next) {
+                return Some(node);
+            }
+            if let Some(sibling) = self.siblings.next() {
+                self.descendants = Some(Descendants::new(&sibling, true));
+                continue;
+            }
+            let ancestor = self.next_ancestor.take()?;
+            self.siblings = following_siblings(&ancestor);
+            self.next_ancestor = ancestor.parent_node();
+        }
+    }
+}
+
+impl Following {
+    pub(crate) fn new(node: &RefNode) -> Self {
+        // An attribute is followed by the descendants of its element, and then by the nodes that
+        // follow that element.
+        match (node.node_type(), parent_of(node)) {
+            (NodeType::Attribute, Some(element)) => Self {
+                siblings: following_siblings(&element),
+                next_ancestor: element.parent_node(),
+                descendants: Some(Descendants::new(&element, false)),
+            },
+            _ => Self {
+                siblings: following_siblings(node),
+                next_ancestor: node.parent_node(),
+                descendants: None,
+            },
+        }
+    }
+}
@@ -0,0 +1,345 @@
+/*!
+The type `EvaluationContext` carries the context in which an expression is evaluated.
+*/
+
+use crate::xpath1::evaluate::namespaces::XML_NAMESPACE_URI;
+use crate::xpath1::evaluate::{
+    EvaluationError, FunctionRegistry, NamespaceResolver, NodeSet, VariableResolver,
+};
+use crate::xpath1::model::{Function, QName};
+use crate::xpath1::XPathObject;
+use std::collections::HashMap;
+use std::fmt::{Debug, Display, Formatter};
+use std::rc::Rc;
+use xml_dom::level2::RefNode;
+
+// ------------------------------------------------------------------------------------------------
+// Public Types
+// ------------------------------------------------------------------------------------------------
+
+///
+/// The signature of a function that may be called from an expression. The function is provided
+/// with the context of the call, and the values of the arguments, already evaluated, in order.
+///
+pub type FunctionImpl =
+    dyn Fn(&EvaluationContext, &[XPathObject]) -> Result<XPathObject, EvaluationError>;
+
+///
+/// The context in which an expression is evaluated. As described in the XPath 1.0 specification,
+/// §1 [Introduction](https://www.w3.org/TR/xpath-10/#section-Introduction), the context consists
+/// of:
+///
+/// * a node (the _context node_), in this implementation a [`NodeSet`](struct.NodeSet.html)
+/// * a pair of non-zero positive integers (the _context position_ and the _context size_)
+/// * a set of variable bindings, either bound directly or provided by a
+///   [`VariableResolver`](trait.VariableResolver.html)
+/// * a function library, a [`FunctionRegistry`](struct.FunctionRegistry.html)
+/// * the set of namespace declarations in scope for the expression, either declared directly or
+///   provided by a [`NamespaceResolver`](trait.NamespaceResolver.html)
+///
+/// A context is created from the node, or nodes, to evaluate against and then variables,
+/// functions, and namespaces are added to it.
+///
+/// # Example
+///
+/// ```rust
+/// use xml_dom_xpath::xpath1::evaluate::EvaluationContext;
+/// use xml_dom_xpath::xpath1::model::QName;
+/// use xml_dom_xpath::xpath1::XPathObject;
+///
+/// let mut context = EvaluationContext::default();
+/// context
+///     .bind_variable(
+///         QName::with("order_id"),
+///         XPathObject::String("ord-1001".to_string()),
+///     )
+///     .add_namespace("soap", "http://schemas.xmlsoap.org/soap/envelope/");
+///
+/// assert!(context.variable(&QName::with("order_id")).is_some());
+/// assert_eq!(
+///     context.namespace_uri("soap").as_deref(),
+///     Some("http://schemas.xmlsoap.org/soap/envelope/")
+/// );
+/// ```
+///
+#[derive(Clone)]
+pub struct EvaluationContext {
+    node_set: NodeSet,
+    position: usize,
+    size: usize,
+    variables: Rc<HashMap<QName, XPathObject>>,
+    variable_resolver: Option<Rc<dyn VariableResolver>>,
+    functions: Rc<FunctionRegistry>,
+    namespaces: Rc<HashMap<String, String>>,
+    namespace_resolver: Option<Rc<dyn NamespaceResolver>>,
+    node_limit: Option<usize>,
+}
+
+// ------------------------------------------------------------------------------------------------
+// Implementations
+// ------------------------------------------------------------------------------------------------
+
+impl Default for EvaluationContext {
+    fn default() -> Self {
+        Self::from(NodeSet::default())
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+
+impl Debug for EvaluationContext {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EvaluationContext")
+            .field("node_set", &self.node_set)
+            .field("position", &self.position)
+            .field("size", &self.size)
+            .field("variables", &self.variables)
+            .field("variable_resolver", &self.variable_resolver.is_some())
+            .field("functions", &self.functions)
+            .field("namespaces", &self.namespaces)
+            .field("namespace_resolver", &self.namespace_resolver.is_some())
+            .field("node_limit", &self.node_limit)
+            .finish()
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+
+impl From<NodeSet> for EvaluationContext {
+    fn from(node_set: NodeSet) -> Self {
+        Self {
+            node_set,
+            position: 1,
+            size: 1,
+            variables: Default::default(),
+            variable_resolver: None,
+            functions: Default::default(),
+            namespaces: Default::default(),
+            namespace_resolver: None,
+            node_limit: None,
+        }
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+
+impl From<RefNode> for EvaluationContext {
+    fn from(node: RefNode) -> Self {
+        Self::from(NodeSet::from(node))
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+
+impl From<&RefNode> for EvaluationContext {
+    fn from(node: &RefNode) -> Self {
+        Self::from(NodeSet::from(node))
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+
+impl EvaluationContext {
+    ///
+    /// Bind the variable `name` to `value`, replacing any existing binding. Variables bound in
+    /// this way take precedence over those provided by any variable resolver.
+    ///
+    pub fn bind_variable(&mut self, name: QName, value: XPathObject) -> &mut Self {
+        Rc::make_mut(&mut self.variables).insert(name, value);
+        self
+    }
+
+    ///
+    /// Set the `resolver` consulted for any variable not bound directly in this context,
+    /// replacing any existing resolver.
+    ///
+    pub fn set_variable_resolver<R>(&mut self, resolver: R) -> &mut Self
+    where
+        R: VariableResolver + 'static,
+    {
+        self.variable_resolver = Some(Rc::new(resolver));
+        self
+    }
+
+    ///
+    /// Replace the function library with `functions`.
+    ///
+    pub fn set_functions(&mut self, functions: FunctionRegistry) -> &mut Self {
+        self.functions = Rc::new(functions);
+        self
+    }
+
+    ///
+    /// Register `implementation` in the function library, as the function named by
+    /// `namespace_uri` and the name of `signature`; see
+    /// [`FunctionRegistry::register`](struct.FunctionRegistry.html#method.register).
+    ///
+    pub fn register_function<F>(
+        &mut self,
+        namespace_uri: Option<&str>,
+        signature: Function,
+        implementation: F,
+    ) -> &mut Self
+    where
+        F: Fn(&EvaluationContext, &[XPathObject]) -> Result<XPathObject, EvaluationError> + 'static,
+    {
+        Rc::make_mut(&mut self.functions).register(namespace_uri, signature, implementation);
+        self
+    }
+
+    ///
+    /// Declare the namespace `prefix` to be bound to `namespace_uri`. Namespaces declared in this
+    /// way take precedence over those provided by any namespace resolver.
+    ///
+    pub fn add_namespace(&mut self, prefix: &str, namespace_uri: &str) -> &mut Self {
+        Rc::make_mut(&mut self.namespaces).insert(prefix.to_string(), namespace_uri.to_string());
+        self
+    }
+
+    ///
+    /// Set the `resolver` consulted for any prefix not declared directly in this context,
+    /// replacing any existing resolver.
+    ///
+    pub fn set_namespace_resolver<R>(&mut self, resolver: R) -> &mut Self
+    where
+        R: NamespaceResolver + 'static,
+    {
+        self.namespace_resolver = Some(Rc::new(resolver));
+        self
+    }
+
+    ///
+    /// Set the maximum number of nodes that may be selected by each step of a location path, or
+    /// by a union; an expression that selects more is an
+    /// [`EvaluationError::NodeLimitExceeded`](enum.EvaluationError.html#variant.NodeLimitExceeded)
+    /// error. By default there is no limit.
+    ///
+    pub fn set_node_limit(&mut self, limit: usize) -> &mut Self {
+        self.node_limit = Some(limit);
+        self
+    }
+
+    ///
+    /// Set the context position and context size; the evaluator only sets these from the
+    /// node-set being filtered, so `position` is always in the range `1..=size`.
+    ///
+    pub(crate) fn set_position(&mut self, position: usize, size: usize) -> &mut Self {
+        assert!(position > 0 && position <= size);
+        self.position = position;
+        self.size = size;
+        self
+    }
+
+    ///
+    /// Return the context node(s).
+    ///
+    pub fn node_set(&self) -> &NodeSet {
+        &self.node_set
+    }
+
+    ///
+    /// Return the context position.
+    ///
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    ///
+    /// Return the context size.
+    ///
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    ///
+    /// Return the maximum number of nodes that may be selected by each step, if any.
+    ///
+    pub fn node_limit(&self) -> Option<usize> {
+        self.node_limit
+    }
+
+    ///
+    /// Return the value bound to the variable `name`, if any.
+    ///
+    pub fn variable(&self, name: &QName) -> Option<XPathObject> {
+        self.variables.resolve_variable(name).or_else(|| {
+            self.variable_resolver
+                .as_ref()
+                .and_then(|resolver| resolver.resolve_variable(name))
+        })
+    }
+
+    ///
+    /// Return the function library.
+    ///
+    pub fn functions(&self) -> &FunctionRegistry {
+        &self.functions
+    }
+
+    ///
+    /// Return the namespace URI declared for `prefix`, if any. The prefix `xml` is always bound
+    /// to the XML namespace, `http://www.w3.org/XML/1998/namespace`.
+    ///
+    pub fn namespace_uri(&self, prefix: &str) -> Option<String> {
+        self.namespaces
+            .resolve_namespace(prefix)
+            .or_else(|| {
+                self.namespace_resolver
+                    .as_ref()
+                    .and_then(|resolver| resolver.resolve_namespace(prefix))
+            })
+            .or_else(|| {
+                if prefix == "xml" {
+                    Some(XML_NAMESPACE_URI.to_string())
+                } else {
+                    None
+                }
+            })
+    }
+
+    ///
+    /// Create a new context, sharing variables, functions, and namespaces with this one, with
+    /// `node_set` as the context.
+    ///
+    pub(crate) fn with_node_set(&self, node_set: NodeSet) -> Self {
+        Self {
+            node_set,
+            position: 1,
+            size: 1,
+            variables: self.variables.clone(),
+            variable_resolver: self.variable_resolver.clone(),
+            functions: self.functions.clone(),
+            namespaces: self.namespaces.clone(),
+            namespace_resolver: self.namespace_resolver.clone(),
+            node_limit: self.node_limit,
+        }
+    }
+
+    ///
+    /// Return `node_set` unless it has more nodes than the limit set for this context, in which
+    /// case it was selected by `expr`.
+    ///
+    pub(crate) fn check_node_limit(
+        &self,
+        node_set: NodeSet,
+        expr: &impl Display,
+    ) -> Result<NodeSet, EvaluationError> {
+        match self.node_limit {
+            Some(limit) if node_set.len() > limit => Err(EvaluationError::NodeLimitExceeded {
+                expr: expr.to_string(),
+                limit,
+            }),
+            _ => Ok(node_set),
+        }
+    }
+
+    ///
+    /// Create a new context, sharing variables, functions, and namespaces with this one, with
+    /// `node` as the context node and the provided context position and size.
+    ///
+    pub(crate) fn with_node(&self, node: &RefNode, position: usize, size: usize) -> Self {
+        let mut context = self.with_node_set(NodeSet::from(node));
+        context.set_position(position, size);
+        context
+    }
+}
@@ -0,0 +1,216 @@
+/*!
+Conversions between numbers and strings, as defined by the `string` and `number` functions in
+§4.2 [String Functions](https://www.w3.org/TR/xpath-10/#section-String-Functions) and §4.4
+[Number Functions](https://www.w3.org/TR/xpath-10/#section-Number-Functions).
+*/
+
+// ------------------------------------------------------------------------------------------------
+// Public Functions
+// ------------------------------------------------------------------------------------------------
+
+///
+/// Convert `value` to a string, as if by a call to the `string` function.
+///
+/// * NaN is converted to the string `NaN`.
+/// * positive zero and negative zero are both converted to the string `0`.
+/// * positive and negative infinity are converted to the strings `Infinity` and `-Infinity`.
+/// * an integer is represented in decimal form with no decimal point and no leading zeros,
+///   preceded by a minus sign if it is negative.
+/// * any other number is represented in decimal form with at least one digit before, and at
+///   least one digit after, the decimal point; with as many digits as are needed to uniquely
+///   distinguish the number from all other numbers, and never in scientific notation.
+///
+/// # Example
+///
+/// ```rust
+/// use xml_dom_xpath::xpath1::evaluate::number_to_string;
+///
+/// assert_eq!(number_to_string(1.0 / 0.0), "Infinity");
+/// assert_eq!(number_to_string(-0.0), "0");
+/// assert_eq!(number_to_string(42.0), "42");
+/// assert_eq!(number_to_string(-0.5), "-0.5");
+/// assert_eq!(number_to_string(1e21), "1000000000000000000000");
+/// ```
+///
+pub fn number_to_string(value: f64) -> String {
+    if value.is_nan() {
+        "NaN".to_string()
+    } else if value.is_infinite() {
+        if value.is_sign_positive() {
+            "Infinity".to_string()
+        } else {
+            "-Infinity".to_string()
+        }
+    } else if value == 0.0 {
+        "0".to_string()
+    } else {
+        // The `Display` implementation for `f64` writes the shortest representation that
+        // round-trips, without an exponent, and without a decimal point for integers.
+        value.to_string()
+    }
+}
+
+///
+/// Convert `value` to a number, as if by a call to the `number` function. A string that consists
+/// of optional whitespace, followed by an optional minus sign, followed by a `Number`, followed by
+/// whitespace is converted to the IEEE 754 number that is nearest to the mathematical value
+/// represented by the string; any other string is converted to NaN.
+///
+/// Note that the XPath `Number` production is narrower than the syntax accepted by Rust; a leading
+/// plus sign, an exponent, and names such as `inf` or `NaN` are all converted to NaN.
+///
+/// # Example
+///
+/// ```rust
+/// use xml_dom_xpath::xpath1::evaluate::string_to_number;
+///
+/// assert_eq!(string_to_number(" 12 "), 12.0);
+/// assert_eq!(string_to_number("-.5"), -0.5);
+/// assert!(string_to_number("1e3").is_nan());
+/// assert!(string_to_number("+1").is_nan());
+/// ```
+///
+pub fn string_to_number(value: &str) -> f64 {
+    let value = value.trim_matches(is_xml_whitespace);
+    let unsigned = value.strip_prefix('-').unwrap_or(value);
+    if is_number(unsigned) {
+        value.parse::<f64>().unwrap_or(f64::NAN)
+    } else {
+        f64::NAN
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+// Private Functions
+// ------------------------------------------------------------------------------------------------
+
+//
+// Whitespace, as defined by the production `S` (3) in
+// [XML 1.0](https://www.w3.org/TR/REC-xml/#NT-S).
+//
+pub(crate) fn is_xml_whitespace(c: char) -> bool {
+    matches!(c, ' ' | '\t' | '\r' | '\n')
+}
+
+//
+// The XPath production `Number` (30), `Digits ('.' Digits?)? | '.' Digits`.
+//
+fn is_number(value: &str) -> bool {
+    let is_digits = |value: &str| value.chars().all(|c| c.is_ascii_digit());
+    match value.find('.') {
+        None => !value.is_empty() && is_digits(value),
+        Some(index) => {
+            let (integer, fraction) = (&value[..index], &value[index + 1..]);
+            (!integer.is_empty() || !fraction.is_empty())
+                && is_digits(integer)
+                && is_digits(fraction)
+        }
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+// Unit Tests
+// ------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_number_to_string_special_values() {
+        assert_eq!(number_to_string(f64::NAN), "NaN");
+        assert_eq!(number_to_string(-f64::NAN), "NaN");
+        assert_eq!(number_to_string(f64::INFINITY), "Infinity");
+        assert_eq!(number_to_string(f64::NEG_INFINITY), "-Infinity");
+        assert_eq!(number_to_string(0.0), "0");
+        assert_eq!(number_to_string(-0.0), "0");
+    }
+
+    #[test]
+    fn test_number_to_string_integers() {
+        assert_eq!(number_to_string(1.0), "1");
+        assert_eq!(number_to_string(-1.0), "-1");
+        assert_eq!(number_to_string(10.0), "10");
+        assert_eq!(number_to_string(123456789.0), "123456789");
+        assert_eq!(number_to_string(9007199254740992.0), "9007199254740992");
+        assert_eq!(number_to_string(1e21), "1000000000000000000000");
+        assert_eq!(number_to_string(-1e21), "-1000000000000000000000");
+    }
+
+    #[test]
+    fn test_number_to_string_fractions() {
+        assert_eq!(number_to_string(0.5), "0.5");
+        assert_eq!(number_to_string(-0.5), "-0.5");
+        assert_eq!(number_to_string(1.25), "1.25");
+        assert_eq!(number_to_string(0.1 + 0.2), "0.30000000000000004");
+        assert_eq!(number_to_string(1.0 / 3.0), "0.3333333333333333");
+        assert_eq!(number_to_string(1e-7), "0.0000001");
+        assert_eq!(number_to_string(-1.5e-10), "-0.00000000015");
+        assert_eq!(number_to_string(f64::MIN_POSITIVE).len(), 326);
+    }
+
+    #[test]
+    fn test_string_to_number_valid() {
+        assert_eq!(string_to_number("0"), 0.0);
+        assert_eq!(string_to_number("12"), 12.0);
+        assert_eq!(string_to_number("-12"), -12.0);
+        assert_eq!(string_to_number("12.5"), 12.5);
+        assert_eq!(string_to_number("12."), 12.0);
+        assert_eq!(string_to_number(".5"), 0.5);
+        assert_eq!(string_to_number("-.5"), -0.5);
+        assert_eq!(string_to_number("007"), 7.0);
+        assert_eq!(
+            string_to_number("1000000000000000000000"),
+            1000000000000000000000.0
+        );
+        assert_eq!(string_to_number("0.1"), 0.1);
+    }
+
+    #[test]
+    fn test_string_to_number_negative_zero() {
+        let value = string_to_number("-0");
+        assert_eq!(value, 0.0);
+        assert!(value.is_sign_negative());
+    }
+
+    #[test]
+    fn test_string_to_number_whitespace() {
+        assert_eq!(string_to_number(" 12 "), 12.0);
+        assert_eq!(string_to_number("\t\r\n12\n"), 12.0);
+        assert_eq!(string_to_number("  -1.5"), -1.5);
+        assert!(string_to_number("1 2").is_nan());
+        assert!(string_to_number("- 1").is_nan());
+        assert!(string_to_number("\u{A0}12").is_nan());
+        assert!(string_to_number("\u{2003}12").is_nan());
+    }
+
+    #[test]
+    fn test_string_to_number_invalid() {
+        assert!(string_to_number("").is_nan());
+        assert!(string_to_number(" ").is_nan());
+        assert!(string_to_number(".").is_nan());
+        assert!(string_to_number("-").is_nan());
+        assert!(string_to_number("-.").is_nan());
+        assert!(string_to_number("--1").is_nan());
+        assert!(string_to_number("+1").is_nan());
+        assert!(string_to_number("1e3").is_nan());
+        assert!(string_to_number("1E3").is_nan());
+        assert!(string_to_number("1.5e-3").is_nan());
+        assert!(string_to_number("1.2.3").is_nan());
+        assert!(string_to_number("0x10").is_nan());
+        assert!(string_to_number("1,000").is_nan());
+        assert!(string_to_number("NaN").is_nan());
+        assert!(string_to_number("inf").is_nan());
+        assert!(string_to_number("Infinity").is_nan());
+        assert!(string_to_number("-Infinity").is_nan());
+        assert!(string_to_number("١٢").is_nan());
+        assert!(string_to_number("abc").is_nan());
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for value in [1.0, -1.0, 0.5, 44.95, 1.0 / 3.0, 1e-7, 1e21, 123456.789] {
+            assert_eq!(string_to_number(&number_to_string(value)), value);
+        }
+    }
+}
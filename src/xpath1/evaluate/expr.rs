@@ -0,0 +1,320 @@
+/*!
+The evaluation of expressions, modeled as a tree of `Predicate` values, within an
+`EvaluationContext`.
+*/
+
+use crate::xpath1::evaluate::convert::string_to_number;
+use crate::xpath1::evaluate::filters::{Filter, PredicateFilter};
+use crate::xpath1::evaluate::functions::evaluate_function;
+use crate::xpath1::evaluate::{evaluate_path, EvaluationContext, EvaluationError, NodeSet};
+use crate::xpath1::model::{
+    DataType, ExprNode, FilterExpr, LocationPath, Predicate, Step, Terminal,
+};
+use crate::xpath1::XPathObject;
+use std::cmp::Ordering;
+use xml_dom::level2::convert::as_attribute;
+use xml_dom::level2::{Node, NodeType, RefNode};
+
+// ------------------------------------------------------------------------------------------------
+// Public Functions
+// ------------------------------------------------------------------------------------------------
+
+///
+/// Evaluate the expression `predicate` within `context`.
+///
+pub(crate) fn evaluate_predicate(
+    context: &EvaluationContext,
+    predicate: &Predicate,
+) -> Result<XPathObject, EvaluationError> {
+    match predicate {
+        Predicate::Expr(ExprNode::Union { left, right, .. }) => {
+            let left = evaluate_node_set(context, left)?;
+            let right = evaluate_node_set(context, right)?;
+            let mut union: NodeSet = left.iter().chain(right.iter()).cloned().collect();
+            union.sort_document_order();
+            Ok(XPathObject::NodeSet(
+                context.check_node_limit(union, predicate)?,
+            ))
+        }
+        Predicate::Expr(ExprNode::And { left, right, .. }) => Ok(XPathObject::Boolean(
+            // The right operand is not evaluated if the left operand evaluates to false.
+            evaluate_predicate(context, left)?.to_boolean()
+                && evaluate_predicate(context, right)?.to_boolean(),
+        )),
+        Predicate::Expr(ExprNode::Or { left, right, .. }) => Ok(XPathObject::Boolean(
+            // The right operand is not evaluated if the left operand evaluates to true.
+            evaluate_predicate(context, left)?.to_boolean()
+                || evaluate_predicate(context, right)?.to_boolean(),
+        )),
+        Predicate::Expr(ExprNode::Equals { left, right, .. }) => {
+            evaluate_comparison(context, Comparison::Equals, left, right)
+        }
+        Predicate::Expr(ExprNode::NotEquals { left, right, .. }) => {
+            evaluate_comparison(context, Comparison::NotEquals, left, right)
+        }
+        Predicate::Expr(ExprNode::LessThan { left, right, .. }) => {
+            evaluate_comparison(context, Comparison::LessThan, left, right)
+        }
+        Predicate::Expr(ExprNode::LessThanOrEqual { left, right, .. }) => {
+            evaluate_comparison(context, Comparison::LessThanOrEqual, left, right)
+        }
+        Predicate::Expr(ExprNode::GreaterThan { left, right, .. }) => {
+            evaluate_comparison(context, Comparison::GreaterThan, left, right)
+        }
+        Predicate::Expr(ExprNode::GreaterThanOrEqual { left, right, .. }) => {
+            evaluate_comparison(context, Comparison::GreaterThanOrEqual, left, right)
+        }
+        Predicate::Expr(ExprNode::Add { left, right, .. }) => {
+            evaluate_arithmetic(context, left, right, |left, right| left + right)
+        }
+        Predicate::Expr(ExprNode::Subtract { left, right, .. }) => {
+            evaluate_arithmetic(context, left, right, |left, right| left - right)
+        }
+        Predicate::Expr(ExprNode::Multiply { left, right, .. }) => {
+            evaluate_arithmetic(context, left, right, |left, right| left * right)
+        }
+        Predicate::Expr(ExprNode::Divide { left, right, .. })
+        | Predicate::Expr(ExprNode::FPDiv { left, right, .. }) => {
+            evaluate_arithmetic(context, left, right, |left, right| left / right)
+        }
+        Predicate::Expr(ExprNode::Modulus { left, right, .. }) => {
+            // The remainder of a truncating division, as the `%` operator in Java and ECMAScript;
+            // the result has the same sign as the dividend.
+            evaluate_arithmetic(context, left, right, |left, right| left % right)
+        }
+        Predicate::Expr(ExprNode::UnaryMinus { value, .. }) => Ok(XPathObject::Number(
+            -evaluate_predicate(context, value)?.to_number(),
+        )),
+        Predicate::Function(function) => evaluate_function(context, function),
+        Predicate::Terminal(terminal) => evaluate_terminal(context, terminal),
+        Predicate::Filter(filter) => evaluate_filter(context, filter),
+    }
+}
+
+///
+/// Return the string-value of `node`, as defined for each node type in §5
+/// [Data Model](https://www.w3.org/TR/xpath-10/#data-model).
+///
+pub(crate) fn string_value(node: &RefNode) -> String {
+    match node.node_type() {
+        NodeType::Document | NodeType::Element => {
+            fn descendant_text(node: &RefNode, value: &mut String) {
+                for child in node.child_nodes() {
+                    match child.node_type() {
+                        NodeType::Text | NodeType::CData => {
+                            value.push_str(&child.node_value().unwrap_or_default())
+                        }
+                        NodeType::Element => descendant_text(&child, value),
+                        _ => {}
+                    }
+                }
+            }
+            let mut value = String::new();
+            descendant_text(node, &mut value);
+            value
+        }
+        NodeType::Attribute => as_attribute(node)
+            .ok()
+            .and_then(|attribute| attribute.value())
+            .unwrap_or_default(),
+        _ => node.node_value().unwrap_or_default(),
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+// Private Types
+// ------------------------------------------------------------------------------------------------
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Comparison {
+    Equals,
+    NotEquals,
+    LessThan,
+    LessThanOrEqual,
+    GreaterThan,
+    GreaterThanOrEqual,
+}
+
+// ------------------------------------------------------------------------------------------------
+// Implementations
+// ------------------------------------------------------------------------------------------------
+
+impl Comparison {
+    fn is_equality(&self) -> bool {
+        matches!(self, Comparison::Equals | Comparison::NotEquals)
+    }
+
+    //
+    // The comparison to use when the operands are swapped, so that `a < b` is `b > a`.
+    //
+    fn swapped(&self) -> Self {
+        match self {
+            Comparison::LessThan => Comparison::GreaterThan,
+            Comparison::LessThanOrEqual => Comparison::GreaterThanOrEqual,
+            Comparison::GreaterThan => Comparison::LessThan,
+            Comparison::GreaterThanOrEqual => Comparison::LessThanOrEqual,
+            other => *other,
+        }
+    }
+
+    //
+    // Test the ordering of two values, where `None` means the values are unordered, as is any
+    // comparison with NaN; unordered values are only ever not equal.
+    //
+    fn test(&self, ordering: Option<Ordering>) -> bool {
+        match self {
+            Comparison::Equals => ordering == Some(Ordering::Equal),
+            Comparison::NotEquals => ordering != Some(Ordering::Equal),
+            Comparison::LessThan => ordering == Some(Ordering::Less),
+            Comparison::LessThanOrEqual => {
+                matches!(ordering, Some(Ordering::Less) | Some(Ordering::Equal))
+            }
+            Comparison::GreaterThan => ordering == Some(Ordering::Greater),
+            Comparison::GreaterThanOrEqual => {
+                matches!(ordering, Some(Ordering::Greater) | Some(Ordering::Equal))
+            }
+        }
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+// Private Functions
+// ------------------------------------------------------------------------------------------------
+
+//
+// Both operands are converted to numbers, as if by a call to the `number` function, and the
+// operation performed according to IEEE 754; so that, for example, division by zero results in
+// an infinity or NaN rather than an error.
+//
+fn evaluate_arithmetic<F>(
+    context: &EvaluationContext,
+    left: &Predicate,
+    right: &Predicate,
+    operation: F,
+) -> Result<XPathObject, EvaluationError>
+where
+    F: Fn(f64, f64) -> f64,
+{
+    let left = evaluate_predicate(context, left)?.to_number();
+    let right = evaluate_predicate(context, right)?.to_number();
+    Ok(XPathObject::Number(operation(left, right)))
+}
+
+fn evaluate_comparison(
+    context: &EvaluationContext,
+    comparison: Comparison,
+    left: &Predicate,
+    right: &Predicate,
+) -> Result<XPathObject, EvaluationError> {
+    let left = evaluate_predicate(context, left)?;
+    let right = evaluate_predicate(context, right)?;
+    Ok(XPathObject::Boolean(compare(comparison, &left, &right)))
+}
+
+//
+// Compare two objects according to §3.4 [Booleans](https://www.w3.org/TR/xpath-10/#booleans). A
+// comparison involving a node-set is true if it is true for the string-value of any node in the
+// node-set, converted to a number where compared with a number, except that a node-set compared
+// with a boolean is first converted to a boolean. Comparisons of node-sets are therefore not
+// transitive, and `$a = $b` and `$a != $b` may both be true.
+//
+fn compare(comparison: Comparison, left: &XPathObject, right: &XPathObject) -> bool {
+    match (left, right) {
+        (XPathObject::NodeSet(left), XPathObject::NodeSet(right)) => {
+            let right: Vec<XPathObject> = right
+                .iter()
+                .map(|node| XPathObject::String(string_value(node)))
+                .collect();
+            left.iter().any(|node| {
+                let left = XPathObject::String(string_value(node));
+                right.iter().any(|right| compare(comparison, &left, right))
+            })
+        }
+        (XPathObject::NodeSet(left), XPathObject::Boolean(_)) => {
+            compare(comparison, &XPathObject::Boolean(!left.is_empty()), right)
+        }
+        (XPathObject::NodeSet(left), XPathObject::Number(_)) => left.iter().any(|node| {
+            let left = XPathObject::Number(string_to_number(&string_value(node)));
+            compare(comparison, &left, right)
+        }),
+        (XPathObject::NodeSet(left), XPathObject::String(_)) => left.iter().any(|node| {
+            let left = XPathObject::String(string_value(node));
+            compare(comparison, &left, right)
+        }),
+        (_, XPathObject::NodeSet(_)) => compare(comparison.swapped(), right, left),
+        _ => comparison.test(compare_values(comparison, left, right)),
+    }
+}
+
+//
+// Neither object is a node-set. The operators `=` and `!=` compare booleans if either object is a
+// boolean, otherwise numbers if either is a number, otherwise strings; the operators `<`, `<=`,
+// `>`, and `>=` always compare numbers.
+//
+fn compare_values(
+    comparison: Comparison,
+    left: &XPathObject,
+    right: &XPathObject,
+) -> Option<Ordering> {
+    let is_boolean = |value: &XPathObject| matches!(value, XPathObject::Boolean(_));
+    let is_number = |value: &XPathObject| matches!(value, XPathObject::Number(_));
+    if !comparison.is_equality() {
+        left.to_number().partial_cmp(&right.to_number())
+    } else if is_boolean(left) || is_boolean(right) {
+        Some(left.to_boolean().cmp(&right.to_boolean()))
+    } else if is_number(left) || is_number(right) {
+        left.to_number().partial_cmp(&right.to_number())
+    } else {
+        Some(left.to_string_value().cmp(&right.to_string_value()))
+    }
+}
+
+fn evaluate_terminal(
+    context: &EvaluationContext,
+    terminal: &Terminal,
+) -> Result<XPathObject, EvaluationError> {
+    match terminal {
+        Terminal::Variable(name, _) => context
+            .variable(name)
+            .ok_or_else(|| EvaluationError::UnboundVariable(name.to_string())),
+        Terminal::Literal(value, _) => Ok(XPathObject::String(value.clone())),
+        Terminal::Number(value, _) => Ok(XPathObject::Number(*value)),
+        Terminal::Select(select) => {
+            evaluate_path(context, &LocationPath::with(Step::with(select.clone())))
+        }
+        Terminal::Path(path) => evaluate_path(context, path),
+    }
+}
+
+fn evaluate_filter(
+    context: &EvaluationContext,
+    filter: &FilterExpr,
+) -> Result<XPathObject, EvaluationError> {
+    if filter.predicate_exprs().next().is_none() && filter.path().is_none() {
+        return evaluate_predicate(context, filter.primary_expr());
+    }
+    // A predicate in a filter expression filters the node-set with respect to the child axis,
+    // and so proximity positions are in document order.
+    let mut primary = evaluate_node_set(context, filter.primary_expr())?;
+    for predicate in filter.predicate_exprs() {
+        primary = PredicateFilter::new(predicate.clone()).apply(context, &primary)?;
+    }
+    match filter.path() {
+        None => Ok(XPathObject::NodeSet(primary)),
+        Some(path) => evaluate_path(&context.with_node_set(primary), path),
+    }
+}
+
+fn evaluate_node_set(
+    context: &EvaluationContext,
+    predicate: &Predicate,
+) -> Result<NodeSet, EvaluationError> {
+    match evaluate_predicate(context, predicate)? {
+        XPathObject::NodeSet(node_set) => Ok(node_set),
+        value => Err(EvaluationError::type_error(
+            predicate,
+            DataType::NodeSet,
+            &value,
+        )),
+    }
+}
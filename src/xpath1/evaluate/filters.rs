@@ -1,18 +1,30 @@
 /*!
-
-
+The filters applied to the nodes selected by each step's axis.
 */
 
 // ------------------------------------------------------------------------------------------------
 // Public Types
 // ------------------------------------------------------------------------------------------------
 
+use crate::xpath1::evaluate::expr::evaluate_predicate;
+use crate::xpath1::evaluate::namespaces::expanded_name;
+use crate::xpath1::evaluate::{EvaluationContext, EvaluationError, NodeSet};
 use crate::xpath1::model::{NodeTest, Predicate};
-use std::str::FromStr;
-use xml_dom::level2::{Name, Node, NodeType, ProcessingInstruction, RefNode};
+use crate::xpath1::XPathObject;
+use xml_dom::level2::{Node, NodeType, ProcessingInstruction, RefNode};
 
+//
+// A filter is applied to the nodes selected by an axis, in order, and returns the subset of those
+// nodes that pass the filter. The order of nodes in `node_set` is significant as it determines the
+// proximity position of each node. The `context` provides the variables, functions, and namespaces
+// in scope for the filter.
+//
 pub trait Filter {
-    fn apply(&self, to: &RefNode) -> bool;
+    fn apply(
+        &self,
+        context: &EvaluationContext,
+        node_set: &NodeSet,
+    ) -> Result<NodeSet, EvaluationError>;
 }
 
 //
@@ -28,7 +40,14 @@ pub struct NodeTestFilter {
     node_test: NodeTest,
 }
 
-#[allow(dead_code)]
+//
+// A predicate filters a node-set with respect to an axis to produce a new node-set. For each node
+// in the node-set to be filtered, the predicate is evaluated with that node as the context node,
+// with the number of nodes in the node-set as the context size, and with the proximity position of
+// the node in the node-set with respect to the axis as the context position; if the predicate
+// evaluates to true for that node, the node is included in the new node-set; otherwise, it is not
+// included.
+//
 pub struct PredicateFilter {
     predicate: Predicate,
 }
@@ -38,13 +57,69 @@ pub struct PredicateFilter {
 // ------------------------------------------------------------------------------------------------
 
 impl Filter for NodeTestFilter {
-    fn apply(&self, node: &RefNode) -> bool {
+    fn apply(
+        &self,
+        context: &EvaluationContext,
+        node_set: &NodeSet,
+    ) -> Result<NodeSet, EvaluationError> {
+        let name_test = self.name_test(context)?;
+        Ok(node_set
+            .iter()
+            .filter(|node| self.matches(node, name_test.as_ref()))
+            .cloned()
+            .collect())
+    }
+}
+
+impl NodeTestFilter {
+    pub fn new(principal_type: NodeType, node_test: NodeTest) -> Self {
+        Self {
+            principal_type,
+            node_test,
+        }
+    }
+
+    //
+    // A QName in the node test is expanded into an expanded-name using the namespace declarations
+    // from the expression context, not those of the document. A node test `prefix:*` is true for
+    // any node of the principal node type whose expanded-name has the namespace URI to which the
+    // prefix expands, regardless of the local part of the name.
+    //
+    pub(crate) fn name_test(
+        &self,
+        context: &EvaluationContext,
+    ) -> Result<Option<NameTest>, EvaluationError> {
+        let (prefix, local_name) = match &self.node_test {
+            NodeTest::Named(name) => (name.prefix(), Some(name.local_name().to_string())),
+            NodeTest::NamespaceWildcard(prefix) => (Some(prefix.as_str()), None),
+            _ => return Ok(None),
+        };
+        let namespace_uri = match prefix {
+            None => None,
+            Some(prefix) => Some(
+                context
+                    .namespace_uri(prefix)
+                    .ok_or_else(|| EvaluationError::unknown_prefix(prefix, &self.node_test))?,
+            ),
+        };
+        Ok(Some(NameTest {
+            namespace_uri,
+            local_name,
+        }))
+    }
+
+    pub(crate) fn matches(&self, node: &RefNode, name_test: Option<&NameTest>) -> bool {
         match &self.node_test {
             NodeTest::All => node.node_type() == self.principal_type,
-            NodeTest::Named(name) => {
+            NodeTest::Named(_) | NodeTest::NamespaceWildcard(_) => {
                 if node.node_type() == self.principal_type {
-                    let name = Name::from_str(&name).unwrap();
-                    node.node_name() == name
+                    let name_test = name_test.unwrap();
+                    let (namespace_uri, local_name) = expanded_name(node);
+                    namespace_uri == name_test.namespace_uri
+                        && match &name_test.local_name {
+                            None => true,
+                            Some(name) => name == &local_name,
+                        }
                 } else {
                     false
                 }
@@ -63,18 +138,20 @@ impl Filter for NodeTestFilter {
     }
 }
 
-impl NodeTestFilter {
-    pub fn new(principal_type: NodeType, node_test: NodeTest) -> Self {
-        Self {
-            principal_type,
-            node_test,
-        }
-    }
-}
-
 impl Filter for PredicateFilter {
-    fn apply(&self, _to: &RefNode) -> bool {
-        unimplemented!()
+    fn apply(
+        &self,
+        context: &EvaluationContext,
+        node_set: &NodeSet,
+    ) -> Result<NodeSet, EvaluationError> {
+        let size = node_set.len();
+        let mut result: Vec<RefNode> = Vec::new();
+        for (index, node) in node_set.iter().enumerate() {
+            if self.includes(context, node, index + 1, size)? {
+                result.push(node.clone());
+            }
+        }
+        Ok(result.into_iter().collect())
     }
 }
 
@@ -82,12 +159,39 @@ impl PredicateFilter {
     pub fn new(predicate: Predicate) -> Self {
         Self { predicate }
     }
+
+    //
+    // If the result is a number, the result will be converted to true if the number is equal to
+    // the context position and will be converted to false otherwise; if the result is not a
+    // number, then the result will be converted as if by a call to the boolean function.
+    //
+    pub(crate) fn includes(
+        &self,
+        context: &EvaluationContext,
+        node: &RefNode,
+        position: usize,
+        size: usize,
+    ) -> Result<bool, EvaluationError> {
+        let node_context = context.with_node(node, position, size);
+        Ok(match evaluate_predicate(&node_context, &self.predicate)? {
+            XPathObject::Number(number) => number == position as f64,
+            value => value.to_boolean(),
+        })
+    }
 }
 
 // ------------------------------------------------------------------------------------------------
 // Private Types
 // ------------------------------------------------------------------------------------------------
 
+//
+// The expanded form of a name test, where a `local_name` of `None` matches any local part.
+//
+pub(crate) struct NameTest {
+    namespace_uri: Option<String>,
+    local_name: Option<String>,
+}
+
 // ------------------------------------------------------------------------------------------------
 // Private Functions
 // ------------------------------------------------------------------------------------------------
@@ -0,0 +1,866 @@
+/*!
+The evaluation of function calls, and the implementation of the XPath 1.0 core function library,
+described in §4 [Core Function Library](https://www.w3.org/TR/xpath-10/#corelib).
+
+Each function is called with arguments that have already been converted to the types declared in
+the function's signature, as described by the `Function` and `Argument` metadata in the model.
+*/
+
+use crate::xpath1::evaluate::convert::is_xml_whitespace;
+use crate::xpath1::evaluate::expr::{evaluate_predicate, string_value};
+use crate::xpath1::evaluate::node_set::{is_namespace_node, parent_of};
+use crate::xpath1::evaluate::{
+    string_to_number, EvaluationContext, EvaluationError, FunctionRegistry, NodeSet,
+};
+use crate::xpath1::model::{required_functions, DataType, FunctionCall};
+use crate::xpath1::XPathObject;
+use std::iter::FromIterator;
+use xml_dom::level2::{Document, Node, NodeType, ProcessingInstruction, RefNode};
+
+// ------------------------------------------------------------------------------------------------
+// Public Functions
+// ------------------------------------------------------------------------------------------------
+
+///
+/// Evaluate a function call; the function is found in the context's function registry by the
+/// expanded name of the call, using the context's namespace declarations to resolve any prefix.
+/// The number of arguments is checked and each argument converted to the type required by the
+/// function's signature. Optional arguments that are omitted, and which are defined to default to
+/// the context node, are provided.
+///
+pub(crate) fn evaluate_function(
+    context: &EvaluationContext,
+    call: &FunctionCall,
+) -> Result<XPathObject, EvaluationError> {
+    let name = call.name();
+    let namespace_uri = match name.prefix() {
+        None => None,
+        Some(prefix) => Some(
+            context
+                .namespace_uri(prefix)
+                .ok_or_else(|| EvaluationError::unknown_prefix(prefix, call))?,
+        ),
+    };
+    let namespace_uri = namespace_uri.as_deref();
+    let functions = context.functions();
+    let (function, function_impl) = match (
+        functions.signature(namespace_uri, name.local_name()),
+        functions.implementation(namespace_uri, name.local_name()),
+    ) {
+        (Some(function), Some(function_impl)) => (function, function_impl),
+        _ => return Err(EvaluationError::UnknownFunction(call.to_string())),
+    };
+    let arguments = call.arguments().collect::<Vec<_>>();
+    if !function.accepts_arguments(arguments.len()) {
+        return Err(EvaluationError::WrongArity(call.to_string()));
+    }
+    let signature = function.arguments().collect::<Vec<_>>();
+
+    let mut values: Vec<XPathObject> = Vec::new();
+    for (index, argument) in arguments.iter().enumerate() {
+        // For a variadic function any additional arguments take the type of the last.
+        let data_type = signature[index.min(signature.len() - 1)].data_type();
+        let value = evaluate_predicate(context, argument)?;
+        values.push(convert_argument(value, data_type, &argument.to_string())?);
+    }
+    for omitted in signature.iter().skip(arguments.len()) {
+        if !omitted.defaults_to_context_node() {
+            break;
+        }
+        values.push(convert_argument(
+            XPathObject::NodeSet(context.node_set().clone()),
+            omitted.data_type(),
+            &call.to_string(),
+        )?)
+    }
+
+    // An extension function may return any object, but only the declared type is expected.
+    let result = function_impl(context, &values)?;
+    match function.result_type() {
+        DataType::Object => Ok(result),
+        result_type if result.data_type() == *result_type => Ok(result),
+        result_type => Err(EvaluationError::type_error(
+            call,
+            result_type.clone(),
+            &result,
+        )),
+    }
+}
+
+///
+/// Register all the functions of the core function library, which have no namespace URI.
+///
+pub(crate) fn register_core_functions(registry: &mut FunctionRegistry) {
+    for function in required_functions() {
+        let function_impl = core_function(function.name()).unwrap();
+        registry.register(None, function, function_impl);
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+// Private Types
+// ------------------------------------------------------------------------------------------------
+
+type CoreFunction = fn(&EvaluationContext, &[XPathObject]) -> Result<XPathObject, EvaluationError>;
+
+// ------------------------------------------------------------------------------------------------
+// Private Functions
+// ------------------------------------------------------------------------------------------------
+
+fn core_function(name: &str) -> Option<CoreFunction> {
+    Some(match name {
+        // 4.1 Node Set Functions
+        "last" => last,
+        "position" => position,
+        "count" => count,
+        "id" => id,
+        "local-name" => local_name,
+        "namespace-uri" => namespace_uri,
+        "name" => name_fn,
+        // 4.2 String Functions
+        "string" => string,
+        "concat" => concat,
+        "starts-with" => starts_with,
+        "contains" => contains,
+        "substring-before" => substring_before,
+        "substring-after" => substring_after,
+        "substring" => substring,
+        "string-length" => string_length,
+        "normalize-space" => normalize_space,
+        "translate" => translate,
+        // 4.3 Boolean Functions
+        "boolean" => boolean,
+        "not" => not,
+        "true" => true_fn,
+        "false" => false_fn,
+        "lang" => lang,
+        // 4.4 Number Functions
+        "number" => number,
+        "sum" => sum,
+        "floor" => floor,
+        "ceiling" => ceiling,
+        "round" => round,
+        _ => return None,
+    })
+}
+
+fn convert_argument(
+    value: XPathObject,
+    data_type: &DataType,
+    fragment: &str,
+) -> Result<XPathObject, EvaluationError> {
+    Ok(match data_type {
+        DataType::Bool => XPathObject::Boolean(value.to_boolean()),
+        DataType::Number => XPathObject::Number(value.to_number()),
+        DataType::String => XPathObject::String(value.to_string_value()),
+        DataType::Object => value,
+        DataType::NodeSet => match value {
+            XPathObject::NodeSet(_) => value,
+            _ => {
+                return Err(EvaluationError::type_error(
+                    &fragment,
+                    DataType::NodeSet,
+                    &value,
+                ))
+            }
+        },
+    })
+}
+
+// ------------------------------------------------------------------------------------------------
+
+fn string_arg(arguments: &[XPathObject], index: usize) -> &str {
+    match &arguments[index] {
+        XPathObject::String(value) => value,
+        _ => unreachable!(),
+    }
+}
+
+fn number_arg(arguments: &[XPathObject], index: usize) -> f64 {
+    match &arguments[index] {
+        XPathObject::Number(value) => *value,
+        _ => unreachable!(),
+    }
+}
+
+fn boolean_arg(arguments: &[XPathObject], index: usize) -> bool {
+    match &arguments[index] {
+        XPathObject::Boolean(value) => *value,
+        _ => unreachable!(),
+    }
+}
+
+fn node_set_arg(arguments: &[XPathObject], index: usize) -> &NodeSet {
+    match &arguments[index] {
+        XPathObject::NodeSet(value) => value,
+        _ => unreachable!(),
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+// 4.1 Node Set Functions
+// ------------------------------------------------------------------------------------------------
+
+fn last(context: &EvaluationContext, _: &[XPathObject]) -> Result<XPathObject, EvaluationError> {
+    Ok(XPathObject::Number(context.size() as f64))
+}
+
+fn position(
+    context: &EvaluationContext,
+    _: &[XPathObject],
+) -> Result<XPathObject, EvaluationError> {
+    Ok(XPathObject::Number(context.position() as f64))
+}
+
+fn count(_: &EvaluationContext, arguments: &[XPathObject]) -> Result<XPathObject, EvaluationError> {
+    Ok(XPathObject::Number(node_set_arg(arguments, 0).len() as f64))
+}
+
+//
+// When the argument to id is of type node-set, then the result is the union of the result of
+// applying id to the string-value of each of the nodes in the argument node-set. When the
+// argument to id is of any other type, the argument is converted to a string as if by a call to
+// the string function; the string is split into a whitespace-separated list of tokens; the result
+// is a node-set containing the elements in the same document as the context node that have a
+// unique ID equal to any of the tokens in the list.
+//
+fn id(
+    context: &EvaluationContext,
+    arguments: &[XPathObject],
+) -> Result<XPathObject, EvaluationError> {
+    let values = match &arguments[0] {
+        XPathObject::NodeSet(node_set) => node_set.iter().map(string_value).collect(),
+        value => vec![value.to_string_value()],
+    };
+    let mut ids: Vec<&str> = Vec::new();
+    for token in values
+        .iter()
+        .flat_map(|value| value.split(is_xml_whitespace))
+    {
+        if !token.is_empty() && !ids.contains(&token) {
+            ids.push(token);
+        }
+    }
+    let document = match context.node_set().iter().next() {
+        Some(node) if node.node_type() == NodeType::Document => Some(node.clone()),
+        Some(node) => node.owner_document(),
+        None => None,
+    };
+    Ok(XPathObject::NodeSet(match document {
+        None => NodeSet::default(),
+        Some(document) => {
+            NodeSet::from_iter(ids.iter().filter_map(|id| document.get_element_by_id(id)))
+        }
+    }))
+}
+
+fn local_name(
+    _: &EvaluationContext,
+    arguments: &[XPathObject],
+) -> Result<XPathObject, EvaluationError> {
+    Ok(XPathObject::String(
+        match node_set_arg(arguments, 0).iter().next() {
+            None => String::new(),
+            Some(node) if is_namespace_node(node) => namespace_prefix(node),
+            Some(node) => match node.node_type() {
+                NodeType::Element | NodeType::Attribute => node.node_name().local_name().clone(),
+                NodeType::ProcessingInstruction => node.target(),
+                _ => String::new(),
+            },
+        },
+    ))
+}
+
+fn namespace_uri(
+    _: &EvaluationContext,
+    arguments: &[XPathObject],
+) -> Result<XPathObject, EvaluationError> {
+    Ok(XPathObject::String(
+        match node_set_arg(arguments, 0).iter().next() {
+            Some(node)
+                if !is_namespace_node(node)
+                    && matches!(node.node_type(), NodeType::Element | NodeType::Attribute) =>
+            {
+                node.node_name().namespace_uri().clone().unwrap_or_default()
+            }
+            _ => String::new(),
+        },
+    ))
+}
+
+fn name_fn(
+    _: &EvaluationContext,
+    arguments: &[XPathObject],
+) -> Result<XPathObject, EvaluationError> {
+    Ok(XPathObject::String(
+        match node_set_arg(arguments, 0).iter().next() {
+            None => String::new(),
+            Some(node) if is_namespace_node(node) => namespace_prefix(node),
+            Some(node) => match node.node_type() {
+                NodeType::Element | NodeType::Attribute => node.node_name().to_string(),
+                NodeType::ProcessingInstruction => node.target(),
+                _ => String::new(),
+            },
+        },
+    ))
+}
+
+//
+// The name of a namespace node is the prefix it declares, or empty for the default namespace.
+//
+fn namespace_prefix(node: &RefNode) -> String {
+    let name = node.node_name();
+    match name.prefix() {
+        Some(_) => name.local_name().clone(),
+        None => String::new(),
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+// 4.2 String Functions
+// ------------------------------------------------------------------------------------------------
+
+fn string(
+    _: &EvaluationContext,
+    arguments: &[XPathObject],
+) -> Result<XPathObject, EvaluationError> {
+    Ok(XPathObject::String(arguments[0].to_string_value()))
+}
+
+fn concat(
+    _: &EvaluationContext,
+    arguments: &[XPathObject],
+) -> Result<XPathObject, EvaluationError> {
+    Ok(XPathObject::String(
+        (0..arguments.len())
+            .map(|index| string_arg(arguments, index))
+            .collect(),
+    ))
+}
+
+fn starts_with(
+    _: &EvaluationContext,
+    arguments: &[XPathObject],
+) -> Result<XPathObject, EvaluationError> {
+    Ok(XPathObject::Boolean(
+        string_arg(arguments, 0).starts_with(string_arg(arguments, 1)),
+    ))
+}
+
+fn contains(
+    _: &EvaluationContext,
+    arguments: &[XPathObject],
+) -> Result<XPathObject, EvaluationError> {
+    Ok(XPathObject::Boolean(
+        string_arg(arguments, 0).contains(string_arg(arguments, 1)),
+    ))
+}
+
+fn substring_before(
+    _: &EvaluationContext,
+    arguments: &[XPathObject],
+) -> Result<XPathObject, EvaluationError> {
+    let value = string_arg(arguments, 0);
+    Ok(XPathObject::String(
+        match value.find(string_arg(arguments, 1)) {
+            None => String::new(),
+            Some(index) => value[..index].to_string(),
+        },
+    ))
+}
+
+fn substring_after(
+    _: &EvaluationContext,
+    arguments: &[XPathObject],
+) -> Result<XPathObject, EvaluationError> {
+    let value = string_arg(arguments, 0);
+    let split_at = string_arg(arguments, 1);
+    Ok(XPathObject::String(match value.find(split_at) {
+        None => String::new(),
+        Some(index) => value[index + split_at.len()..].to_string(),
+    }))
+}
+
+//
+// The substring function returns the substring of the first argument starting at the position
+// specified in the second argument with length specified in the third argument. More precisely,
+// each character in the string is considered to have a numeric position: the position of the
+// first character is 1, the position of the second character is 2 and so on. The returned
+// substring contains those characters for which the position of the character is greater than or
+// equal to the rounded value of the second argument and, if the third argument is specified, less
+// than the sum of the rounded value of the second argument and the rounded value of the third
+// argument. The comparisons and addition follow the standard IEEE 754 rules, and so NaN or
+// infinite arguments are handled without special cases.
+//
+fn substring(
+    _: &EvaluationContext,
+    arguments: &[XPathObject],
+) -> Result<XPathObject, EvaluationError> {
+    let start = round_half_up(number_arg(arguments, 1));
+    let end = if arguments.len() > 2 {
+        start + round_half_up(number_arg(arguments, 2))
+    } else {
+        f64::INFINITY
+    };
+    Ok(XPathObject::String(
+        string_arg(arguments, 0)
+            .chars()
+            .enumerate()
+            .filter(|(index, _)| {
+                let position = (index + 1) as f64;
+                position >= start && position < end
+            })
+            .map(|(_, c)| c)
+            .collect(),
+    ))
+}
+
+fn string_length(
+    _: &EvaluationContext,
+    arguments: &[XPathObject],
+) -> Result<XPathObject, EvaluationError> {
+    Ok(XPathObject::Number(
+        string_arg(arguments, 0).chars().count() as f64,
+    ))
+}
+
+fn normalize_space(
+    _: &EvaluationContext,
+    arguments: &[XPathObject],
+) -> Result<XPathObject, EvaluationError> {
+    Ok(XPathObject::String(
+        string_arg(arguments, 0)
+            .split(is_xml_whitespace)
+            .filter(|token| !token.is_empty())
+            .collect::<Vec<&str>>()
+            .join(" "),
+    ))
+}
+
+//
+// The translate function returns the first argument string with occurrences of characters in the
+// second argument string replaced by the character at the corresponding position in the third
+// argument string. If there is a character in the second argument string with no character at a
+// corresponding position in the third argument string, then that character is removed. If a
+// character occurs more than once in the second argument string, then the first occurrence
+// determines the replacement character.
+//
+fn translate(
+    _: &EvaluationContext,
+    arguments: &[XPathObject],
+) -> Result<XPathObject, EvaluationError> {
+    let from = string_arg(arguments, 1).chars().collect::<Vec<char>>();
+    let to = string_arg(arguments, 2).chars().collect::<Vec<char>>();
+    Ok(XPathObject::String(
+        string_arg(arguments, 0)
+            .chars()
+            .filter_map(|c| match from.iter().position(|f| *f == c) {
+                None => Some(c),
+                Some(index) => to.get(index).cloned(),
+            })
+            .collect(),
+    ))
+}
+
+// ------------------------------------------------------------------------------------------------
+// 4.3 Boolean Functions
+// ------------------------------------------------------------------------------------------------
+
+fn boolean(
+    _: &EvaluationContext,
+    arguments: &[XPathObject],
+) -> Result<XPathObject, EvaluationError> {
+    Ok(XPathObject::Boolean(arguments[0].to_boolean()))
+}
+
+fn not(_: &EvaluationContext, arguments: &[XPathObject]) -> Result<XPathObject, EvaluationError> {
+    Ok(XPathObject::Boolean(!boolean_arg(arguments, 0)))
+}
+
+fn true_fn(_: &EvaluationContext, _: &[XPathObject]) -> Result<XPathObject, EvaluationError> {
+    Ok(XPathObject::Boolean(true))
+}
+
+fn false_fn(_: &EvaluationContext, _: &[XPathObject]) -> Result<XPathObject, EvaluationError> {
+    Ok(XPathObject::Boolean(false))
+}
+
+//
+// The lang function returns true or false depending on whether the language of the context node
+// as specified by xml:lang attributes is the same as or is a sublanguage of the language specified
+// by the argument string. The language of the context node is determined by the value of the
+// xml:lang attribute on the context node, or, if the context node has no xml:lang attribute, by
+// the value of the xml:lang attribute on the nearest ancestor of the context node that has an
+// xml:lang attribute.
+//
+fn lang(
+    context: &EvaluationContext,
+    arguments: &[XPathObject],
+) -> Result<XPathObject, EvaluationError> {
+    let language = string_arg(arguments, 0).to_lowercase();
+    let mut next = context.node_set().iter().next().cloned();
+    while let Some(node) = next {
+        if node.node_type() == NodeType::Element {
+            let xml_lang = node.attributes().into_iter().find_map(|(name, attribute)| {
+                if name.prefix().as_deref() == Some("xml") && name.local_name() == "lang" {
+                    Some(string_value(&attribute))
+                } else {
+                    None
+                }
+            });
+            if let Some(xml_lang) = xml_lang {
+                let xml_lang = xml_lang.to_lowercase();
+                return Ok(XPathObject::Boolean(
+                    xml_lang == language || xml_lang.starts_with(&format!("{}-", language)),
+                ));
+            }
+        }
+        next = parent_of(&node);
+    }
+    Ok(XPathObject::Boolean(false))
+}
+
+// ------------------------------------------------------------------------------------------------
+// 4.4 Number Functions
+// ------------------------------------------------------------------------------------------------
+
+fn number(
+    _: &EvaluationContext,
+    arguments: &[XPathObject],
+) -> Result<XPathObject, EvaluationError> {
+    Ok(XPathObject::Number(arguments[0].to_number()))
+}
+
+fn sum(_: &EvaluationContext, arguments: &[XPathObject]) -> Result<XPathObject, EvaluationError> {
+    Ok(XPathObject::Number(
+        node_set_arg(arguments, 0)
+            .iter()
+            .map(|node| string_to_number(&string_value(node)))
+            .sum(),
+    ))
+}
+
+fn floor(_: &EvaluationContext, arguments: &[XPathObject]) -> Result<XPathObject, EvaluationError> {
+    Ok(XPathObject::Number(number_arg(arguments, 0).floor()))
+}
+
+fn ceiling(
+    _: &EvaluationContext,
+    arguments: &[XPathObject],
+) -> Result<XPathObject, EvaluationError> {
+    Ok(XPathObject::Number(number_arg(arguments, 0).ceil()))
+}
+
+fn round(_: &EvaluationContext, arguments: &[XPathObject]) -> Result<XPathObject, EvaluationError> {
+    Ok(XPathObject::Number(round_half_up(number_arg(arguments, 0))))
+}
+
+//
+// The round function returns the number that is closest to the argument and that is an integer.
+// If there are two such numbers, then the one that is closest to positive infinity is returned.
+// If the argument is NaN or an infinity then the same value is returned. If the argument is less
+// than zero, but greater than or equal to -0.5, then negative zero is returned.
+//
+fn round_half_up(value: f64) -> f64 {
+    if value.is_nan() || value.is_infinite() {
+        return value;
+    }
+    let floor = value.floor();
+    let rounded = if value - floor >= 0.5 {
+        floor + 1.0
+    } else {
+        floor
+    };
+    if rounded == 0.0 && value.is_sign_negative() {
+        -0.0
+    } else {
+        rounded
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+// Unit Tests
+// ------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::xpath1::evaluate::evaluate_expr;
+    use crate::xpath1::model::{AxisSpecifier, Expr, NodeTest, Predicate, QName};
+    use crate::xpath1::parser::read_expr;
+    use xml_dom::parser::read_xml;
+
+    const LANG_XML: &str = r#"<?xml version="1.0"?>
+<doc xmlns:x="http://example.org/x" xml:lang="en-GB">
+    <x:para xml:id="p1" x:kind="intro"><b>Hello</b>,    World</x:para>
+    <para xml:id="p2" xml:lang="de">Hallo</para>
+    <?render fast?>
+</doc>"#;
+
+    fn catalog_document() -> RefNode {
+        read_xml(include_str!("../../../tests/example.xml")).unwrap()
+    }
+
+    fn lang_document() -> RefNode {
+        read_xml(LANG_XML).unwrap()
+    }
+
+    // The context is the document element, the document itself must be kept alive by the caller.
+    fn element_context(document: &RefNode) -> EvaluationContext {
+        EvaluationContext::from(document.first_child().unwrap())
+    }
+
+    fn evaluate(context: &EvaluationContext, xpath: &str) -> XPathObject {
+        evaluate_expr(context, &read_expr(xpath).unwrap()).unwrap()
+    }
+
+    fn check_string(context: &EvaluationContext, xpath: &str, expected: &str) {
+        match evaluate(context, xpath) {
+            XPathObject::String(value) => assert_eq!(value, expected, "{}", xpath),
+            _ => panic!("Expecting a string from {}", xpath),
+        }
+    }
+
+    fn check_number(context: &EvaluationContext, xpath: &str, expected: f64) {
+        match evaluate(context, xpath) {
+            XPathObject::Number(value) if expected.is_nan() => assert!(value.is_nan(), "{}", xpath),
+            XPathObject::Number(value) => assert_eq!(value, expected, "{}", xpath),
+            _ => panic!("Expecting a number from {}", xpath),
+        }
+    }
+
+    fn check_boolean(context: &EvaluationContext, xpath: &str, expected: bool) {
+        match evaluate(context, xpath) {
+            XPathObject::Boolean(value) => assert_eq!(value, expected, "{}", xpath),
+            _ => panic!("Expecting a boolean from {}", xpath),
+        }
+    }
+
+    fn call(name: &str, arguments: &[XPathObject]) -> XPathObject {
+        core_function(name).unwrap()(&EvaluationContext::default(), arguments).unwrap()
+    }
+
+    // --------------------------------------------------------------------------------------------
+
+    #[test]
+    fn test_node_set_functions() {
+        let document = catalog_document();
+        let context = element_context(&document);
+        check_number(&context, "count(book)", 12.0);
+        check_number(&context, "count(magazine)", 0.0);
+        check_number(&context, "last()", 1.0);
+        check_number(&context, "position()", 1.0);
+        check_string(&context, "local-name()", "catalog");
+        check_string(&context, "local-name(book)", "book");
+        check_string(&context, "local-name(magazine)", "");
+        check_string(&context, "name(book)", "book");
+        check_string(&context, "namespace-uri(book)", "");
+    }
+
+    #[test]
+    fn test_node_set_function_type_error() {
+        let document = catalog_document();
+        let context = element_context(&document);
+        let result = evaluate_expr(&context, &read_expr("count('book')").unwrap());
+        assert_eq!(
+            result.err(),
+            Some(EvaluationError::TypeError {
+                expr: "'book'".to_string(),
+                expected: DataType::NodeSet,
+                found: DataType::String,
+            })
+        );
+    }
+
+    #[test]
+    fn test_wrong_arity() {
+        // The parser rejects these calls, so the model is constructed directly.
+        let document = catalog_document();
+        let context = element_context(&document);
+        let xpath = Expr::with(Predicate::function_with(
+            "position",
+            &[Predicate::select(
+                AxisSpecifier::Child,
+                NodeTest::Named(QName::with("book")),
+            )],
+        ));
+        let result = evaluate_expr(&context, &xpath);
+        assert_eq!(
+            result.err(),
+            Some(EvaluationError::WrongArity(
+                "position(child::book)".to_string()
+            ))
+        );
+
+        let xpath = Expr::with(Predicate::function_with(
+            "concat",
+            &[Predicate::literal("a")],
+        ));
+        let result = evaluate_expr(&context, &xpath);
+        assert_eq!(
+            result.err(),
+            Some(EvaluationError::WrongArity("concat('a')".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_names_and_namespaces() {
+        let document = lang_document();
+        let mut context = element_context(&document);
+        context.add_namespace("x", "http://example.org/x");
+        check_string(&context, "name(child::*)", "x:para");
+        check_string(&context, "local-name(child::*)", "para");
+        check_string(&context, "name((child::*)/attribute::x:kind)", "x:kind");
+        check_string(&context, "local-name(processing-instruction())", "render");
+    }
+
+    #[test]
+    fn test_id() {
+        let document = lang_document();
+        let context = element_context(&document);
+        check_number(&context, "count(id('p1'))", 1.0);
+        check_number(&context, "count(id(' p2  p1 p1 '))", 2.0);
+        check_number(&context, "count(id('p3'))", 0.0);
+        check_string(&context, "string(id('p2'))", "Hallo");
+    }
+
+    #[test]
+    fn test_string_functions() {
+        let document = catalog_document();
+        let context = element_context(&document);
+        check_string(&context, "string((book)/price)", "44.95");
+        check_string(&context, "string(12)", "12");
+        check_string(&context, "string(true())", "true");
+        check_string(&context, "concat('a', 'b', 3, true())", "ab3true");
+        check_boolean(&context, "starts-with('abcde', 'abc')", true);
+        check_boolean(&context, "starts-with('abcde', 'bcd')", false);
+        check_boolean(&context, "contains('abcde', 'bcd')", true);
+        check_boolean(&context, "contains('abcde', '')", true);
+        check_string(&context, "substring-before('1999/04/01', '/')", "1999");
+        check_string(&context, "substring-before('1999/04/01', '-')", "");
+        check_string(&context, "substring-after('1999/04/01', '/')", "04/01");
+        check_string(&context, "substring-after('1999/04/01', '19')", "99/04/01");
+        check_string(&context, "substring-after('1999/04/01', '')", "1999/04/01");
+        check_number(&context, "string-length('façade')", 6.0);
+        check_string(&context, "normalize-space('  a \n\t b  c ')", "a b c");
+        check_string(&context, "translate('bar', 'abc', 'ABC')", "BAr");
+        check_string(&context, "translate('--aaa--', 'abc-', 'ABC')", "AAA");
+        check_string(&context, "translate('aba', 'aa', 'xy')", "xbx");
+    }
+
+    #[test]
+    fn test_string_functions_default_to_context_node() {
+        let document = lang_document();
+        let context = element_context(&document);
+        let para = EvaluationContext::from(context.node_set().child().iter().nth(1).unwrap());
+        check_string(&para, "string()", "Hallo");
+        check_number(&para, "string-length()", 5.0);
+        check_string(&para, "normalize-space()", "Hallo");
+        check_string(&context, "normalize-space(child::*)", "Hello, World");
+    }
+
+    #[test]
+    fn test_substring() {
+        let document = catalog_document();
+        let context = element_context(&document);
+        check_string(&context, "substring('12345', 2, 3)", "234");
+        check_string(&context, "substring('12345', 2)", "2345");
+        check_string(&context, "substring('12345', 1.5, 2.6)", "234");
+        check_string(&context, "substring('12345', 0, 3)", "12");
+        check_string(&context, "substring('12345', 'x', 3)", "");
+        check_string(&context, "substring('12345', 1, 'x')", "");
+
+        let substring = |start: f64, length: f64| {
+            call(
+                "substring",
+                &[
+                    XPathObject::String("12345".to_string()),
+                    XPathObject::Number(start),
+                    XPathObject::Number(length),
+                ],
+            )
+        };
+        assert!(matches!(substring(-42.0, f64::INFINITY), XPathObject::String(s) if s == "12345"));
+        assert!(
+            matches!(substring(f64::NEG_INFINITY, f64::INFINITY), XPathObject::String(s) if s.is_empty())
+        );
+    }
+
+    #[test]
+    fn test_boolean_functions() {
+        let document = lang_document();
+        let context = element_context(&document);
+        check_boolean(&context, "boolean(child::*)", true);
+        check_boolean(&context, "boolean(child::table)", false);
+        check_boolean(&context, "boolean('')", false);
+        check_boolean(&context, "boolean(0)", false);
+        check_boolean(&context, "boolean(number('x'))", false);
+        check_boolean(&context, "not(true())", false);
+        check_boolean(&context, "not('')", true);
+        check_boolean(&context, "false()", false);
+        check_boolean(&context, "lang('en')", true);
+        check_boolean(&context, "lang('EN-gb')", true);
+        check_boolean(&context, "lang('en-US')", false);
+        check_boolean(&context, "lang('e')", false);
+
+        let para = EvaluationContext::from(context.node_set().child().iter().nth(1).unwrap());
+        check_boolean(&para, "lang('de')", true);
+        check_boolean(&para, "lang('en')", false);
+        check_number(&context, "count(//@*[lang('de')])", 2.0);
+        check_number(&context, "count(child::*[1]/@*[lang('en')])", 2.0);
+    }
+
+    #[test]
+    fn test_number_functions() {
+        let document = catalog_document();
+        let context = element_context(&document);
+        check_number(&context, "number('12.5')", 12.5);
+        check_number(&context, "number('twelve')", f64::NAN);
+        check_number(&context, "number(true())", 1.0);
+        check_number(
+            &context,
+            "sum((book)/price)",
+            [
+                44.95, 5.95, 5.95, 5.95, 5.95, 4.95, 4.95, 4.95, 6.95, 36.95, 36.95, 49.95,
+            ]
+            .iter()
+            .sum(),
+        );
+        check_number(&context, "floor(2.5)", 2.0);
+        check_number(&context, "ceiling(2.5)", 3.0);
+        check_number(&context, "round(2.5)", 3.0);
+        check_number(&context, "round(number('-2.5'))", -2.0);
+        check_number(&context, "round(number('x'))", f64::NAN);
+    }
+
+    #[test]
+    fn test_string_and_number_conversion() {
+        let context = EvaluationContext::default();
+        check_string(&context, "string(1 div 0)", "Infinity");
+        check_string(&context, "string(-1 div 0)", "-Infinity");
+        check_string(&context, "string(0 div 0)", "NaN");
+        check_string(&context, "string(-0)", "0");
+        check_string(&context, "string(2.0)", "2");
+        check_string(&context, "string(-0.25)", "-0.25");
+        check_string(&context, "concat(1 div 3, '')", "0.3333333333333333");
+        check_number(&context, "number(' 12 ')", 12.0);
+        check_number(&context, "number('1e3')", f64::NAN);
+        check_number(&context, "number('+1')", f64::NAN);
+        check_number(&context, "number('Infinity')", f64::NAN);
+        check_number(&context, "'3' * '4'", 12.0);
+    }
+
+    #[test]
+    fn test_round_half_up() {
+        assert_eq!(round_half_up(0.49999999999999994), 0.0);
+        assert_eq!(round_half_up(1.5), 2.0);
+        assert_eq!(round_half_up(-1.5), -1.0);
+        assert_eq!(round_half_up(-1.6), -2.0);
+        assert!(round_half_up(-0.5).is_sign_negative());
+        assert!(round_half_up(-0.2).is_sign_negative());
+        assert!(round_half_up(-0.0).is_sign_negative());
+        assert_eq!(round_half_up(f64::INFINITY), f64::INFINITY);
+        assert_eq!(round_half_up(f64::NEG_INFINITY), f64::NEG_INFINITY);
+        assert!(round_half_up(f64::NAN).is_nan());
+    }
+}
@@ -0,0 +1,716 @@
+/*!
+Lazy, iterator-based, evaluation of location paths; the nodes selected by a path are produced one
+at a time, so that a consumer that stops early, such as one that only needs the first node, does
+not cause the whole document to be walked.
+*/
+
+use crate::xpath1::evaluate::axes::{following_siblings, Descendants, Following};
+use crate::xpath1::evaluate::expr::evaluate_predicate;
+use crate::xpath1::evaluate::filters::{Filter, NodeTestFilter, PredicateFilter};
+use crate::xpath1::evaluate::node_set::parent_of;
+use crate::xpath1::evaluate::{
+    evaluate_step, principal_node_type, select_nodes, EvaluationContext, EvaluationError, NodeSet,
+};
+use crate::xpath1::model::{
+    get_function, AxisSpecifier, DataType, Expr, ExprNode, LocationPath, NodeTest, Predicate, Step,
+    Terminal,
+};
+use crate::xpath1::XPathObject;
+use std::fmt::{Debug, Formatter};
+use std::iter::{once, once_with, Peekable};
+use std::vec::IntoIter;
+use xml_dom::level2::{Node, RefNode};
+
+// ------------------------------------------------------------------------------------------------
+// Public Types
+// ------------------------------------------------------------------------------------------------
+
+///
+/// An iterator over the nodes selected by a location path, in document order and without
+/// duplicates, returned by [`iter_path`](fn.iter_path.html). Any error is returned as the last
+/// item of the iteration.
+///
+pub struct NodeIter {
+    nodes: Nodes,
+    failed: bool,
+}
+
+// ------------------------------------------------------------------------------------------------
+// Public Functions
+// ------------------------------------------------------------------------------------------------
+
+///
+/// Given an [`EvaluationContext`](struct.EvaluationContext.html), return an iterator over the
+/// nodes selected by the `xpath` location path; these are the same nodes, in the same order, as
+/// those returned by [`evaluate_path`](fn.evaluate_path.html).
+///
+/// Steps on the forward axes are evaluated lazily, walking the tree only as far as is needed to
+/// produce the next node; a predicate that is a constant position, such as `[1]`, stops the walk
+/// of its axis once that position is reached. Steps that cannot be evaluated lazily without
+/// losing document order, such as those on a reverse axis, or predicates that depend on the
+/// context size, are evaluated for all of their input nodes when first needed.
+///
+/// # Example
+///
+/// ```rust
+/// use xml_dom_xpath::xpath1::evaluate::{iter_path, EvaluationContext};
+/// use xml_dom_xpath::xpath1::parser::read_str;
+/// use xml_dom::level2::Node;
+/// use xml_dom::parser::read_xml;
+///
+/// let document_node = read_xml(r#"<order><item/><item/><item/></order>"#).unwrap();
+/// let xpath = read_str("//item[1]").unwrap();
+///
+/// let mut nodes = iter_path(&EvaluationContext::from(document_node), &xpath);
+/// assert_eq!(nodes.next().unwrap().unwrap().node_name().to_string(), "item");
+/// assert!(nodes.next().is_none());
+/// ```
+///
+pub fn iter_path(context: &EvaluationContext, xpath: &LocationPath) -> NodeIter {
+    let mut start = if xpath.is_absolute() {
+        context.node_set().document()
+    } else {
+        context.node_set().clone()
+    };
+    start.sort_document_order();
+    let mut order = if start.len() > 1 {
+        Order::Nested
+    } else {
+        Order::Single
+    };
+    let mut nodes: Nodes = Box::new(start.into_iter().map(Ok));
+
+    let mut steps = xpath.steps().cloned().peekable();
+    while let Some(step) = steps.next() {
+        let axis = step.select_expr().axis_specifier();
+        if is_any_node_descendant_or_self(&step)
+            && matches!(
+                steps.peek(),
+                Some(next) if next.select_expr().axis_specifier() == AxisSpecifier::Child
+            )
+        {
+            let child_step = steps.next().unwrap();
+            nodes = descendant_children(context, outermost(nodes, order), child_step.clone());
+            nodes = limit_nodes(context, nodes, &child_step);
+            order = Order::Nested;
+        } else if let Some(next_order) = order.after(&step) {
+            if matches!(
+                axis,
+                AxisSpecifier::Descendant | AxisSpecifier::DescendantOrSelf
+            ) {
+                nodes = outermost(nodes, order);
+            }
+            nodes = limit_nodes(context, lazy_step(context, nodes, step.clone()), &step);
+            order = next_order;
+        } else {
+            nodes = eager_step(context, nodes, step);
+            order = Order::Nested;
+        }
+    }
+
+    NodeIter::new(nodes)
+}
+
+// ------------------------------------------------------------------------------------------------
+// Implementations
+// ------------------------------------------------------------------------------------------------
+
+impl Debug for NodeIter {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("NodeIter")
+            .field("failed", &self.failed)
+            .finish()
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+
+impl Iterator for NodeIter {
+    type Item = Result<RefNode, EvaluationError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+        let next = self.nodes.next();
+        self.failed = matches!(next, Some(Err(_)));
+        next
+    }
+}
+
+impl NodeIter {
+    fn new(nodes: Nodes) -> Self {
+        Self {
+            nodes,
+            failed: false,
+        }
+    }
+
+    //
+    // Return the nodes selected by any expression; a location path is evaluated lazily, but any
+    // other expression is evaluated completely when the first node is requested.
+    //
+    pub(crate) fn from_expr(context: &EvaluationContext, xpath: &Expr) -> Self {
+        match xpath.root() {
+            Predicate::Terminal(Terminal::Path(path)) => iter_path(context, path),
+            Predicate::Terminal(Terminal::Select(select)) => {
+                iter_path(context, &LocationPath::with(Step::with(select.clone())))
+            }
+            root => {
+                let context = context.clone();
+                let root = root.clone();
+                Self::new(deferred(move || {
+                    match evaluate_predicate(&context, &root)? {
+                        XPathObject::NodeSet(node_set) => Ok(node_set),
+                        value => Err(EvaluationError::type_error(
+                            &root,
+                            DataType::NodeSet,
+                            &value,
+                        )),
+                    }
+                }))
+            }
+        }
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+
+impl Iterator for DescendantChildren {
+    type Item = Result<RefNode, EvaluationError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some((children, selected)) = self.stack.last_mut() {
+            match children.next() {
+                None => {
+                    let _ = self.stack.pop();
+                }
+                Some(child) => {
+                    let is_selected = match selected.peek() {
+                        None => false,
+                        Some(Ok(next)) => next == &child,
+                        Some(Err(_)) => return selected.next(),
+                    };
+                    if is_selected {
+                        let _ = selected.next();
+                    }
+                    self.push(&child);
+                    if is_selected {
+                        return Some(Ok(child));
+                    }
+                }
+            }
+        }
+        None
+    }
+}
+
+impl DescendantChildren {
+    fn new(context: &EvaluationContext, child_step: &Step, node: &RefNode) -> Self {
+        let mut new_self = Self {
+            context: context.clone(),
+            child_step: child_step.clone(),
+            stack: Vec::new(),
+        };
+        new_self.push(node);
+        new_self
+    }
+
+    fn push(&mut self, node: &RefNode) {
+        self.stack.push((
+            node.child_nodes().into_iter(),
+            filter_axis(&self.context, &self.child_step, node).peekable(),
+        ));
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+
+impl Order {
+    //
+    // The order of the nodes produced by `step`, evaluated lazily, with input in this order, or
+    // `None` if the step must be evaluated eagerly to produce nodes in document order. A lazy
+    // step on a descendant axis skips input nodes nested within an earlier input node, which is
+    // only correct without predicates, as these may depend on positions relative to the input.
+    //
+    fn after(self, step: &Step) -> Option<Self> {
+        match (step.select_expr().axis_specifier(), self) {
+            (AxisSpecifier::SelfNode, order) => Some(order),
+            (AxisSpecifier::Attribute, _) | (AxisSpecifier::Namespace, _) => Some(Order::Disjoint),
+            (AxisSpecifier::Descendant, Order::Nested)
+            | (AxisSpecifier::DescendantOrSelf, Order::Nested) => {
+                if step.predicate_exprs().next().is_none() {
+                    Some(Order::Nested)
+                } else {
+                    None
+                }
+            }
+            (AxisSpecifier::Descendant, _) | (AxisSpecifier::DescendantOrSelf, _) => {
+                Some(Order::Nested)
+            }
+            (AxisSpecifier::Child, Order::Single) | (AxisSpecifier::Child, Order::Disjoint) => {
+                Some(Order::Disjoint)
+            }
+            (AxisSpecifier::FollowingSibling, Order::Single) => Some(Order::Disjoint),
+            (AxisSpecifier::Following, Order::Single) => Some(Order::Nested),
+            (AxisSpecifier::Parent, Order::Single) => Some(Order::Single),
+            _ => None,
+        }
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+// Private Types
+// ------------------------------------------------------------------------------------------------
+
+type Nodes = Box<dyn Iterator<Item = Result<RefNode, EvaluationError>>>;
+
+//
+// A pre-order walk of the descendants of a node, as `Descendants`, that keeps, for each node on
+// the path to the current position, both its remaining children and its remaining children that
+// are selected by the child step. The selected children are a subsequence of the children, so a
+// child reached by the walk is selected only if it is the next selected child of its parent.
+//
+struct DescendantChildren {
+    context: EvaluationContext,
+    child_step: Step,
+    stack: Vec<(IntoIter<RefNode>, Peekable<Nodes>)>,
+}
+
+//
+// The nodes produced by each step are always in document order, without duplicates; this records
+// what more is known about them, and so which following steps may also be evaluated lazily.
+//
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Order {
+    // At most one node.
+    Single,
+    // No node is a descendant of another, so the subtrees below the nodes are also in order.
+    Disjoint,
+    // A node may be a descendant of an earlier node.
+    Nested,
+}
+
+// ------------------------------------------------------------------------------------------------
+// Private Functions
+// ------------------------------------------------------------------------------------------------
+
+//
+// Evaluate the axis, node test, and predicates of `step` for each input node in turn; the caller
+// ensures that the nodes selected for each input node follow those selected for the previous
+// one.
+//
+fn lazy_step(context: &EvaluationContext, input: Nodes, step: Step) -> Nodes {
+    let context = context.clone();
+    Box::new(input.flat_map(move |node| match node {
+        Ok(node) => filter_axis(&context, &step, &node),
+        Err(e) => Box::new(once(Err(e))),
+    }))
+}
+
+//
+// Count the nodes selected by a lazy step, and fail once there are more than the context's limit,
+// as `evaluate_step` does for all of the nodes selected by a step.
+//
+fn limit_nodes(context: &EvaluationContext, nodes: Nodes, step: &Step) -> Nodes {
+    match context.node_limit() {
+        None => nodes,
+        Some(limit) => {
+            let expr = step.to_string();
+            Box::new(nodes.enumerate().map(move |(index, node)| match node {
+                Ok(_) if index >= limit => Err(EvaluationError::NodeLimitExceeded {
+                    expr: expr.clone(),
+                    limit,
+                }),
+                node => node,
+            }))
+        }
+    }
+}
+
+//
+// Evaluate `step` for all of the input nodes, as `evaluate_path` does, once the first node is
+// requested.
+//
+fn eager_step(context: &EvaluationContext, input: Nodes, step: Step) -> Nodes {
+    let context = context.clone();
+    deferred(move || {
+        let node_set = input.collect::<Result<NodeSet, EvaluationError>>()?;
+        evaluate_step(&context.with_node_set(node_set), &step)
+    })
+}
+
+//
+// The common abbreviation `//name` is the pair of steps `descendant-or-self::node()/child::name`,
+// which is evaluated as a single walk of the descendants of each input node in turn.
+//
+fn descendant_children(context: &EvaluationContext, input: Nodes, child_step: Step) -> Nodes {
+    let context = context.clone();
+    Box::new(input.flat_map(move |node| -> Nodes {
+        match node {
+            Ok(node) => Box::new(DescendantChildren::new(&context, &child_step, &node)),
+            Err(e) => Box::new(once(Err(e))),
+        }
+    }))
+}
+
+//
+// The nodes selected by the axis of `step` from `node`, in proximity order, filtered by the node
+// test and predicates of `step`.
+//
+fn filter_axis(context: &EvaluationContext, step: &Step, node: &RefNode) -> Nodes {
+    let select_expr = step.select_expr();
+    let axis = select_expr.axis_specifier();
+    let node_test = NodeTestFilter::new(principal_node_type(axis), select_expr.node_test());
+    let name_test = match node_test.name_test(context) {
+        Ok(name_test) => name_test,
+        Err(e) => return Box::new(once(Err(e))),
+    };
+    let mut nodes: Nodes = Box::new(
+        axis_nodes(step, node)
+            .filter(move |node| node_test.matches(node, name_test.as_ref()))
+            .map(Ok),
+    );
+    for predicate in step.predicate_exprs() {
+        nodes = filter_predicate(context, nodes, predicate.clone());
+    }
+    nodes
+}
+
+fn axis_nodes(step: &Step, node: &RefNode) -> Box<dyn Iterator<Item = RefNode>> {
+    let axis = step.select_expr().axis_specifier();
+    match axis {
+        AxisSpecifier::Child => Box::new(node.child_nodes().into_iter()),
+        AxisSpecifier::Descendant => Box::new(Descendants::new(node, false)),
+        AxisSpecifier::DescendantOrSelf => Box::new(Descendants::new(node, true)),
+        AxisSpecifier::Following => Box::new(Following::new(node)),
+        AxisSpecifier::FollowingSibling => Box::new(following_siblings(node)),
+        AxisSpecifier::Parent => Box::new(parent_of(node).into_iter()),
+        AxisSpecifier::SelfNode => Box::new(once(node.clone())),
+        _ => {
+            let nodes = select_nodes(&NodeSet::from(node), step);
+            if axis.is_reverse() {
+                Box::new(nodes.into_iter().rev())
+            } else {
+                Box::new(nodes.into_iter())
+            }
+        }
+    }
+}
+
+//
+// A predicate that depends on the context size needs all of the nodes selected for the context
+// node before any can be filtered; otherwise each node is filtered as it is produced, and a
+// constant position stops the walk once it is reached.
+//
+fn filter_predicate(context: &EvaluationContext, nodes: Nodes, predicate: Predicate) -> Nodes {
+    let context = context.clone();
+    if needs_context_size(&predicate) {
+        return deferred(move || {
+            let node_set = nodes.collect::<Result<NodeSet, EvaluationError>>()?;
+            PredicateFilter::new(predicate).apply(&context, &node_set)
+        });
+    }
+    if let Some(target) = constant_position(&predicate) {
+        let target = if target >= 1.0 && target.fract() == 0.0 && target <= usize::MAX as f64 {
+            target as usize
+        } else {
+            0
+        };
+        return Box::new(nodes.take(target).enumerate().filter_map(
+            move |(index, node)| match node {
+                Ok(node) if index + 1 == target => Some(Ok(node)),
+                Ok(_) => None,
+                Err(e) => Some(Err(e)),
+            },
+        ));
+    }
+    let filter = PredicateFilter::new(predicate);
+    Box::new(
+        nodes
+            .enumerate()
+            .filter_map(move |(index, node)| match node {
+                // The context size is not yet known, but is not used by the predicate, see
+                // `needs_context_size`; the position is the least size it could be.
+                Ok(node) => match filter.includes(&context, &node, index + 1, index + 1) {
+                    Ok(true) => Some(Ok(node)),
+                    Ok(false) => None,
+                    Err(e) => Some(Err(e)),
+                },
+                Err(e) => Some(Err(e)),
+            }),
+    )
+}
+
+//
+// Only the `last` function in the core library uses the context size, but an extension function
+// may use any part of the context.
+//
+fn needs_context_size(predicate: &Predicate) -> bool {
+    predicate.function_calls().iter().any(|call| {
+        let name = call.name();
+        name.prefix().is_some()
+            || name.local_name() == "last"
+            || get_function(name.local_name()).is_none()
+    })
+}
+
+//
+// A predicate that is a number, or compares `position()` with a number; note that the parser
+// turns `[2]` into `[position() = 2]`.
+//
+fn constant_position(predicate: &Predicate) -> Option<f64> {
+    let is_position = |predicate: &Predicate| {
+        matches!(predicate, Predicate::Function(call)
+            if call.name().prefix().is_none()
+                && call.name().local_name() == "position"
+                && call.arguments().count() == 0)
+    };
+    match predicate {
+        Predicate::Terminal(Terminal::Number(number, _)) => Some(*number),
+        Predicate::Expr(ExprNode::Equals { left, right, .. }) => {
+            match (left.as_ref(), right.as_ref()) {
+                (Predicate::Terminal(Terminal::Number(number, _)), other)
+                | (other, Predicate::Terminal(Terminal::Number(number, _)))
+                    if is_position(other) =>
+                {
+                    Some(*number)
+                }
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+fn is_any_node_descendant_or_self(step: &Step) -> bool {
+    let select_expr = step.select_expr();
+    select_expr.axis_specifier() == AxisSpecifier::DescendantOrSelf
+        && select_expr.node_test() == NodeTest::Node
+        && step.predicate_exprs().next().is_none()
+}
+
+//
+// Remove any input node that is a descendant of the previous node kept, so that walking the
+// descendants of each node kept in turn visits each node once, and in document order.
+//
+fn outermost(input: Nodes, order: Order) -> Nodes {
+    if order != Order::Nested {
+        return input;
+    }
+    let mut last: Option<RefNode> = None;
+    Box::new(input.filter(move |node| match node {
+        Ok(node) => {
+            if matches!(&last, Some(last) if is_descendant(node, last)) {
+                false
+            } else {
+                last = Some(node.clone());
+                true
+            }
+        }
+        Err(_) => true,
+    }))
+}
+
+fn is_descendant(node: &RefNode, ancestor: &RefNode) -> bool {
+    let mut next = parent_of(node);
+    while let Some(parent) = next {
+        if &parent == ancestor {
+            return true;
+        }
+        next = parent_of(&parent);
+    }
+    false
+}
+
+//
+// Produce the nodes of a node-set that is only computed when the first node is requested.
+//
+fn deferred<F>(f: F) -> Nodes
+where
+    F: FnOnce() -> Result<NodeSet, EvaluationError> + 'static,
+{
+    Box::new(once_with(f).flat_map(|result| -> Nodes {
+        match result {
+            Ok(node_set) => Box::new(node_set.into_iter().map(Ok)),
+            Err(e) => Box::new(once(Err(e))),
+        }
+    }))
+}
+
+// ------------------------------------------------------------------------------------------------
+// Unit Tests
+// ------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::xpath1::evaluate::{evaluate_path, VariableResolver};
+    use crate::xpath1::model::QName;
+    use crate::xpath1::parser::read_str;
+    use crate::xpath1::XPathObject;
+    use std::cell::Cell;
+    use std::rc::Rc;
+    use xml_dom::level2::Node;
+    use xml_dom::parser::read_xml;
+
+    const NESTED_XML: &str = r#"<?xml version="1.0"?>
+<a id="a1" xmlns:x="urn:x">
+  <b id="b1"><a id="a2"><b id="b2"/><c id="c1"/><b id="b3"><a id="a3"/></b></a></b>
+  <c id="c2"><b id="b4" x:n="1"/>text<!-- comment --><?pi data?></c>
+  <b id="b5"/>
+</a>"#;
+
+    fn check_same_nodes(xml: &str, expressions: &[&str]) {
+        let document_node = read_xml(xml).unwrap();
+        let root_element = document_node.first_child().unwrap();
+        let mut contexts = [
+            EvaluationContext::from(document_node.clone()),
+            EvaluationContext::from(root_element.clone()),
+            EvaluationContext::from(
+                root_element
+                    .child_nodes()
+                    .into_iter()
+                    .rev()
+                    .collect::<NodeSet>(),
+            ),
+        ];
+        for context in contexts.iter_mut() {
+            let _ = context.add_namespace("x", "urn:x");
+        }
+        for context in &contexts {
+            for expression in expressions {
+                let xpath = read_str(expression).expect(expression);
+                let expected = evaluate_path(context, &xpath)
+                    .unwrap()
+                    .into_node_set()
+                    .unwrap();
+                let actual = iter_path(context, &xpath)
+                    .collect::<Result<NodeSet, EvaluationError>>()
+                    .unwrap();
+                assert!(
+                    actual.iter().eq(expected.iter()),
+                    "{} selected {} nodes, expected {}",
+                    expression,
+                    actual.len(),
+                    expected.len()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_same_nodes_as_evaluate_path() {
+        check_same_nodes(
+            NESTED_XML,
+            &[
+                "//b",
+                "//a//b",
+                "//b[1]",
+                "//b[2]",
+                "//b[last()]",
+                "//*[@id][2]",
+                "//b[@x:n or @id = 'b3']",
+                "//a/b",
+                "//a//b[1]",
+                "//a/descendant::b[1]",
+                "descendant::b",
+                "descendant-or-self::*",
+                "descendant::b/descendant::a",
+                "//b/@id",
+                "//b[1]/following::*",
+                "//b[1]/following-sibling::*",
+                "//b/following-sibling::*",
+                "//a/..",
+                "//b/parent::*[1]",
+                "//b/ancestor::a",
+                "//b/preceding::*[1]",
+                "/a/*/text()",
+                "/a/c/node()",
+                "/a/c/processing-instruction('pi')",
+                "//comment()",
+                "//node()",
+                "//b/self::*[position() = 1]",
+                "//b[position() > 1][1]",
+                "//*[count(b) = 1]",
+                "//*[string(@id) = 'c1']/following::b",
+                "child::*",
+                "*/*",
+                "self::node()",
+                "..",
+                "//b[0]",
+                "//b[1.5]",
+                "//b[-1]",
+            ],
+        );
+        check_same_nodes(
+            include_str!("../../../tests/example.xml"),
+            &[
+                "//book",
+                "//book[1]/title",
+                "//book[price > 40]",
+                "/catalog/book[3]/author",
+                "//book[last()]/@id",
+                "//title/following::price[1]",
+            ],
+        );
+    }
+
+    struct CountingResolver(Rc<Cell<usize>>);
+
+    impl VariableResolver for CountingResolver {
+        fn resolve_variable(&self, _: &QName) -> Option<XPathObject> {
+            self.0.set(self.0.get() + 1);
+            Some(XPathObject::Boolean(true))
+        }
+    }
+
+    #[test]
+    fn test_stops_early() {
+        let document_node = read_xml(include_str!("../../../tests/example.xml")).unwrap();
+        let counter = Rc::new(Cell::new(0));
+        let mut context = EvaluationContext::from(document_node);
+        context.set_variable_resolver(CountingResolver(counter.clone()));
+
+        let xpath = read_str("//book[$all][1]").unwrap();
+        let mut nodes = iter_path(&context, &xpath);
+        assert!(nodes.next().unwrap().is_ok());
+        assert!(nodes.next().is_none());
+        assert_eq!(counter.get(), 1);
+
+        counter.set(0);
+        let mut nodes = iter_path(&context, &read_str("//book[$all]").unwrap());
+        assert!(nodes.next().unwrap().is_ok());
+        assert!(nodes.next().unwrap().is_ok());
+        assert_eq!(counter.get(), 2);
+
+        counter.set(0);
+        let _ = evaluate_path(&context, &xpath).unwrap();
+        assert_eq!(counter.get(), 12);
+    }
+
+    #[test]
+    fn test_errors() {
+        let document_node = read_xml(NESTED_XML).unwrap();
+        let context = EvaluationContext::from(document_node);
+
+        let mut nodes = iter_path(&context, &read_str("//b[$undefined]").unwrap());
+        assert_eq!(
+            nodes.next(),
+            Some(Err(EvaluationError::UnboundVariable(
+                "undefined".to_string()
+            )))
+        );
+        assert!(nodes.next().is_none());
+
+        let mut nodes = iter_path(&context, &read_str("//y:b").unwrap());
+        assert!(matches!(
+            nodes.next(),
+            Some(Err(EvaluationError::UnknownNamespacePrefix { .. }))
+        ));
+        assert!(nodes.next().is_none());
+
+        let nodes = iter_path(&context, &read_str("/a[$undefined]/b").unwrap());
+        assert_eq!(nodes.filter(Result::is_err).count(), 1);
+    }
+}
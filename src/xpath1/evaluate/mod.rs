@@ -1,27 +1,82 @@
 /*!
-This provides the evaluation implementation, it takes a `NodeSet` as the context and a parsed
-XPath `LocationPath`. The primary API is the [`evaluate_path`](fn.evaluate_path.html) function.
+This provides the evaluation implementation, it takes an `EvaluationContext`, holding the context
+nodes, variable bindings, functions, and namespaces, and a parsed XPath `LocationPath`, or
+`Expr`. The primary API is the [`evaluate_path`](fn.evaluate_path.html) function, with
+[`evaluate_expr`](fn.evaluate_expr.html) for any expression, and [`iter_path`](fn.iter_path.html)
+to produce the selected nodes lazily.
 
 # Example
 
+```rust
+use xml_dom_xpath::xpath1::evaluate::{evaluate_expr, evaluate_path, EvaluationContext};
+use xml_dom_xpath::xpath1::parser::{read_expr, read_str};
+use xml_dom_xpath::xpath1::XPathObject;
+use xml_dom::parser::read_xml;
+
+let document_node = read_xml(r#"<order><item sku="a1"/><item sku="b2"/></order>"#).unwrap();
+let context = EvaluationContext::from(document_node);
+
+let result = evaluate_path(&context, &read_str("//item[@sku = 'b2']").unwrap()).unwrap();
+assert!(matches!(result, XPathObject::NodeSet(nodes) if nodes.len() == 1));
+
+let result = evaluate_expr(&context, &read_expr("count(//item) * 2").unwrap()).unwrap();
+assert_eq!(result, XPathObject::Number(4.0));
+```
+
 */
 
-use crate::xpath1::model::{AxisSpecifier, LocationPath, Step};
+use crate::xpath1::model::{AxisSpecifier, DataType, Expr, LocationPath, Step};
+use crate::xpath1::parser::AXIS_NAMES;
 use crate::xpath1::XPathObject;
 use std::fmt::{Display, Formatter};
-use xml_dom::level2::NodeType;
+use std::iter::FromIterator;
+use xml_dom::level2::{NodeType, RefNode};
 
 // ------------------------------------------------------------------------------------------------
 // Public Types
 // ------------------------------------------------------------------------------------------------
 
 ///
-/// Potential errors returned by [`evaluate_path`](fn.evaluate_path.html).
+/// Potential errors returned by [`evaluate_path`](fn.evaluate_path.html). Each error carries the
+/// failing sub-expression, in the form produced by its `Display` implementation, so that it may
+/// be located within the whole expression.
 ///
 #[derive(Clone, Debug, PartialEq)]
 pub enum EvaluationError {
-    /// A cycle was detected in the expression axis.
-    CycleError,
+    /// The expression, or sub-expression, is not supported by the evaluator.
+    Unsupported(String),
+    /// The expression, or sub-expression, evaluated to an object of one type where another is
+    /// required; as there is no conversion to a node-set, this is usually a value such as a
+    /// number where a node-set is required.
+    TypeError {
+        /// The sub-expression that produced the object.
+        expr: String,
+        /// The type of object required.
+        expected: DataType,
+        /// The type of object produced.
+        found: DataType,
+    },
+    /// The function call names a function that is not in the evaluation context's registry.
+    UnknownFunction(String),
+    /// The function was called with the wrong number of arguments.
+    WrongArity(String),
+    /// The variable reference has no value bound in the evaluation context.
+    UnboundVariable(String),
+    /// The qualified name uses a prefix with no namespace declared in the evaluation context.
+    UnknownNamespacePrefix {
+        /// The undeclared prefix.
+        prefix: String,
+        /// The sub-expression that uses the prefix.
+        expr: String,
+    },
+    /// The expression selected more nodes than the limit set in the evaluation context, see
+    /// [`set_node_limit`](struct.EvaluationContext.html#method.set_node_limit).
+    NodeLimitExceeded {
+        /// The sub-expression that selected the nodes.
+        expr: String,
+        /// The limit that was exceeded.
+        limit: usize,
+    },
 }
 
 // ------------------------------------------------------------------------------------------------
@@ -29,37 +84,92 @@ pub enum EvaluationError {
 // ------------------------------------------------------------------------------------------------
 
 ///
-/// Given a [`NodeSet`](struct.NodeSet.html), evaluate the `xpath` expression and return an
-/// [`XPathObject`](../enum.XPathObject.html) result.
+/// Given an [`EvaluationContext`](struct.EvaluationContext.html), evaluate the `xpath` location
+/// path and return an [`XPathObject`](../enum.XPathObject.html) result.
+///
+/// Each step is evaluated with the node-set selected by the previous step as its context, and
+/// the resulting node-set is always in document order.
 ///
 pub fn evaluate_path(
-    node_set: &NodeSet,
+    context: &EvaluationContext,
     xpath: &LocationPath,
 ) -> Result<XPathObject, EvaluationError> {
     let mut next_set = if xpath.is_absolute() {
-        node_set.document()
+        context.node_set().document()
     } else {
-        node_set.clone()
+        context.node_set().clone()
     };
     for step in xpath.steps() {
-        next_set = filter_nodes(&select_nodes(node_set, step), step)
+        next_set = evaluate_step(&context.with_node_set(next_set), step)?
     }
     Ok(XPathObject::NodeSet(next_set))
 }
 
+///
+/// Given an [`EvaluationContext`](struct.EvaluationContext.html), evaluate the `xpath` expression
+/// and return an [`XPathObject`](../enum.XPathObject.html) result.
+///
+pub fn evaluate_expr(
+    context: &EvaluationContext,
+    xpath: &Expr,
+) -> Result<XPathObject, EvaluationError> {
+    evaluate_predicate(context, xpath.root())
+}
+
 // ------------------------------------------------------------------------------------------------
 // Implementations
 // ------------------------------------------------------------------------------------------------
 
 impl Display for EvaluationError {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "{}",
-            match self {
-                EvaluationError::CycleError => "A cycle was detected in the expression axis.",
+        match self {
+            EvaluationError::Unsupported(expr) => {
+                write!(f, "The expression '{}' is not supported.", expr)
             }
-        )
+            EvaluationError::TypeError {
+                expr,
+                expected,
+                found,
+            } => write!(
+                f,
+                "The expression '{}' evaluated to {} where {} is required.",
+                expr,
+                type_name(found),
+                type_name(expected)
+            ),
+            EvaluationError::UnknownFunction(expr) => {
+                write!(f, "The function called in '{}' is not known.", expr)
+            }
+            EvaluationError::WrongArity(expr) => write!(
+                f,
+                "The function call '{}' has the wrong number of arguments.",
+                expr
+            ),
+            EvaluationError::UnboundVariable(name) => {
+                write!(f, "The variable '${}' has no value bound.", name)
+            }
+            EvaluationError::UnknownNamespacePrefix { prefix, expr } => {
+                write!(
+                    f,
+                    "The namespace prefix '{}' used in '{}' is not declared",
+                    prefix, expr
+                )?;
+                if AXIS_NAMES.contains(&prefix.as_str()) {
+                    write!(
+                        f,
+                        ", an axis name is followed by '::', did you mean '{}::'?",
+                        prefix
+                    )
+                } else {
+                    write!(f, ".")
+                }
+            }
+            EvaluationError::NodeLimitExceeded { expr, limit } => write!(
+                f,
+                "The expression '{}' selected more than the limit of {} nodes.",
+                expr, limit
+            ),
+        }
     }
 }
 
@@ -67,6 +177,25 @@ impl Display for EvaluationError {
 
 impl std::error::Error for EvaluationError {}
 
+// ------------------------------------------------------------------------------------------------
+
+impl EvaluationError {
+    pub(crate) fn type_error(expr: &impl Display, expected: DataType, found: &XPathObject) -> Self {
+        Self::TypeError {
+            expr: expr.to_string(),
+            expected,
+            found: found.data_type(),
+        }
+    }
+
+    pub(crate) fn unknown_prefix(prefix: &str, expr: &impl Display) -> Self {
+        Self::UnknownNamespacePrefix {
+            prefix: prefix.to_string(),
+            expr: expr.to_string(),
+        }
+    }
+}
+
 // ------------------------------------------------------------------------------------------------
 // Private Functions
 // ------------------------------------------------------------------------------------------------
@@ -90,15 +219,46 @@ fn select_nodes(node_set: &NodeSet, step: &Step) -> NodeSet {
     }
 }
 
-fn filter_nodes(node_set: &NodeSet, step: &Step) -> NodeSet {
+//
+// The axis, node test, and predicates of a step are evaluated for each node in `node_set` in turn,
+// and so each predicate sees the proximity positions of nodes with respect to a single context
+// node. The axis functions return nodes in document order, so for a reverse axis the nodes are
+// reversed before filtering so that proximity positions are in reverse document order. The nodes
+// selected for each context node are then combined, without duplicates, into a single node-set in
+// document order; this only requires a sort where there is more than one context node.
+//
+fn evaluate_step(context: &EvaluationContext, step: &Step) -> Result<NodeSet, EvaluationError> {
+    let filters = step_filters(step);
+    let is_reverse = step.select_expr().axis_specifier().is_reverse();
+    let mut result: Vec<RefNode> = Vec::new();
+    for node in context.node_set().iter() {
+        let mut selected = select_nodes(&NodeSet::from(node), step);
+        if is_reverse {
+            selected = selected.iter().rev().cloned().collect();
+        }
+        for filter in &filters {
+            selected = filter.apply(context, &selected)?;
+        }
+        result.extend(selected.iter().cloned());
+    }
+    let result = if context.node_set().len() > 1 {
+        let mut result = NodeSet::from_iter(result);
+        result.sort_document_order();
+        result
+    } else if is_reverse {
+        NodeSet::from_iter(result.into_iter().rev())
+    } else {
+        NodeSet::from_iter(result)
+    };
+    context.check_node_limit(result, step)
+}
+
+fn step_filters(step: &Step) -> Vec<Box<dyn Filter>> {
     let mut filters: Vec<Box<dyn Filter>> = Vec::new();
 
     let select_expr = step.select_expr();
     let filter = Box::new(NodeTestFilter::new(
-        match select_expr.axis_specifier() {
-            AxisSpecifier::Attribute => NodeType::Attribute,
-            _ => NodeType::Element,
-        },
+        principal_node_type(select_expr.axis_specifier()),
         select_expr.node_test(),
     ));
     filters.push(filter);
@@ -108,22 +268,61 @@ fn filter_nodes(node_set: &NodeSet, step: &Step) -> NodeSet {
         filters.push(filter);
     }
 
-    node_set
-        .iter()
-        .filter(|node| filters.iter().all(|filter| filter.apply(node)))
-        .cloned()
-        .collect()
+    filters
+}
+
+fn type_name(data_type: &DataType) -> &'static str {
+    match data_type {
+        DataType::Bool => "a boolean",
+        DataType::Number => "a number",
+        DataType::String => "a string",
+        DataType::Object => "an object",
+        DataType::NodeSet => "a node-set",
+    }
+}
+
+fn principal_node_type(axis: AxisSpecifier) -> NodeType {
+    match axis {
+        AxisSpecifier::Attribute => NodeType::Attribute,
+        _ => NodeType::Element,
+    }
 }
 
 // ------------------------------------------------------------------------------------------------
 // Modules
 // ------------------------------------------------------------------------------------------------
 
+mod context;
+pub use context::{EvaluationContext, FunctionImpl};
+
+mod expr;
+use expr::evaluate_predicate;
+pub(crate) use expr::string_value;
+
+mod convert;
+pub use convert::{number_to_string, string_to_number};
+
+mod variables;
+pub use variables::VariableResolver;
+
+mod namespaces;
+pub use namespaces::{NamespaceResolver, NodeNamespaceResolver};
+
+mod functions;
+
+mod registry;
+pub use registry::FunctionRegistry;
+
 mod filters;
 use filters::{Filter, NodeTestFilter, PredicateFilter};
 
 mod node_set;
-pub use node_set::NodeSet;
+pub use node_set::{compare_document_order, NodeSet};
+
+mod axes;
+
+mod lazy;
+pub use lazy::{iter_path, NodeIter};
 
 // ------------------------------------------------------------------------------------------------
 // Unit Tests
@@ -132,7 +331,12 @@ pub use node_set::NodeSet;
 #[cfg(test)]
 mod tests {
     use super::*;
-    use xml_dom::level2::{Node, RefNode};
+    use crate::xpath1::model::{
+        Argument, DataType, FilterExpr, Function, NodeTest, Predicate, QName, Select,
+    };
+    use crate::xpath1::parser::{read_expr, read_expr_with_functions, read_str, ParseError};
+    use std::collections::HashMap;
+    use xml_dom::level2::{Element, Node};
     use xml_dom::parser::read_xml;
 
     fn make_test_document() -> RefNode {
@@ -167,7 +371,7 @@ mod tests {
         let mut xpath = LocationPath::default();
         let xpath = xpath.all_self();
 
-        let result = evaluate_path(&NodeSet::from(document_node), &xpath);
+        let result = evaluate_path(&EvaluationContext::from(document_node), xpath);
         check_result_nodes(result, 1, NodeType::Document);
     }
 
@@ -177,7 +381,7 @@ mod tests {
         let mut xpath = LocationPath::default();
         let xpath = xpath.all_self_elements();
 
-        let result = evaluate_path(&NodeSet::from(document_node), &xpath);
+        let result = evaluate_path(&EvaluationContext::from(document_node), xpath);
         check_result_nodes(result, 0, NodeType::Element);
     }
 
@@ -187,7 +391,7 @@ mod tests {
         let mut xpath = LocationPath::default();
         let xpath = xpath.all_child_elements();
 
-        let result = evaluate_path(&NodeSet::from(document_node), &xpath);
+        let result = evaluate_path(&EvaluationContext::from(document_node), xpath);
         check_result_nodes(result, 1, NodeType::Element);
     }
 
@@ -197,7 +401,7 @@ mod tests {
         let mut xpath = LocationPath::default();
         let xpath = xpath.child_elements("catalog");
 
-        let result = evaluate_path(&NodeSet::from(document_node), &xpath);
+        let result = evaluate_path(&EvaluationContext::from(document_node), xpath);
         check_result_nodes(result, 1, NodeType::Element);
     }
 
@@ -207,7 +411,872 @@ mod tests {
         let mut xpath = LocationPath::default();
         let xpath = xpath.child_elements("books");
 
-        let result = evaluate_path(&NodeSet::from(document_node), &xpath);
+        let result = evaluate_path(&EvaluationContext::from(document_node), xpath);
+        check_result_nodes(result, 0, NodeType::Element);
+    }
+
+    // --------------------------------------------------------------------------------------------
+
+    #[test]
+    fn test_expr_literal() {
+        let document_node = make_test_document();
+        let xpath = read_expr("'catalog'").unwrap();
+
+        let result = evaluate_expr(&EvaluationContext::from(document_node), &xpath);
+        assert!(matches!(result, Ok(XPathObject::String(s)) if s == "catalog"));
+    }
+
+    #[test]
+    fn test_expr_number() {
+        let document_node = make_test_document();
+        let xpath = read_expr("42").unwrap();
+
+        let result = evaluate_expr(&EvaluationContext::from(document_node), &xpath);
+        assert!(matches!(result, Ok(XPathObject::Number(n)) if n == 42.0));
+    }
+
+    #[test]
+    fn test_expr_union() {
+        let document_node = make_test_document();
+        let xpath = read_expr("child::catalog | self::node()").unwrap();
+
+        let result = evaluate_expr(&EvaluationContext::from(document_node), &xpath);
+        assert!(matches!(result, Ok(XPathObject::NodeSet(node_set)) if node_set.len() == 2));
+    }
+
+    #[test]
+    fn test_expr_filter_path() {
+        let document_node = make_test_document();
+        let xpath = read_expr("(child::catalog)/child::book").unwrap();
+
+        let result = evaluate_expr(&EvaluationContext::from(document_node), &xpath);
+        check_result_nodes(result, 12, NodeType::Element);
+    }
+
+    #[test]
+    fn test_expr_union_not_a_node_set() {
+        let document_node = make_test_document();
+        let xpath = read_expr("child::catalog | 42").unwrap();
+
+        let result = evaluate_expr(&EvaluationContext::from(document_node), &xpath);
+        assert_eq!(
+            result.err(),
+            Some(EvaluationError::TypeError {
+                expr: "42".to_string(),
+                expected: DataType::NodeSet,
+                found: DataType::Number,
+            })
+        );
+    }
+
+    // --------------------------------------------------------------------------------------------
+
+    fn make_catalog_context() -> EvaluationContext {
+        let document_node = make_test_document();
+        let xpath = read_str("child::catalog").unwrap();
+        match evaluate_path(&EvaluationContext::from(document_node), &xpath) {
+            Ok(XPathObject::NodeSet(node_set)) => EvaluationContext::from(node_set),
+            _ => panic!("Expecting a node set!"),
+        }
+    }
+
+    fn check_result_ids(result: Result<XPathObject, EvaluationError>, ids: &[&str]) {
+        match result {
+            Ok(XPathObject::NodeSet(node_set)) => assert_eq!(
+                node_set
+                    .iter()
+                    .filter_map(|node| node.get_attribute("id"))
+                    .collect::<Vec<String>>(),
+                ids.iter().map(|s| s.to_string()).collect::<Vec<String>>()
+            ),
+            _ => panic!("Expecting a node set!"),
+        }
+    }
+
+    fn make_path_with_predicates(step: Step, predicates: &[Predicate]) -> LocationPath {
+        let mut step = step;
+        for predicate in predicates {
+            step.append(predicate.clone());
+        }
+        LocationPath::with(step)
+    }
+
+    #[test]
+    fn test_predicate_number() {
+        // Note that the parser turns `[2]` into `[position() = 2]`.
+        let xpath =
+            make_path_with_predicates(Step::child_elements("book"), &[Predicate::integer(2)]);
+
+        let result = evaluate_path(&make_catalog_context(), &xpath);
+        check_result_ids(result, &["bk102"]);
+    }
+
+    #[test]
+    fn test_predicate_number_out_of_range() {
+        let xpath =
+            make_path_with_predicates(Step::child_elements("book"), &[Predicate::integer(0)]);
+
+        let result = evaluate_path(&make_catalog_context(), &xpath);
+        check_result_nodes(result, 0, NodeType::Element);
+    }
+
+    #[test]
+    fn test_predicate_node_set() {
+        let xpath = read_str("child::book[child::price]").unwrap();
+
+        let result = evaluate_path(&make_catalog_context(), &xpath);
+        check_result_nodes(result, 12, NodeType::Element);
+
+        let xpath = read_str("child::book[child::isbn]").unwrap();
+
+        let result = evaluate_path(&make_catalog_context(), &xpath);
+        check_result_nodes(result, 0, NodeType::Element);
+    }
+
+    #[test]
+    fn test_predicate_string() {
+        let xpath = read_str("child::book['yes']").unwrap();
+
+        let result = evaluate_path(&make_catalog_context(), &xpath);
+        check_result_nodes(result, 12, NodeType::Element);
+
+        let xpath = read_str("child::book['']").unwrap();
+
+        let result = evaluate_path(&make_catalog_context(), &xpath);
+        check_result_nodes(result, 0, NodeType::Element);
+    }
+
+    #[test]
+    fn test_predicates_in_turn() {
+        let xpath = make_path_with_predicates(
+            Step::child_elements("book"),
+            &[
+                Predicate::select(AxisSpecifier::Child, NodeTest::Named(QName::with("price"))),
+                Predicate::integer(3),
+                Predicate::integer(1),
+            ],
+        );
+
+        let result = evaluate_path(&make_catalog_context(), &xpath);
+        check_result_ids(result, &["bk103"]);
+    }
+
+    #[test]
+    fn test_predicate_position_per_context_node() {
+        let books = match evaluate_path(&make_catalog_context(), &read_str("book").unwrap()) {
+            Ok(XPathObject::NodeSet(node_set)) => node_set,
+            _ => panic!("Expecting a node set!"),
+        };
+        let xpath = make_path_with_predicates(Step::all_child_elements(), &[Predicate::integer(1)]);
+
+        let result = evaluate_path(&EvaluationContext::from(books), &xpath);
+        check_result_nodes(result, 12, NodeType::Element);
+    }
+
+    #[test]
+    fn test_filter_expr_predicate() {
+        let mut filter = FilterExpr::with(Predicate::select(
+            AxisSpecifier::Child,
+            NodeTest::Named(QName::with("book")),
+        ));
+        filter.append(Predicate::integer(3));
+        let xpath = Expr::with(Predicate::filter(filter));
+
+        let result = evaluate_expr(&make_catalog_context(), &xpath);
+        check_result_ids(result, &["bk103"]);
+    }
+
+    // --------------------------------------------------------------------------------------------
+
+    #[test]
+    fn test_path_steps_in_turn() {
+        let document_node = make_test_document();
+        let context = EvaluationContext::from(document_node);
+
+        let result = evaluate_path(&context, &read_str("child::catalog/child::book").unwrap());
+        check_result_nodes(result, 12, NodeType::Element);
+
+        let mut xpath = LocationPath::absolute();
+        xpath.child_elements("catalog").append({
+            let mut step = Step::child_elements("book");
+            step.append(Predicate::integer(2));
+            step
+        });
+        let result = evaluate_path(&context, &xpath);
+        check_result_ids(result, &["bk102"]);
+
+        let result = evaluate_path(&context, &read_str("//book/price").unwrap());
+        check_result_nodes(result, 12, NodeType::Element);
+
+        let result = evaluate_path(&context, &read_str("catalog/book/isbn").unwrap());
+        check_result_nodes(result, 0, NodeType::Element);
+    }
+
+    #[test]
+    fn test_path_without_duplicates() {
+        let xpath = read_str("book/ancestor::*").unwrap();
+
+        let result = evaluate_path(&make_catalog_context(), &xpath);
+        check_result_nodes(result, 1, NodeType::Element);
+
+        let xpath = read_str("book/price/..").unwrap();
+
+        let result = evaluate_path(&make_catalog_context(), &xpath);
+        check_result_nodes(result, 12, NodeType::Element);
+    }
+
+    #[test]
+    fn test_reverse_axis_from_single_node_in_document_order() {
+        let xpath = read_str("book[4]/preceding-sibling::book[position() < 3]").unwrap();
+
+        let result = evaluate_path(&make_catalog_context(), &xpath);
+        check_result_ids(result, &["bk102", "bk103"]);
+    }
+
+    #[test]
+    fn test_union_in_document_order() {
+        let book = |position: i32| {
+            Predicate::path(make_path_with_predicates(
+                Step::child_elements("book"),
+                &[Predicate::integer(position)],
+            ))
+        };
+        let xpath = Expr::with(Predicate::union(
+            Predicate::union(book(3), book(1)),
+            book(3),
+        ));
+
+        let result = evaluate_expr(&make_catalog_context(), &xpath);
+        check_result_ids(result, &["bk101", "bk103"]);
+    }
+
+    #[test]
+    fn test_reverse_axis_proximity() {
+        let third_book = || {
+            let mut step = Step::child_elements("book");
+            step.append(Predicate::integer(3));
+            step
+        };
+        let preceding_book = |position: Option<i32>| {
+            let mut xpath = LocationPath::with(third_book());
+            xpath.append({
+                let mut step = Step::with(Select::with(
+                    AxisSpecifier::PrecedingSibling,
+                    NodeTest::Named(QName::with("book")),
+                ));
+                if let Some(position) = position {
+                    step.append(Predicate::integer(position));
+                }
+                step
+            });
+            xpath
+        };
+
+        let result = evaluate_path(&make_catalog_context(), &preceding_book(Some(1)));
+        check_result_ids(result, &["bk102"]);
+
+        let result = evaluate_path(&make_catalog_context(), &preceding_book(Some(2)));
+        check_result_ids(result, &["bk101"]);
+
+        let result = evaluate_path(&make_catalog_context(), &preceding_book(None));
+        check_result_ids(result, &["bk101", "bk102"]);
+
+        let mut xpath = LocationPath::with(third_book());
+        xpath.append(Step::child_elements("price"));
+        xpath.append({
+            let mut step = Step::with(Select::with(AxisSpecifier::AncestorOrSelf, NodeTest::All));
+            step.append(Predicate::integer(2));
+            step
+        });
+        let result = evaluate_path(&make_catalog_context(), &xpath);
+        check_result_ids(result, &["bk103"]);
+    }
+
+    // --------------------------------------------------------------------------------------------
+
+    fn check_boolean(context: &EvaluationContext, expr: &str, expected: bool) {
+        let result = evaluate_expr(context, &read_expr(expr).unwrap());
+        assert!(
+            matches!(result, Ok(XPathObject::Boolean(value)) if value == expected),
+            "{} => {:?}",
+            expr,
+            result
+        );
+    }
+
+    #[test]
+    fn test_compare_node_set_and_number() {
+        let context = make_catalog_context();
+        check_boolean(&context, "book/price > 40", true);
+        check_boolean(&context, "book/price > 50", false);
+        check_boolean(&context, "book/price = 5.95", true);
+        check_boolean(&context, "book/price != 5.95", true);
+        check_boolean(&context, "10 < book/price", true);
+        check_boolean(&context, "4.95 >= book/price", true);
+        check_boolean(&context, "4 >= book/price", false);
+        check_boolean(&context, "book/isbn = 0", false);
+        check_boolean(&context, "book/isbn != 0", false);
+    }
+
+    #[test]
+    fn test_compare_node_set_and_string() {
+        let context = make_catalog_context();
+        check_boolean(&context, "book/genre = 'Horror'", true);
+        check_boolean(&context, "'Western' = book/genre", false);
+        check_boolean(&context, "book/genre != 'Computer'", true);
+        check_boolean(&context, "book/price < '5'", true);
+    }
+
+    #[test]
+    fn test_compare_node_set_and_boolean() {
+        let context = make_catalog_context();
+        check_boolean(&context, "book = true()", true);
+        check_boolean(&context, "book/isbn = false()", true);
+        check_boolean(&context, "true() != book/isbn", true);
+        check_boolean(&context, "book/isbn < true()", true);
+    }
+
+    #[test]
+    fn test_compare_node_sets() {
+        let context = make_catalog_context();
+        check_boolean(&context, "book/genre = book/genre", true);
+        check_boolean(&context, "book/genre != book/genre", true);
+        check_boolean(&context, "book/price > book/price", true);
+        check_boolean(&context, "book/isbn = book/genre", false);
+        check_boolean(&context, "book/isbn != book/genre", false);
+    }
+
+    #[test]
+    fn test_compare_values() {
+        let context = EvaluationContext::default();
+        check_boolean(&context, "1 = 1.0", true);
+        check_boolean(&context, "'1' = 1", true);
+        check_boolean(&context, "'1.0' = '1'", false);
+        check_boolean(&context, "true() = 2", true);
+        check_boolean(&context, "false() = ''", true);
+        check_boolean(&context, "'abc' = 'abc'", true);
+        check_boolean(&context, "'abc' != 'abd'", true);
+        check_boolean(&context, "'abc' < 'abd'", false);
+        check_boolean(&context, "'2' < '10'", true);
+        check_boolean(&context, "true() > false()", true);
+        check_boolean(&context, "1 <= 1", true);
+        check_boolean(&context, "number('x') = number('x')", false);
+        check_boolean(&context, "number('x') != number('x')", true);
+        check_boolean(&context, "number('x') < 1", false);
+        check_boolean(&context, "number('x') >= 1", false);
+    }
+
+    #[test]
+    fn test_compare_in_predicate() {
+        let xpath = read_str("book[price > 40]").unwrap();
+        let result = evaluate_path(&make_catalog_context(), &xpath);
+        check_result_ids(result, &["bk101", "bk112"]);
+
+        let xpath = read_str("book[genre = 'Romance']").unwrap();
+        let result = evaluate_path(&make_catalog_context(), &xpath);
+        check_result_ids(result, &["bk106", "bk107"]);
+
+        let xpath = read_str("book[2]").unwrap();
+        let result = evaluate_path(&make_catalog_context(), &xpath);
+        check_result_ids(result, &["bk102"]);
+    }
+
+    fn check_number(context: &EvaluationContext, expr: &str, expected: f64) {
+        let result = evaluate_expr(context, &read_expr(expr).unwrap());
+        match result {
+            Ok(XPathObject::Number(value)) => assert!(
+                (value.is_nan() && expected.is_nan())
+                    || (value == expected
+                        && value.is_sign_negative() == expected.is_sign_negative()),
+                "{} => {}, expecting {}",
+                expr,
+                value,
+                expected
+            ),
+            _ => panic!("{} => {:?}", expr, result),
+        }
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        let context = EvaluationContext::default();
+        check_number(&context, "1 + 2", 3.0);
+        check_number(&context, "'1' + true()", 2.0);
+        check_number(&context, "3 - 5", -2.0);
+        check_number(&context, "2 * 3.5", 7.0);
+        check_number(&context, "7 div 2", 3.5);
+        check_number(&context, "- 2", -2.0);
+        check_number(&context, "- - 2", 2.0);
+        check_number(&context, "- 0", -0.0);
+        check_number(&context, "1 + 'x'", f64::NAN);
+    }
+
+    #[test]
+    fn test_arithmetic_ieee_754() {
+        let context = EvaluationContext::default();
+        check_number(&context, "1 div 0", f64::INFINITY);
+        check_number(&context, "-1 div 0", f64::NEG_INFINITY);
+        check_number(&context, "1 div -0", f64::NEG_INFINITY);
+        check_number(&context, "0 div 0", f64::NAN);
+        check_number(&context, "(1 div 0) - (1 div 0)", f64::NAN);
+        check_number(&context, "0 * -1", -0.0);
+    }
+
+    #[test]
+    fn test_modulus() {
+        let context = EvaluationContext::default();
+        check_number(&context, "5 mod 2", 1.0);
+        check_number(&context, "5 mod -2", 1.0);
+        check_number(&context, "-5 mod 2", -1.0);
+        check_number(&context, "-5 mod -2", -1.0);
+        check_number(&context, "5.5 mod 2", 1.5);
+        check_number(&context, "5 mod 0", f64::NAN);
+        check_number(&context, "5 mod (1 div 0)", 5.0);
+    }
+
+    #[test]
+    fn test_arithmetic_on_node_sets() {
+        let context = make_catalog_context();
+        check_number(&context, "book[1]/price * 2", 89.9);
+        check_number(&context, "book/price + 1", 45.95);
+        check_number(&context, "book/isbn + 1", f64::NAN);
+    }
+
+    #[test]
+    fn test_and_or() {
+        let context = make_catalog_context();
+        check_boolean(&context, "true() and true()", true);
+        check_boolean(&context, "true() and 0", false);
+        check_boolean(&context, "false() or 'x'", true);
+        check_boolean(&context, "book and book/isbn", false);
+        check_boolean(&context, "book/isbn or book/price > 40", true);
+    }
+
+    #[test]
+    fn test_and_or_short_circuit() {
+        // The right operand is an error, an unbound variable, if evaluated.
+        let context = EvaluationContext::default();
+        check_boolean(&context, "false() and $undefined", false);
+        check_boolean(&context, "true() or $undefined", true);
+
+        let result = evaluate_expr(&context, &read_expr("true() and $undefined").unwrap());
+        assert!(matches!(result, Err(EvaluationError::UnboundVariable(_))));
+    }
+
+    // --------------------------------------------------------------------------------------------
+
+    #[test]
+    fn test_context_variable() {
+        let mut context = make_catalog_context();
+        context.bind_variable(QName::with("id"), XPathObject::String("bk101".to_string()));
+        let xpath = read_expr("$id").unwrap();
+
+        let result = evaluate_expr(&context, &xpath);
+        assert!(matches!(result, Ok(XPathObject::String(s)) if s == "bk101"));
+    }
+
+    #[test]
+    fn test_context_unbound_variable() {
+        let xpath = read_expr("$id").unwrap();
+
+        let result = evaluate_expr(&make_catalog_context(), &xpath);
+        assert_eq!(
+            result.err(),
+            Some(EvaluationError::UnboundVariable("id".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_context_variable_in_predicate() {
+        let mut context = make_catalog_context();
+        context.bind_variable(QName::with("flag"), XPathObject::Boolean(false));
+        let xpath = read_str("child::book[$flag]").unwrap();
+
+        let result = evaluate_path(&context, &xpath);
+        check_result_nodes(result, 0, NodeType::Element);
+    }
+
+    struct PrefixedResolver;
+
+    impl VariableResolver for PrefixedResolver {
+        fn resolve_variable(&self, name: &QName) -> Option<XPathObject> {
+            name.prefix()
+                .map(|prefix| XPathObject::String(format!("{}-{}", prefix, name.local_name())))
+        }
+    }
+
+    #[test]
+    fn test_context_variable_resolver() {
+        let mut variables: HashMap<QName, XPathObject> = HashMap::new();
+        let _ = variables.insert(QName::with("flag"), XPathObject::Boolean(true));
+        let mut context = make_catalog_context();
+        context.set_variable_resolver(variables);
+
+        let result = evaluate_path(&context, &read_str("child::book[$flag]").unwrap());
+        check_result_nodes(result, 12, NodeType::Element);
+
+        context.bind_variable(QName::with("flag"), XPathObject::Boolean(false));
+
+        let result = evaluate_path(&context, &read_str("child::book[$flag]").unwrap());
         check_result_nodes(result, 0, NodeType::Element);
     }
+
+    #[test]
+    fn test_context_custom_variable_resolver() {
+        let mut context = make_catalog_context();
+        context.set_variable_resolver(PrefixedResolver);
+
+        let result = evaluate_expr(&context, &read_expr("$ord:id").unwrap());
+        assert!(matches!(result, Ok(XPathObject::String(s)) if s == "ord-id"));
+
+        let result = evaluate_expr(&context, &read_expr("$id").unwrap());
+        assert_eq!(
+            result.err(),
+            Some(EvaluationError::UnboundVariable("id".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_context_function_position() {
+        let xpath = read_str("child::book[last()]").unwrap();
+
+        let result = evaluate_path(&make_catalog_context(), &xpath);
+        check_result_ids(result, &["bk112"]);
+    }
+
+    const EXAMPLE_NS: &str = "http://example.org/functions";
+
+    fn register_upper_case(functions: &mut FunctionRegistry, namespace_uri: Option<&str>) {
+        functions.register(
+            namespace_uri,
+            Function::with(
+                "upper-case",
+                &[Argument::with("value", DataType::String, true)],
+                DataType::String,
+            ),
+            |_, arguments| match arguments {
+                [XPathObject::String(value)] => Ok(XPathObject::String(value.to_uppercase())),
+                _ => unreachable!(),
+            },
+        );
+    }
+
+    #[test]
+    fn test_context_extension_function() {
+        let mut functions = FunctionRegistry::default();
+        register_upper_case(&mut functions, Some(EXAMPLE_NS));
+        let mut context = make_catalog_context();
+        context
+            .set_functions(functions)
+            .add_namespace("ex", EXAMPLE_NS);
+
+        let result = evaluate_expr(&context, &read_expr("ex:upper-case('bk101')").unwrap());
+        assert!(matches!(result, Ok(XPathObject::String(s)) if s == "BK101"));
+
+        let result = evaluate_expr(&context, &read_expr("ex:upper-case(true())").unwrap());
+        assert!(matches!(result, Ok(XPathObject::String(s)) if s == "TRUE"));
+    }
+
+    #[test]
+    fn test_context_extension_function_errors() {
+        let mut context = make_catalog_context();
+        context.register_function(
+            Some(EXAMPLE_NS),
+            Function::with("always", &[], DataType::Bool),
+            |_, _| Ok(XPathObject::Boolean(true)),
+        );
+
+        let xpath = read_expr("ex:always()").unwrap();
+        let result = evaluate_expr(&context, &xpath);
+        assert_eq!(
+            result.err(),
+            Some(EvaluationError::UnknownNamespacePrefix {
+                prefix: "ex".to_string(),
+                expr: "ex:always()".to_string(),
+            })
+        );
+
+        context.add_namespace("ex", "http://example.org/other");
+        let result = evaluate_expr(&context, &xpath);
+        assert_eq!(
+            result.err(),
+            Some(EvaluationError::UnknownFunction("ex:always()".to_string()))
+        );
+
+        context.add_namespace("ex", EXAMPLE_NS);
+        let result = evaluate_expr(&context, &xpath);
+        assert!(matches!(result, Ok(XPathObject::Boolean(true))));
+
+        let xpath = read_expr("ex:always(1)").unwrap();
+        let result = evaluate_expr(&context, &xpath);
+        assert_eq!(
+            result.err(),
+            Some(EvaluationError::WrongArity("ex:always(1)".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_context_extension_function_optional_arguments() {
+        let mut context = make_catalog_context();
+        context.add_namespace("ex", EXAMPLE_NS);
+        context.register_function(
+            Some(EXAMPLE_NS),
+            Function::with(
+                "arguments",
+                &[
+                    Argument::with("value", DataType::String, false),
+                    Argument::with_context_default("node", DataType::String),
+                ],
+                DataType::Number,
+            ),
+            |_, arguments| Ok(XPathObject::Number(arguments.len() as f64)),
+        );
+        let result = evaluate_expr(&context, &read_expr("ex:arguments()").unwrap());
+        assert_eq!(result, Ok(XPathObject::Number(0.0)));
+
+        context.register_function(
+            Some(EXAMPLE_NS),
+            Function::with(
+                "arguments",
+                &[Argument::with_context_default("node", DataType::String)],
+                DataType::Number,
+            ),
+            |_, arguments| Ok(XPathObject::Number(arguments.len() as f64)),
+        );
+        let result = evaluate_expr(&context, &read_expr("ex:arguments()").unwrap());
+        assert_eq!(result, Ok(XPathObject::Number(1.0)));
+    }
+
+    #[test]
+    fn test_context_unprefixed_extension_function() {
+        let mut functions = FunctionRegistry::default();
+        register_upper_case(&mut functions, None);
+
+        assert_eq!(
+            read_expr("upper-case('bk101')").err(),
+            Some(ParseError::UnknownFunction("upper-case".to_string()))
+        );
+        let xpath = read_expr_with_functions("upper-case('bk101')", &functions).unwrap();
+
+        let mut context = make_catalog_context();
+        context.set_functions(functions);
+        let result = evaluate_expr(&context, &xpath);
+        assert!(matches!(result, Ok(XPathObject::String(s)) if s == "BK101"));
+    }
+
+    #[test]
+    fn test_context_extension_function_result_type() {
+        let mut context = make_catalog_context();
+        context
+            .register_function(
+                Some(EXAMPLE_NS),
+                Function::with("count-books", &[], DataType::Number),
+                |_, _| Ok(XPathObject::String("twelve".to_string())),
+            )
+            .add_namespace("ex", EXAMPLE_NS);
+
+        let result = evaluate_expr(&context, &read_expr("ex:count-books()").unwrap());
+        assert_eq!(
+            result.err(),
+            Some(EvaluationError::TypeError {
+                expr: "ex:count-books()".to_string(),
+                expected: DataType::Number,
+                found: DataType::String,
+            })
+        );
+    }
+
+    #[test]
+    fn test_unknown_function() {
+        // The parser rejects calls to unknown functions, so the model is constructed directly.
+        let result = evaluate_predicate(
+            &make_catalog_context(),
+            &Predicate::function_with("upper-case", &[Predicate::literal("bk101")]),
+        );
+        assert_eq!(
+            result.err(),
+            Some(EvaluationError::UnknownFunction(
+                "upper-case('bk101')".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_node_limit() {
+        let mut context = make_catalog_context();
+        context.set_node_limit(12);
+        check_result_count(&context, "child::book", 12);
+
+        context.set_node_limit(11);
+        let xpath = read_str("child::book/child::title").unwrap();
+        let expected = EvaluationError::NodeLimitExceeded {
+            expr: "child::book".to_string(),
+            limit: 11,
+        };
+        assert_eq!(
+            evaluate_path(&context, &xpath).err(),
+            Some(expected.clone())
+        );
+        assert_eq!(
+            iter_path(&context, &xpath).find_map(Result::err),
+            Some(expected)
+        );
+        assert!(iter_path(&context, &read_str("child::book[1]").unwrap()).all(|r| r.is_ok()));
+
+        context.set_node_limit(3);
+        let result = evaluate_expr(
+            &context,
+            &read_expr("child::book[1] | child::book[2] | child::book[3] | child::book[4]")
+                .unwrap(),
+        );
+        assert!(matches!(
+            result.err(),
+            Some(EvaluationError::NodeLimitExceeded { limit: 3, .. })
+        ));
+    }
+
+    #[test]
+    fn test_error_display() {
+        let errors = [
+            (
+                EvaluationError::TypeError {
+                    expr: "42".to_string(),
+                    expected: DataType::NodeSet,
+                    found: DataType::Number,
+                },
+                "The expression '42' evaluated to a number where a node-set is required.",
+            ),
+            (
+                EvaluationError::UnknownFunction("upper-case('a')".to_string()),
+                "The function called in 'upper-case('a')' is not known.",
+            ),
+            (
+                EvaluationError::WrongArity("count()".to_string()),
+                "The function call 'count()' has the wrong number of arguments.",
+            ),
+            (
+                EvaluationError::UnboundVariable("id".to_string()),
+                "The variable '$id' has no value bound.",
+            ),
+            (
+                EvaluationError::UnknownNamespacePrefix {
+                    prefix: "soap".to_string(),
+                    expr: "child::soap:Body".to_string(),
+                },
+                "The namespace prefix 'soap' used in 'child::soap:Body' is not declared.",
+            ),
+            (
+                EvaluationError::UnknownNamespacePrefix {
+                    prefix: "child".to_string(),
+                    expr: "child::child:para".to_string(),
+                },
+                "The namespace prefix 'child' used in 'child::child:para' is not declared, an \
+                 axis name is followed by '::', did you mean 'child::'?",
+            ),
+            (
+                EvaluationError::NodeLimitExceeded {
+                    expr: "child::book".to_string(),
+                    limit: 10,
+                },
+                "The expression 'child::book' selected more than the limit of 10 nodes.",
+            ),
+        ];
+        for (error, message) in &errors {
+            assert_eq!(error.to_string(), *message);
+        }
+    }
+
+    // --------------------------------------------------------------------------------------------
+
+    const SOAP_NS: &str = "http://schemas.xmlsoap.org/soap/envelope/";
+
+    const SOAP_XML: &str = r#"<?xml version="1.0"?>
+<env:Envelope xmlns:env="http://schemas.xmlsoap.org/soap/envelope/" xmlns="http://example.org/orders">
+  <env:Header/>
+  <env:Body env:encodingStyle="http://example.org/encoding" id="b1">
+    <order xmlns="" id="o1"/>
+    <o:order xmlns:o="http://example.org/orders" id="o2"/>
+  </env:Body>
+</env:Envelope>"#;
+
+    fn make_soap_context() -> EvaluationContext {
+        EvaluationContext::from(read_xml(SOAP_XML).unwrap())
+    }
+
+    fn check_result_count(context: &EvaluationContext, xpath: &str, count: usize) {
+        match evaluate_path(context, &read_str(xpath).unwrap()) {
+            Ok(XPathObject::NodeSet(node_set)) => assert_eq!(node_set.len(), count, "{}", xpath),
+            _ => panic!("Expecting a node set from {}", xpath),
+        }
+    }
+
+    #[test]
+    fn test_namespace_name_test() {
+        let mut context = make_soap_context();
+        context
+            .add_namespace("soap", SOAP_NS)
+            .add_namespace("env", "http://example.org/other");
+
+        check_result_count(&context, "child::soap:Envelope", 1);
+        check_result_count(&context, "child::env:Envelope", 0);
+        check_result_count(&context, "descendant::soap:Body", 1);
+        check_result_count(&context, "descendant::soap:*", 3);
+        check_result_count(
+            &context,
+            "descendant::soap:Body[attribute::soap:encodingStyle]",
+            1,
+        );
+        check_result_count(&context, "descendant::soap:Body[attribute::id]", 1);
+        check_result_count(&context, "descendant::soap:Body[attribute::soap:id]", 0);
+    }
+
+    #[test]
+    fn test_namespace_unprefixed_name_test() {
+        // An unprefixed name test never uses the default namespace of the document.
+        let mut context = make_soap_context();
+        context.add_namespace("ord", "http://example.org/orders");
+
+        check_result_count(&context, "descendant::order", 1);
+        check_result_count(&context, "descendant::ord:order", 1);
+        check_result_count(&context, "descendant::ord:*", 1);
+        check_result_count(&context, "descendant::Envelope", 0);
+    }
+
+    #[test]
+    fn test_namespace_unknown_prefix() {
+        let context = make_soap_context();
+        let result = evaluate_path(&context, &read_str("descendant::soap:Body").unwrap());
+        assert_eq!(
+            result.err(),
+            Some(EvaluationError::UnknownNamespacePrefix {
+                prefix: "soap".to_string(),
+                expr: "soap:Body".to_string(),
+            })
+        );
+
+        let result = evaluate_path(&context, &read_str("child:para").unwrap());
+        assert!(result
+            .err()
+            .unwrap()
+            .to_string()
+            .ends_with("did you mean 'child::'?"));
+    }
+
+    #[test]
+    fn test_namespace_resolver() {
+        let document_node = read_xml(SOAP_XML).unwrap();
+        let mut context = EvaluationContext::from(&document_node);
+        context.set_namespace_resolver(NodeNamespaceResolver::from(
+            document_node.first_child().unwrap(),
+        ));
+        check_result_count(&context, "descendant::env:Body", 1);
+
+        let mut namespaces: HashMap<String, String> = HashMap::new();
+        let _ = namespaces.insert("env".to_string(), "http://example.org/other".to_string());
+        context.set_namespace_resolver(namespaces);
+        check_result_count(&context, "descendant::env:Body", 0);
+
+        context.add_namespace("env", SOAP_NS);
+        check_result_count(&context, "descendant::env:Body", 1);
+    }
 }
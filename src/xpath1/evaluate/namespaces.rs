@@ -0,0 +1,194 @@
+/*!
+The trait `NamespaceResolver` provides namespace URIs for the prefixes used in an expression.
+*/
+
+use std::collections::HashMap;
+use xml_dom::level2::convert::as_attribute;
+use xml_dom::level2::{Node, NodeType, RefNode};
+
+// ------------------------------------------------------------------------------------------------
+// Public Types
+// ------------------------------------------------------------------------------------------------
+
+///
+/// Provides the namespace URI declared for a prefix, when a qualified name such as `soap:Body`,
+/// `soap:*`, or `ex:upper-case()` is evaluated. If the resolver returns `None` the evaluator will
+/// report an
+/// [`EvaluationError::UnknownNamespacePrefix`](enum.EvaluationError.html#variant.UnknownNamespacePrefix)
+/// error.
+///
+/// # Example
+///
+/// ```rust
+/// use xml_dom_xpath::xpath1::evaluate::{EvaluationContext, NamespaceResolver};
+/// use std::collections::HashMap;
+///
+/// let mut namespaces: HashMap<String, String> = HashMap::new();
+/// let _ = namespaces.insert(
+///     "soap".to_string(),
+///     "http://schemas.xmlsoap.org/soap/envelope/".to_string(),
+/// );
+/// assert!(namespaces.resolve_namespace("soap").is_some());
+///
+/// let mut context = EvaluationContext::default();
+/// context.set_namespace_resolver(namespaces);
+/// assert_eq!(
+///     context.namespace_uri("soap").as_deref(),
+///     Some("http://schemas.xmlsoap.org/soap/envelope/")
+/// );
+/// ```
+///
+pub trait NamespaceResolver {
+    /// Return the namespace URI declared for `prefix`, if any.
+    fn resolve_namespace(&self, prefix: &str) -> Option<String>;
+}
+
+///
+/// A resolver that uses the namespace declarations in scope for a DOM node; that is, the `xmlns`
+/// attributes on the node itself, if it is an element, and on each of its ancestors, with the
+/// nearest declaration of a prefix taking precedence.
+///
+/// # Example
+///
+/// ```rust
+/// use xml_dom_xpath::xpath1::evaluate::{NamespaceResolver, NodeNamespaceResolver};
+/// use xml_dom::level2::Node;
+/// use xml_dom::parser::read_xml;
+///
+/// let document_node = read_xml(
+///     r#"<s:Envelope xmlns:s="http://schemas.xmlsoap.org/soap/envelope/"><s:Body/></s:Envelope>"#,
+/// )
+/// .unwrap();
+/// let body = document_node.first_child().unwrap().first_child().unwrap();
+///
+/// let resolver = NodeNamespaceResolver::from(body);
+/// assert_eq!(
+///     resolver.resolve_namespace("s").as_deref(),
+///     Some("http://schemas.xmlsoap.org/soap/envelope/")
+/// );
+/// assert_eq!(resolver.resolve_namespace("soap"), None);
+/// ```
+///
+#[derive(Clone, Debug)]
+pub struct NodeNamespaceResolver {
+    node: RefNode,
+}
+
+//
+// The namespace URI bound to the prefix `xml` by definition, see
+// [Namespaces in XML 1.0](https://www.w3.org/TR/REC-xml-names/#xmlReserved).
+//
+pub(crate) const XML_NAMESPACE_URI: &str = "http://www.w3.org/XML/1998/namespace";
+
+// ------------------------------------------------------------------------------------------------
+// Public Functions
+// ------------------------------------------------------------------------------------------------
+
+///
+/// Return the expanded name of `node`, the pair of namespace URI and local name. Unless the DOM
+/// has already recorded a namespace URI on the node's name, a prefix is resolved using the
+/// declarations in scope for the node; an unprefixed element name is in the default namespace,
+/// if one is declared, while an unprefixed attribute name is never in a namespace.
+///
+pub(crate) fn expanded_name(node: &RefNode) -> (Option<String>, String) {
+    let name = node.node_name();
+    let local_name = name.local_name().clone();
+    if let Some(namespace_uri) = name.namespace_uri() {
+        return (Some(namespace_uri.clone()), local_name);
+    }
+    let namespace_uri = match (node.node_type(), name.prefix()) {
+        (NodeType::Element, prefix) => in_scope_namespace(Some(node.clone()), prefix.as_deref()),
+        (NodeType::Attribute, Some(prefix)) => {
+            in_scope_namespace(owner_element(node), Some(prefix.as_str()))
+        }
+        _ => None,
+    };
+    (namespace_uri, local_name)
+}
+
+// ------------------------------------------------------------------------------------------------
+// Implementations
+// ------------------------------------------------------------------------------------------------
+
+impl NamespaceResolver for HashMap<String, String> {
+    fn resolve_namespace(&self, prefix: &str) -> Option<String> {
+        self.get(prefix).cloned()
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+
+impl NamespaceResolver for NodeNamespaceResolver {
+    fn resolve_namespace(&self, prefix: &str) -> Option<String> {
+        let element = match self.node.node_type() {
+            NodeType::Attribute => owner_element(&self.node),
+            _ => Some(self.node.clone()),
+        };
+        in_scope_namespace(element, Some(prefix))
+    }
+}
+
+impl From<RefNode> for NodeNamespaceResolver {
+    fn from(node: RefNode) -> Self {
+        Self { node }
+    }
+}
+
+impl From<&RefNode> for NodeNamespaceResolver {
+    fn from(node: &RefNode) -> Self {
+        Self { node: node.clone() }
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+// Private Functions
+// ------------------------------------------------------------------------------------------------
+
+fn owner_element(node: &RefNode) -> Option<RefNode> {
+    as_attribute(node)
+        .ok()
+        .and_then(|attribute| attribute.owner_element())
+}
+
+//
+// Walk from `node` to the root looking for the nearest declaration of `prefix`, or of the default
+// namespace where `prefix` is `None`. An empty default namespace declaration, `xmlns=""`, undoes
+// any default namespace declared by an ancestor.
+//
+fn in_scope_namespace(node: Option<RefNode>, prefix: Option<&str>) -> Option<String> {
+    if prefix == Some("xml") {
+        return Some(XML_NAMESPACE_URI.to_string());
+    }
+    let mut next = node;
+    while let Some(node) = next {
+        if node.node_type() == NodeType::Element {
+            let declared = node.attributes().into_iter().find_map(|(name, attribute)| {
+                let declares = match prefix {
+                    None => name.prefix().is_none() && name.local_name() == "xmlns",
+                    Some(prefix) => {
+                        name.prefix().as_deref() == Some("xmlns") && name.local_name() == prefix
+                    }
+                };
+                if declares {
+                    Some(
+                        as_attribute(&attribute)
+                            .ok()
+                            .and_then(|attribute| attribute.value())
+                            .unwrap_or_default(),
+                    )
+                } else {
+                    None
+                }
+            });
+            if let Some(namespace_uri) = declared {
+                return if namespace_uri.is_empty() {
+                    None
+                } else {
+                    Some(namespace_uri)
+                };
+            }
+        }
+        next = node.parent_node();
+    }
+    None
+}
@@ -1,28 +1,72 @@
 /*!
 The type `NodeSet` represents both the input to, and output from, each step in a path.
-
-
-
 */
 
+use crate::xpath1::evaluate::axes::{following_siblings, Descendants, Following};
+use std::cmp::Ordering;
 use std::collections::vec_deque::Iter;
-use std::collections::VecDeque;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::iter::FromIterator;
-use xml_dom::level2::{Node, NodeType, RefNode};
+use std::rc::Rc;
+use xml_dom::level2::convert::as_attribute;
+use xml_dom::level2::{Name, Node, NodeType, RefNode};
 
 // ------------------------------------------------------------------------------------------------
 // Public Types
 // ------------------------------------------------------------------------------------------------
 
 ///
-/// A container of DOM nodes. This is not truly a set, there may be duplicates and the order of
-/// items is defined by the individual axis functions, by default it is document order. The name
-/// reflects the type introduced in the XPath 1.0 specification, §3.3
+/// A container of DOM nodes, without duplicates; two nodes are the same node only if they are
+/// the identical DOM node, not simply equal in content. The result of each axis function, and of
+/// evaluating a path or a union, is in document order as defined by
+/// [`compare_document_order`](fn.compare_document_order.html). The name reflects the type
+/// introduced in the XPath 1.0 specification, §3.3
 /// [Node Sets](https://www.w3.org/TR/xpath-10/#node-sets).
 ///
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, Default, PartialEq)]
 pub struct NodeSet(VecDeque<RefNode>);
 
+// ------------------------------------------------------------------------------------------------
+// Public Functions
+// ------------------------------------------------------------------------------------------------
+
+///
+/// Compare two nodes by their position in document order, as defined in §5
+/// [Data Model](https://www.w3.org/TR/xpath-10/#data-model); a node is before its descendants, the
+/// namespace nodes of an element are before its attribute nodes, and both are after the element
+/// and before its children.
+///
+/// The relative order of the attribute (or namespace) nodes of a single element is by name, and
+/// the relative order of nodes in different documents is consistent, but otherwise arbitrary; in
+/// both cases this is implementation-dependent in the specification.
+///
+/// # Example
+///
+/// ```rust
+/// use xml_dom_xpath::xpath1::evaluate::compare_document_order;
+/// use xml_dom::level2::Node;
+/// use xml_dom::parser::read_xml;
+/// use std::cmp::Ordering;
+///
+/// let document_node = read_xml(r#"<a><b/><c/></a>"#).unwrap();
+/// let a = document_node.first_child().unwrap();
+/// let b = a.first_child().unwrap();
+/// let c = a.last_child().unwrap();
+///
+/// assert_eq!(compare_document_order(&a, &b), Ordering::Less);
+/// assert_eq!(compare_document_order(&c, &b), Ordering::Greater);
+/// assert_eq!(compare_document_order(&b, &b), Ordering::Equal);
+/// ```
+///
+pub fn compare_document_order(lhs: &RefNode, rhs: &RefNode) -> Ordering {
+    if lhs == rhs {
+        Ordering::Equal
+    } else {
+        let mut child_indices = ChildIndices::default();
+        document_position(lhs, &mut child_indices).cmp(&document_position(rhs, &mut child_indices))
+    }
+}
+
 // ------------------------------------------------------------------------------------------------
 // Macros
 // ------------------------------------------------------------------------------------------------
@@ -42,14 +86,6 @@ macro_rules! veq {
 // Implementations
 // ------------------------------------------------------------------------------------------------
 
-impl Default for NodeSet {
-    fn default() -> Self {
-        Self(Default::default())
-    }
-}
-
-// ------------------------------------------------------------------------------------------------
-
 impl From<RefNode> for NodeSet {
     fn from(node: RefNode) -> Self {
         Self(veq![node])
@@ -67,8 +103,27 @@ impl From<&RefNode> for NodeSet {
 // ------------------------------------------------------------------------------------------------
 
 impl FromIterator<RefNode> for NodeSet {
+    ///
+    /// Collect the nodes of `iter`, in order, skipping any node already collected.
+    ///
     fn from_iter<T: IntoIterator<Item = RefNode>>(iter: T) -> Self {
-        Self(VecDeque::from_iter(iter))
+        let mut seen: HashSet<usize> = HashSet::new();
+        Self(
+            iter.into_iter()
+                .filter(|node| seen.insert(node_identity(node)))
+                .collect(),
+        )
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+
+impl IntoIterator for NodeSet {
+    type Item = RefNode;
+    type IntoIter = std::collections::vec_deque::IntoIter<RefNode>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
     }
 }
 
@@ -91,6 +146,10 @@ impl NodeSet {
     /// The `ancestor` axis contains the ancestors of the context node; the ancestors of the context
     /// node consist of the parent of context node and the parent's parent and so on; thus, the
     /// ancestor axis will always include the root node, unless the context node is the root node.
+    /// The parent of an attribute or namespace node is the element that contains it.
+    ///
+    /// This is a reverse axis; the result is in document order, the positions shown below are the
+    /// proximity positions used when evaluating a predicate on this axis.
     ///
     /// # Result
     ///
@@ -110,20 +169,23 @@ impl NodeSet {
     pub fn ancestor(&self) -> Self {
         fn parents(node: &RefNode) -> VecDeque<RefNode> {
             let mut result = NodeSet::inner_new();
-            let mut next = node.parent_node();
+            let mut next = parent_of(node);
             while let Some(node) = next {
-                result.push_back(node.clone());
-                next = node.parent_node();
+                next = parent_of(&node);
+                result.push_front(node);
             }
             result
         }
-        Self(self.iter().map(parents).flatten().collect())
+        self.axis_result(self.iter().flat_map(parents))
     }
 
     ///
     /// The `ancestor-or-self` axis contains the context node and the ancestors of the context node;
     /// thus, the ancestor axis will always include the root node.
     ///
+    /// This is a reverse axis; the result is in document order, the positions shown below are the
+    /// proximity positions used when evaluating a predicate on this axis.
+    ///
     /// # Result
     ///
     /// ```text
@@ -140,17 +202,11 @@ impl NodeSet {
     /// ```
     ///
     pub fn ancestor_or_self(&self) -> Self {
-        fn parents(node: &RefNode) -> VecDeque<RefNode> {
-            let mut result = NodeSet::inner_new();
+        self.axis_result(self.iter().flat_map(|node| {
+            let mut result = NodeSet::from(node).ancestor().into_inner();
             result.push_back(node.clone());
-            let mut next = node.parent_node();
-            while let Some(node) = next {
-                result.push_back(node.clone());
-                next = node.parent_node();
-            }
             result
-        }
-        Self(self.iter().map(parents).flatten().collect())
+        }))
     }
 
     ///
@@ -161,28 +217,9 @@ impl NodeSet {
     /// is 'xmlns'.
     ///
     pub fn attribute(&self) -> Self {
-        Self(
+        self.axis_result(
             self.iter()
-                .filter_map(|node| {
-                    if node.node_type() == NodeType::Element {
-                        let attribute_hash = node.attributes();
-                        let attribute_nodes: VecDeque<RefNode> = attribute_hash
-                            .iter()
-                            .filter_map(|(name, node)| {
-                                if name.is_namespace_attribute() {
-                                    None
-                                } else {
-                                    Some(node.clone())
-                                }
-                            })
-                            .collect();
-                        Some(attribute_nodes)
-                    } else {
-                        None
-                    }
-                })
-                .flatten()
-                .collect(),
+                .flat_map(|node| attribute_nodes(node, |name| !is_namespace_declaration(name))),
         )
     }
 
@@ -205,17 +242,7 @@ impl NodeSet {
     /// ```
     ///
     pub fn child(&self) -> Self {
-        Self(
-            self.iter()
-                .map(|node| {
-                    node.child_nodes()
-                        .iter()
-                        .cloned()
-                        .collect::<VecDeque<RefNode>>()
-                })
-                .flatten()
-                .collect(),
-        )
+        self.axis_result(self.iter().flat_map(|node| node.child_nodes()))
     }
 
     ///
@@ -239,14 +266,7 @@ impl NodeSet {
     /// ```
     ///
     pub fn descendant(&self) -> Self {
-        let mut descendants = self.child();
-        let mut next = descendants
-            .iter()
-            .map(|node| NodeSet::from(node).descendant().into_inner())
-            .flatten()
-            .collect::<VecDeque<RefNode>>();
-        descendants.append(&mut next);
-        descendants
+        self.axis_result(self.iter().flat_map(|node| Descendants::new(node, false)))
     }
 
     ///
@@ -269,18 +289,12 @@ impl NodeSet {
     /// ```
     ///
     pub fn descendant_or_self(&self) -> Self {
-        let mut descendants = self.self_node();
-        let mut next = descendants
-            .iter()
-            .map(|node| NodeSet::from(node).descendant().into_inner())
-            .flatten()
-            .collect::<VecDeque<RefNode>>();
-        descendants.append(&mut next);
-        descendants
+        self.axis_result(self.iter().flat_map(|node| Descendants::new(node, true)))
     }
 
     ///
-    /// Non-standard axis, used to select the document node (if present) for all input nodes.
+    /// Non-standard axis, used to select the document node (if present) for all input nodes; the
+    /// document node is its own document.
     ///
     /// # Result
     ///
@@ -298,17 +312,20 @@ impl NodeSet {
     /// ```
     ///
     pub fn document(&self) -> Self {
-        Self(
-            self.iter()
-                .filter_map(|node| node.owner_document())
-                .collect(),
-        )
+        self.axis_result(self.iter().filter_map(|node| {
+            if node.node_type() == NodeType::Document {
+                Some(node.clone())
+            } else {
+                node.owner_document()
+            }
+        }))
     }
 
     ///
     /// The `following` axis contains all nodes in the same document as the context node that are
     /// after the context node in document order, excluding any descendants and excluding attribute
-    /// nodes and namespace nodes.
+    /// nodes and namespace nodes. For an attribute or namespace node this includes the descendants
+    /// of the element that contains it.
     ///
     /// # Result
     ///
@@ -326,13 +343,7 @@ impl NodeSet {
     /// ```
     ///
     pub fn following(&self) -> Self {
-        Self(
-            self.following_sibling()
-                .iter()
-                .map(|node| NodeSet::from(node).descendant_or_self().into_inner())
-                .flatten()
-                .collect(),
-        )
+        self.axis_result(self.iter().flat_map(Following::new))
     }
 
     ///
@@ -356,16 +367,7 @@ impl NodeSet {
     /// ```
     ///
     pub fn following_sibling(&self) -> Self {
-        fn siblings(node: &RefNode) -> VecDeque<RefNode> {
-            let mut result = NodeSet::inner_new();
-            let mut next = node.next_sibling();
-            while let Some(sibling) = next {
-                result.push_back(sibling.clone());
-                next = sibling.next_sibling();
-            }
-            result
-        }
-        Self(self.iter().map(siblings).flatten().collect())
+        self.axis_result(self.iter().flat_map(following_siblings))
     }
 
     ///
@@ -376,33 +378,15 @@ impl NodeSet {
     /// `prefix`, is 'xmlns'.
     ///
     pub fn namespace(&self) -> Self {
-        Self(
+        self.axis_result(
             self.iter()
-                .filter_map(|node| {
-                    if node.node_type() == NodeType::Element {
-                        let attribute_hash = node.attributes();
-                        let attribute_nodes: VecDeque<RefNode> = attribute_hash
-                            .iter()
-                            .filter_map(|(name, node)| {
-                                if name.is_namespace_attribute() {
-                                    Some(node.clone())
-                                } else {
-                                    None
-                                }
-                            })
-                            .collect();
-                        Some(attribute_nodes)
-                    } else {
-                        None
-                    }
-                })
-                .flatten()
-                .collect(),
+                .flat_map(|node| attribute_nodes(node, is_namespace_declaration)),
         )
     }
 
     ///
-    /// The parent axis contains the parent of the context node, if there is one.
+    /// The parent axis contains the parent of the context node, if there is one; the parent of an
+    /// attribute or namespace node is the element that contains it.
     ///
     /// # Result
     ///
@@ -420,7 +404,7 @@ impl NodeSet {
     /// ```
     ///
     pub fn parent(&self) -> Self {
-        Self(self.iter().filter_map(|node| node.parent_node()).collect())
+        self.axis_result(self.iter().filter_map(parent_of))
     }
 
     ///
@@ -428,7 +412,8 @@ impl NodeSet {
     /// before the context node in document order, excluding any ancestors and excluding attribute
     /// nodes and namespace nodes.
     ///
-    /// **Note**: the order of nodes is based on adjacency, in effect reverse document order.
+    /// This is a reverse axis; the result is in document order, the positions shown below are the
+    /// proximity positions used when evaluating a predicate on this axis.
     ///
     /// # Result
     ///
@@ -446,32 +431,27 @@ impl NodeSet {
     /// ```
     ///
     pub fn preceding(&self) -> Self {
-        fn reverse_descendant(node_set: &NodeSet) -> VecDeque<RefNode> {
-            let mut children: VecDeque<RefNode> = node_set.child().iter().rev().cloned().collect();
-            let mut next: VecDeque<RefNode> = children
-                .iter()
-                .map(|node| reverse_descendant(&NodeSet::from(node)))
-                .flatten()
-                .collect::<VecDeque<RefNode>>();
-            children.append(&mut next);
-            children
+        fn preceding_nodes(node: &RefNode) -> VecDeque<RefNode> {
+            // The preceding siblings of the outermost ancestor come before those of the next
+            // ancestor, and so on, down to those of the context node itself.
+            let mut result = NodeSet::inner_new();
+            for ancestor in NodeSet::from(node).ancestor_or_self().iter() {
+                for sibling in NodeSet::from(ancestor).preceding_sibling().iter() {
+                    result.extend(NodeSet::from(sibling).descendant_or_self().into_inner());
+                }
+            }
+            result
         }
-
-        let mut previous: VecDeque<RefNode> =
-            self.preceding_sibling().iter().rev().cloned().collect();
-        let mut next = previous
-            .iter()
-            .map(|node| reverse_descendant(&NodeSet::from(node)))
-            .flatten()
-            .collect::<VecDeque<RefNode>>();
-        previous.append(&mut next);
-        Self(previous)
+        self.axis_result(self.iter().flat_map(preceding_nodes))
     }
 
     ///
     /// The `preceding-sibling` axis contains all the preceding siblings of the context node; if the
     /// context node is an attribute node or namespace node, the `preceding-sibling` axis is empty.
     ///
+    /// This is a reverse axis; the result is in document order, the positions shown below are the
+    /// proximity positions used when evaluating a predicate on this axis.
+    ///
     /// # Result
     ///
     /// ```text
@@ -488,26 +468,20 @@ impl NodeSet {
     /// ```
     ///
     pub fn preceding_sibling(&self) -> Self {
-        Self(
-            self.iter()
-                .map(|node| match node.parent_node() {
-                    None => veq!(),
-                    Some(parent) => {
-                        let mut result = NodeSet::inner_new();
-                        let siblings = parent.child_nodes();
-                        let mut child_iter = siblings.iter();
-                        while let Some(child) = child_iter.next() {
-                            if child == node {
-                                break;
-                            }
-                            result.push_back(child.clone());
-                        }
-                        result
+        self.axis_result(self.iter().flat_map(|node| match node.parent_node() {
+            None => veq!(),
+            Some(parent) => {
+                let mut result = NodeSet::inner_new();
+                let siblings = parent.child_nodes();
+                for child in siblings.iter() {
+                    if child == node {
+                        break;
                     }
-                })
-                .flatten()
-                .collect(),
-        )
+                    result.push_back(child.clone());
+                }
+                result
+            }
+        }))
     }
 
     ///
@@ -529,7 +503,31 @@ impl NodeSet {
     /// ```
     ///
     pub fn self_node(&self) -> Self {
-        Self(self.iter().cloned().collect())
+        Self(self.0.clone())
+    }
+
+    ///
+    /// Sort the nodes in this node-set into document order, as defined by
+    /// [`compare_document_order`](fn.compare_document_order.html).
+    ///
+    pub fn sort_document_order(&mut self) {
+        let mut child_indices = ChildIndices::default();
+        self.0
+            .make_contiguous()
+            .sort_by_cached_key(|node| document_position(node, &mut child_indices));
+    }
+
+    //
+    // Collect the result of an axis, where `nodes` holds the nodes on the axis for each node in
+    // this node-set, in turn, and in document order. Where there is more than one node in this
+    // node-set the results may overlap, or interleave, and so they must be sorted.
+    //
+    fn axis_result<I: IntoIterator<Item = RefNode>>(&self, nodes: I) -> Self {
+        let mut result = Self::from_iter(nodes);
+        if self.len() > 1 {
+            result.sort_document_order();
+        }
+        result
     }
 
     fn into_inner(self) -> VecDeque<RefNode> {
@@ -539,10 +537,144 @@ impl NodeSet {
     fn inner_new() -> VecDeque<RefNode> {
         Default::default()
     }
+}
+
+// ------------------------------------------------------------------------------------------------
 
-    fn append(&mut self, vector: &mut VecDeque<RefNode>) {
-        self.0.append(vector)
+impl ChildIndices {
+    fn index_of(&mut self, parent: &RefNode, child: &RefNode) -> usize {
+        self.0
+            .entry(node_identity(parent))
+            .or_insert_with(|| {
+                parent
+                    .child_nodes()
+                    .iter()
+                    .enumerate()
+                    .map(|(index, child)| (node_identity(child), index))
+                    .collect()
+            })
+            .get(&node_identity(child))
+            .copied()
+            .unwrap_or_default()
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+// Private Types
+// ------------------------------------------------------------------------------------------------
+
+//
+// One step in the path from the root of a tree to a node; the derived ordering puts the namespace
+// nodes of an element before its attribute nodes, and both before its children.
+//
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum PositionStep {
+    Namespace(String),
+    Attribute(String),
+    Child(usize),
+}
+
+//
+// The index of each child of a parent, keyed by the identity of the parent and then the child,
+// built for all of the children of a parent the first time any one of them is needed; sorting
+// the children of a wide element then looks at each child once rather than once for each child.
+//
+#[derive(Debug, Default)]
+struct ChildIndices(HashMap<usize, HashMap<usize, usize>>);
+
+// ------------------------------------------------------------------------------------------------
+// Private Functions
+// ------------------------------------------------------------------------------------------------
+
+//
+// The identity of a node is the address of the shared DOM node, so that two nodes are the same
+// only if they are the identical node.
+//
+fn node_identity(node: &RefNode) -> usize {
+    Rc::as_ptr(node.as_inner()) as *const () as usize
+}
+
+//
+// The parent of a node in the XPath data model; unlike the DOM, the parent of an attribute (or
+// namespace) node is the element that contains it.
+//
+pub(crate) fn parent_of(node: &RefNode) -> Option<RefNode> {
+    if node.node_type() == NodeType::Attribute {
+        as_attribute(node)
+            .ok()
+            .and_then(|attribute| attribute.owner_element())
+    } else {
+        node.parent_node()
+    }
+}
+
+//
+// The position of a node in document order, as the identity of the root of its tree followed by
+// the path of steps from that root to the node; trees are therefore ordered by the address of
+// their root, which is consistent while the nodes are alive.
+//
+fn document_position(
+    node: &RefNode,
+    child_indices: &mut ChildIndices,
+) -> (usize, Vec<PositionStep>) {
+    let mut steps: Vec<PositionStep> = Vec::new();
+    let mut current = node.clone();
+    while let Some(parent) = parent_of(&current) {
+        steps.push(if current.node_type() == NodeType::Attribute {
+            let name = current.node_name();
+            if is_namespace_declaration(&name) {
+                PositionStep::Namespace(name.to_string())
+            } else {
+                PositionStep::Attribute(name.to_string())
+            }
+        } else {
+            PositionStep::Child(child_indices.index_of(&parent, &current))
+        });
+        current = parent;
+    }
+    steps.reverse();
+    (node_identity(&current), steps)
+}
+
+//
+// An attribute is a namespace declaration where its name is `xmlns` or has the prefix `xmlns`; the
+// parser does not record the `xmlns` namespace URI on these names, so the name alone is checked.
+//
+fn is_namespace_declaration(name: &Name) -> bool {
+    name.is_namespace_attribute()
+        || (name.prefix().is_none() && name.local_name() == "xmlns")
+        || name.prefix().as_deref() == Some("xmlns")
+}
+
+//
+// Namespace nodes are represented by the namespace declaration attributes on an element.
+//
+pub(crate) fn is_namespace_node(node: &RefNode) -> bool {
+    node.node_type() == NodeType::Attribute && is_namespace_declaration(&node.node_name())
+}
+
+//
+// The attributes of `node`, if it is an element, selected by name and sorted by name so that the
+// order is the same as that of `compare_document_order`.
+//
+fn attribute_nodes<P>(node: &RefNode, predicate: P) -> VecDeque<RefNode>
+where
+    P: Fn(&Name) -> bool,
+{
+    if node.node_type() != NodeType::Element {
+        return NodeSet::inner_new();
     }
+    let mut attributes: Vec<(String, RefNode)> = node
+        .attributes()
+        .into_iter()
+        .filter(|(name, _)| predicate(name))
+        .map(|(name, attribute)| (name.to_string(), attribute))
+        .collect();
+    attributes.sort_by(|(lhs, _), (rhs, _)| lhs.cmp(rhs));
+    attributes
+        .into_iter()
+        .map(|(_, attribute)| attribute)
+        .collect()
 }
 
 // ------------------------------------------------------------------------------------------------
@@ -637,7 +769,7 @@ mod tests {
         let node_set = node_set.ancestor();
 
         assert_eq!(node_set.len(), 3);
-        assert_equal_ids(node_set, &["E", "A"]);
+        assert_equal_ids(node_set, &["A", "E"]);
     }
 
     #[test]
@@ -650,7 +782,7 @@ mod tests {
         let node_set = node_set.ancestor_or_self();
 
         assert_eq!(node_set.len(), 4);
-        assert_equal_ids(node_set, &["G", "E", "A"]);
+        assert_equal_ids(node_set, &["A", "E", "G"]);
     }
 
     #[test]
@@ -747,7 +879,154 @@ mod tests {
 
         print_node_set(&node_set, Some("preceding"));
         assert_eq!(node_set.len(), 4);
-        assert_equal_ids(node_set, &["B", "D", "C"]);
+        assert_equal_ids(node_set, &["B", "C", "D"]);
+    }
+
+    #[test]
+    fn test_without_duplicates() {
+        let document_node = make_test_document();
+        let document = as_document(&document_node).unwrap();
+        let e = document.get_element_by_id("E").unwrap();
+        let g = document.get_element_by_id("G").unwrap();
+        let node_set = NodeSet::from_iter(vec![e.clone(), g.clone(), e]);
+
+        assert_eq!(node_set.len(), 2);
+
+        let node_set = node_set.descendant();
+
+        print_node_set(&node_set, Some("without_duplicates"));
+        assert_equal_ids(node_set, &["F", "G", "H", "I", "J"]);
+
+        let node_set = NodeSet::from_iter(vec![g.clone(), g]).ancestor();
+
+        assert_eq!(node_set.len(), 3);
+    }
+
+    #[test]
+    fn test_sort_document_order() {
+        let document_node = make_test_document();
+        let document = as_document(&document_node).unwrap();
+        let mut node_set = NodeSet::from_iter(
+            ["J", "A", "M", "E", "H"]
+                .iter()
+                .map(|id| document.get_element_by_id(id).unwrap()),
+        );
+
+        node_set.sort_document_order();
+
+        assert_equal_ids(node_set, &["A", "E", "H", "J", "M"]);
+    }
+
+    #[test]
+    fn test_compare_document_order() {
+        let document_node = make_test_document();
+        let document = as_document(&document_node).unwrap();
+        let d = document.get_element_by_id("D").unwrap();
+        let e = document.get_element_by_id("E").unwrap();
+        let incomplete = NodeSet::from(&d).attribute().iter().next().unwrap().clone();
+        let c = document.get_element_by_id("C").unwrap();
+
+        assert_eq!(compare_document_order(&d, &d), Ordering::Equal);
+        assert_eq!(compare_document_order(&document_node, &d), Ordering::Less);
+        assert_eq!(compare_document_order(&e, &d), Ordering::Greater);
+        assert_eq!(compare_document_order(&d, &incomplete), Ordering::Less);
+        assert_eq!(compare_document_order(&incomplete, &e), Ordering::Less);
+        assert_eq!(compare_document_order(&c, &incomplete), Ordering::Less);
+    }
+
+    #[test]
+    fn test_compare_namespaces_and_attributes() {
+        let document_node =
+            read_xml(r#"<a xmlns:x="urn:x" x:b="1" c="2"><d xmlns="urn:d"/></a>"#).unwrap();
+        let a = NodeSet::from(&document_node).child();
+        let namespaces = a.namespace();
+        let attributes = a.attribute();
+        let d = a.child();
+
+        assert_eq!(namespaces.len(), 1);
+        assert_eq!(attributes.len(), 2);
+        for namespace in namespaces.iter() {
+            for attribute in attributes.iter() {
+                assert_eq!(compare_document_order(namespace, attribute), Ordering::Less);
+            }
+        }
+        for attribute in attributes.iter() {
+            assert_eq!(
+                compare_document_order(a.iter().next().unwrap(), attribute),
+                Ordering::Less
+            );
+            assert_eq!(
+                compare_document_order(attribute, d.iter().next().unwrap()),
+                Ordering::Less
+            );
+        }
+        assert_eq!(
+            compare_document_order(
+                d.namespace().iter().next().unwrap(),
+                attributes.iter().next().unwrap()
+            ),
+            Ordering::Greater
+        );
+    }
+
+    #[test]
+    fn test_compare_different_documents() {
+        let first = make_test_document();
+        let second = make_test_document();
+        let order = compare_document_order(&first, &second);
+
+        assert_ne!(order, Ordering::Equal);
+        assert_eq!(compare_document_order(&second, &first), order.reverse());
+
+        let first_id = as_document(&first).unwrap().get_element_by_id("N").unwrap();
+        let second_id = as_document(&second)
+            .unwrap()
+            .get_element_by_id("A")
+            .unwrap();
+        assert_eq!(compare_document_order(&first_id, &second_id), order);
+    }
+
+    #[test]
+    fn test_axes_in_document_order() {
+        let document_node = make_test_document();
+        let document = as_document(&document_node).unwrap();
+        let node_set = NodeSet::from_iter(
+            ["K", "C", "H"]
+                .iter()
+                .map(|id| document.get_element_by_id(id).unwrap()),
+        );
+
+        assert_equal_ids(node_set.parent(), &["A", "B", "G"]);
+        assert_equal_ids(node_set.ancestor(), &["A", "B", "E", "G"]);
+        assert_equal_ids(node_set.preceding_sibling(), &["B", "E"]);
+        assert_equal_ids(node_set.following_sibling(), &["D", "I", "J", "N"]);
+    }
+
+    #[test]
+    fn test_preceding_and_following_ancestor_siblings() {
+        let document_node = make_test_document();
+        let document = as_document(&document_node).unwrap();
+        let context_node = document.get_element_by_id("H").unwrap();
+        let node_set = NodeSet::from(context_node);
+
+        assert_equal_ids(node_set.preceding(), &["B", "C", "D", "F"]);
+        assert_equal_ids(node_set.following(), &["I", "J", "K", "L", "M", "N"]);
+    }
+
+    #[test]
+    fn test_attribute_parent_and_following() {
+        let document_node = make_test_document();
+        let document = as_document(&document_node).unwrap();
+        let context_node = document.get_element_by_id("M").unwrap();
+        let attribute = NodeSet::from(context_node).attribute();
+
+        assert_equal_ids(attribute.parent(), &["M"]);
+        assert_equal_ids(attribute.ancestor(), &["A", "K", "M"]);
+        assert_equal_ids(attribute.following(), &["N"]);
+        assert_equal_ids(
+            attribute.preceding(),
+            &["B", "C", "D", "E", "F", "G", "H", "I", "J", "L"],
+        );
     }
 
     #[test]
@@ -0,0 +1,167 @@
+/*!
+The type `FunctionRegistry` holds the library of functions available to an expression.
+*/
+
+use crate::xpath1::evaluate::functions::register_core_functions;
+use crate::xpath1::evaluate::{EvaluationContext, EvaluationError, FunctionImpl};
+use crate::xpath1::model::Function;
+use crate::xpath1::XPathObject;
+use std::collections::HashMap;
+use std::fmt::{Debug, Formatter};
+use std::rc::Rc;
+
+// ------------------------------------------------------------------------------------------------
+// Public Types
+// ------------------------------------------------------------------------------------------------
+
+///
+/// A library of functions, each registered under an expanded name, the pair of namespace URI and
+/// local name, along with the declared [`Function`](../model/struct.Function.html) signature
+/// and the implementation to call.
+///
+/// The default registry contains the functions of the XPath 1.0 core function library, all of
+/// which have no namespace URI. Extension functions are registered under the URI of their
+/// namespace, and are called using a prefix declared in the
+/// [`EvaluationContext`](struct.EvaluationContext.html).
+///
+/// Before an implementation is called the number of arguments is checked against the signature,
+/// and each argument is converted to the type declared for it in the signature, so that an
+/// argument declared as `DataType::String` will always be provided as an `XPathObject::String`.
+///
+/// # Example
+///
+/// ```rust
+/// use xml_dom_xpath::xpath1::evaluate::FunctionRegistry;
+/// use xml_dom_xpath::xpath1::model::{Argument, DataType, Function};
+/// use xml_dom_xpath::xpath1::XPathObject;
+///
+/// let mut functions = FunctionRegistry::default();
+/// functions.register(
+///     Some("http://example.org/functions"),
+///     Function::with(
+///         "normalize-sku",
+///         &[Argument::with("sku", DataType::String, true)],
+///         DataType::String,
+///     ),
+///     |_, arguments| match &arguments[0] {
+///         XPathObject::String(sku) => Ok(XPathObject::String(sku.to_uppercase())),
+///         _ => unreachable!(),
+///     },
+/// );
+///
+/// assert!(functions.contains(Some("http://example.org/functions"), "normalize-sku"));
+/// assert!(functions.contains(None, "count"));
+/// ```
+///
+#[derive(Clone)]
+pub struct FunctionRegistry {
+    functions: HashMap<FunctionKey, RegisteredFunction>,
+}
+
+// ------------------------------------------------------------------------------------------------
+// Private Types
+// ------------------------------------------------------------------------------------------------
+
+type FunctionKey = (Option<String>, String);
+
+#[derive(Clone)]
+struct RegisteredFunction {
+    signature: Function,
+    implementation: Rc<FunctionImpl>,
+}
+
+// ------------------------------------------------------------------------------------------------
+// Implementations
+// ------------------------------------------------------------------------------------------------
+
+impl Default for FunctionRegistry {
+    fn default() -> Self {
+        let mut registry = Self::empty();
+        register_core_functions(&mut registry);
+        registry
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+
+impl Debug for FunctionRegistry {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_set()
+            .entries(self.functions.values().map(|f| f.signature.to_string()))
+            .finish()
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+
+impl FunctionRegistry {
+    ///
+    /// Create a registry with no functions, not even those of the core function library.
+    ///
+    pub fn empty() -> Self {
+        Self {
+            functions: Default::default(),
+        }
+    }
+
+    ///
+    /// Register `implementation` as the function named by `namespace_uri` and the name of
+    /// `signature`, replacing any existing function with the same expanded name.
+    ///
+    pub fn register<F>(
+        &mut self,
+        namespace_uri: Option<&str>,
+        signature: Function,
+        implementation: F,
+    ) -> &mut Self
+    where
+        F: Fn(&EvaluationContext, &[XPathObject]) -> Result<XPathObject, EvaluationError> + 'static,
+    {
+        let _ = self.functions.insert(
+            (
+                namespace_uri.map(String::from),
+                signature.name().to_string(),
+            ),
+            RegisteredFunction {
+                signature,
+                implementation: Rc::new(implementation),
+            },
+        );
+        self
+    }
+
+    ///
+    /// Returns `true` if a function is registered with the expanded name `namespace_uri` and
+    /// `local_name`.
+    ///
+    pub fn contains(&self, namespace_uri: Option<&str>, local_name: &str) -> bool {
+        self.lookup(namespace_uri, local_name).is_some()
+    }
+
+    ///
+    /// Return the signature of the function registered with the expanded name `namespace_uri`
+    /// and `local_name`, if any.
+    ///
+    pub fn signature(&self, namespace_uri: Option<&str>, local_name: &str) -> Option<&Function> {
+        self.lookup(namespace_uri, local_name)
+            .map(|function| &function.signature)
+    }
+
+    ///
+    /// Return the implementation of the function registered with the expanded name
+    /// `namespace_uri` and `local_name`, if any.
+    ///
+    pub fn implementation(
+        &self,
+        namespace_uri: Option<&str>,
+        local_name: &str,
+    ) -> Option<Rc<FunctionImpl>> {
+        self.lookup(namespace_uri, local_name)
+            .map(|function| function.implementation.clone())
+    }
+
+    fn lookup(&self, namespace_uri: Option<&str>, local_name: &str) -> Option<&RegisteredFunction> {
+        self.functions
+            .get(&(namespace_uri.map(String::from), local_name.to_string()))
+    }
+}
@@ -0,0 +1,51 @@
+/*!
+The trait `VariableResolver` provides values for variable references during evaluation.
+*/
+
+use crate::xpath1::model::QName;
+use crate::xpath1::XPathObject;
+use std::collections::HashMap;
+
+// ------------------------------------------------------------------------------------------------
+// Public Types
+// ------------------------------------------------------------------------------------------------
+
+///
+/// Provides the value bound to a variable, by name, when a variable reference such as `$order_id`
+/// is evaluated. If the resolver returns `None` the evaluator will report an
+/// [`EvaluationError::UnboundVariable`](enum.EvaluationError.html#variant.UnboundVariable) error.
+///
+/// # Example
+///
+/// ```rust
+/// use xml_dom_xpath::xpath1::evaluate::{EvaluationContext, VariableResolver};
+/// use xml_dom_xpath::xpath1::model::QName;
+/// use xml_dom_xpath::xpath1::XPathObject;
+/// use std::collections::HashMap;
+///
+/// let mut variables: HashMap<QName, XPathObject> = HashMap::new();
+/// let _ = variables.insert(
+///     QName::with("order_id"),
+///     XPathObject::String("ord-1001".to_string()),
+/// );
+/// assert!(variables.resolve_variable(&QName::with("order_id")).is_some());
+///
+/// let mut context = EvaluationContext::default();
+/// context.set_variable_resolver(variables);
+/// assert!(context.variable(&QName::with("order_id")).is_some());
+/// ```
+///
+pub trait VariableResolver {
+    /// Return the value bound to the variable `name`, if any.
+    fn resolve_variable(&self, name: &QName) -> Option<XPathObject>;
+}
+
+// ------------------------------------------------------------------------------------------------
+// Implementations
+// ------------------------------------------------------------------------------------------------
+
+impl VariableResolver for HashMap<QName, XPathObject> {
+    fn resolve_variable(&self, name: &QName) -> Option<XPathObject> {
+        self.get(name).cloned()
+    }
+}
@@ -2,12 +2,23 @@
 This module implements [Version 1.0](https://www.w3.org/TR/xpath-10/) of the XML Path Language
 (XPath).
 
-The primary API is the [`evaluate_path`](fn.evaluate_path.html) function, however access to the
-underlying [`parser`](parser/index.html), [`model`](model/index.html), and [`evaluate`](evaluate/index.html)
+The primary API is the [`evaluate_path`](fn.evaluate_path.html) function, with the
+[`evaluate_expr`](fn.evaluate_expr.html) function for expressions that are not simply location
+paths, however access to the underlying [`parser`](parser/index.html), [`model`](model/index.html), and [`evaluate`](evaluate/index.html)
 modules is also possible.
+
+Where the result is expected to be of a particular type the functions
+[`evaluate_string`](fn.evaluate_string.html), [`evaluate_number`](fn.evaluate_number.html),
+[`evaluate_boolean`](fn.evaluate_boolean.html), [`select_nodes`](fn.select_nodes.html), and
+[`select_first`](fn.select_first.html) save unpacking the resulting `XPathObject`. Each of these
+functions parses the expression on every call; an expression evaluated repeatedly should instead
+be compiled once as an [`XPath`](struct.XPath.html).
 */
 
-use crate::xpath1::evaluate::EvaluationError;
+use crate::xpath1::evaluate::{
+    number_to_string, string_to_number, string_value, EvaluationError, NodeSet,
+};
+use crate::xpath1::model::DataType;
 use crate::xpath1::parser::ParseError;
 use std::fmt::{Display, Formatter};
 use xml_dom::level2::RefNode;
@@ -41,6 +52,26 @@ pub enum Error {
 /// * `number` (a floating-point number)
 /// * `string` (a sequence of UCS characters)
 ///
+/// Any object may be converted to a boolean, a number, or a string, following the rules of the
+/// corresponding core functions; no object may be converted to a node-set.
+///
+/// # Example
+///
+/// ```rust
+/// use xml_dom_xpath::xpath1::XPathObject;
+///
+/// let value = XPathObject::from("12.50".to_string());
+/// assert_eq!(value.to_number(), 12.5);
+/// assert!(value.to_boolean());
+/// assert!(value.as_node_set().is_none());
+///
+/// let value = XPathObject::from(1.0 / 0.0);
+/// assert_eq!(value.to_string_value(), "Infinity");
+/// assert_eq!(value.to_string(), "Infinity");
+/// assert_eq!(value, XPathObject::Number(f64::INFINITY));
+/// ```
+///
+#[derive(Clone, Debug, PartialEq)]
 pub enum XPathObject {
     /// an unordered collection of nodes without duplicates
     NodeSet(evaluate::NodeSet),
@@ -67,17 +98,99 @@ pub const XPATH_VERSION: &str = "1.0";
 /// This function will first parse the provided `xpath` string with
 /// [`parser::read_str`](parser/fn.read_str.html), then call the underlying
 /// [`evaluate::evaluate_path`](evaluate/fn.evaluate_path.html) function with the parsed expression
-/// and a [`evaluate::NodeSet`](evaluate/struct.NodeSet.html) created from the array `context_nodes`.
+/// and an [`evaluate::EvaluationContext`](evaluate/struct.EvaluationContext.html) created from the
+/// array `context_nodes`.
 ///
 pub fn evaluate_path(xpath: &str, context_nodes: &[RefNode]) -> Result<XPathObject, Error> {
-    use std::iter::FromIterator;
-
     let xpath = parser::read_str(xpath)?;
-    evaluate::evaluate_path(
-        &evaluate::NodeSet::from_iter(context_nodes.iter().cloned()),
-        &xpath,
-    )
-    .map_err(|err| err.into())
+    evaluate::evaluate_path(&make_context(context_nodes), &xpath).map_err(|err| err.into())
+}
+
+///
+/// Evaluate the XPath expression string against the set of nodes that act as context.
+///
+/// This function will first parse the provided `xpath` string with
+/// [`parser::read_expr`](parser/fn.read_expr.html), then call the underlying
+/// [`evaluate::evaluate_expr`](evaluate/fn.evaluate_expr.html) function with the parsed expression
+/// and an [`evaluate::EvaluationContext`](evaluate/struct.EvaluationContext.html) created from the
+/// array `context_nodes`.
+///
+pub fn evaluate_expr(xpath: &str, context_nodes: &[RefNode]) -> Result<XPathObject, Error> {
+    let xpath = parser::read_expr(xpath)?;
+    evaluate::evaluate_expr(&make_context(context_nodes), &xpath).map_err(|err| err.into())
+}
+
+///
+/// Evaluate the XPath expression string, as [`evaluate_expr`](fn.evaluate_expr.html), and convert
+/// the result to a string as if by a call to the `string` function.
+///
+/// # Example
+///
+/// ```rust
+/// use xml_dom_xpath::xpath1::evaluate_string;
+/// use xml_dom::parser::read_xml;
+///
+/// let document_node = read_xml(r#"<order id="ord-1001"><total>12.50</total></order>"#).unwrap();
+///
+/// assert_eq!(evaluate_string("/order/@id", &[document_node.clone()]).unwrap(), "ord-1001");
+/// assert_eq!(evaluate_string("/order/total * 2", &[document_node]).unwrap(), "25");
+/// ```
+///
+pub fn evaluate_string(xpath: &str, context_nodes: &[RefNode]) -> Result<String, Error> {
+    evaluate_expr(xpath, context_nodes).map(|value| value.to_string_value())
+}
+
+///
+/// Evaluate the XPath expression string, as [`evaluate_expr`](fn.evaluate_expr.html), and convert
+/// the result to a number as if by a call to the `number` function.
+///
+pub fn evaluate_number(xpath: &str, context_nodes: &[RefNode]) -> Result<f64, Error> {
+    evaluate_expr(xpath, context_nodes).map(|value| value.to_number())
+}
+
+///
+/// Evaluate the XPath expression string, as [`evaluate_expr`](fn.evaluate_expr.html), and convert
+/// the result to a boolean as if by a call to the `boolean` function.
+///
+pub fn evaluate_boolean(xpath: &str, context_nodes: &[RefNode]) -> Result<bool, Error> {
+    evaluate_expr(xpath, context_nodes).map(|value| value.to_boolean())
+}
+
+///
+/// Evaluate the XPath expression string, as [`evaluate_expr`](fn.evaluate_expr.html), and return
+/// the resulting node-set, in document order. No other type of object may be converted to a
+/// node-set, so any other result is an
+/// [`EvaluationError::TypeError`](evaluate/enum.EvaluationError.html#variant.TypeError) error.
+///
+pub fn select_nodes(xpath: &str, context_nodes: &[RefNode]) -> Result<NodeSet, Error> {
+    let xpath = parser::read_expr(xpath)?;
+    match evaluate::evaluate_expr(&make_context(context_nodes), &xpath)? {
+        XPathObject::NodeSet(node_set) => Ok(node_set),
+        value => Err(EvaluationError::type_error(&xpath, DataType::NodeSet, &value).into()),
+    }
+}
+
+///
+/// Evaluate the XPath expression string, as [`select_nodes`](fn.select_nodes.html), and return
+/// the first node of the resulting node-set in document order, or `None` if it is empty.
+///
+/// # Example
+///
+/// ```rust
+/// use xml_dom_xpath::xpath1::select_first;
+/// use xml_dom::level2::Element;
+/// use xml_dom::parser::read_xml;
+///
+/// let document_node = read_xml(r#"<order><item sku="a"/><item sku="b"/></order>"#).unwrap();
+///
+/// let item = select_first("//item", &[document_node.clone()]).unwrap().unwrap();
+/// assert_eq!(item.get_attribute("sku").as_deref(), Some("a"));
+///
+/// assert!(select_first("//total", &[document_node]).unwrap().is_none());
+/// ```
+///
+pub fn select_first(xpath: &str, context_nodes: &[RefNode]) -> Result<Option<RefNode>, Error> {
+    select_nodes(xpath, context_nodes).map(|node_set| node_set.iter().next().cloned())
 }
 
 // ------------------------------------------------------------------------------------------------
@@ -124,6 +237,131 @@ impl From<EvaluationError> for Error {
     }
 }
 
+// ------------------------------------------------------------------------------------------------
+
+impl Display for XPathObject {
+    ///
+    /// Format this object as its string value, see
+    /// [`to_string_value`](enum.XPathObject.html#method.to_string_value).
+    ///
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.to_string_value())
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+
+impl From<bool> for XPathObject {
+    fn from(value: bool) -> Self {
+        Self::Boolean(value)
+    }
+}
+
+impl From<f64> for XPathObject {
+    fn from(value: f64) -> Self {
+        Self::Number(value)
+    }
+}
+
+impl From<String> for XPathObject {
+    fn from(value: String) -> Self {
+        Self::String(value)
+    }
+}
+
+impl From<NodeSet> for XPathObject {
+    fn from(value: NodeSet) -> Self {
+        Self::NodeSet(value)
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+
+impl XPathObject {
+    ///
+    /// Return the type of this object, as used in function signatures.
+    ///
+    pub fn data_type(&self) -> DataType {
+        match self {
+            XPathObject::NodeSet(_) => DataType::NodeSet,
+            XPathObject::Boolean(_) => DataType::Bool,
+            XPathObject::Number(_) => DataType::Number,
+            XPathObject::String(_) => DataType::String,
+        }
+    }
+
+    ///
+    /// Convert this object to a boolean, as if by a call to the `boolean` function; a node-set is
+    /// true if it is non-empty, a number is true if it is neither zero nor NaN, and a string is
+    /// true if it is non-empty.
+    ///
+    pub fn to_boolean(&self) -> bool {
+        match self {
+            XPathObject::NodeSet(node_set) => !node_set.is_empty(),
+            XPathObject::Boolean(value) => *value,
+            XPathObject::Number(value) => !(*value == 0.0 || value.is_nan()),
+            XPathObject::String(value) => !value.is_empty(),
+        }
+    }
+
+    ///
+    /// Convert this object to a number, as if by a call to the `number` function; a node-set is
+    /// first converted to a string, true is 1 and false is 0, and a string is converted by
+    /// [`string_to_number`](evaluate/fn.string_to_number.html).
+    ///
+    pub fn to_number(&self) -> f64 {
+        match self {
+            XPathObject::NodeSet(_) => string_to_number(&self.to_string_value()),
+            XPathObject::Boolean(value) => {
+                if *value {
+                    1.0
+                } else {
+                    0.0
+                }
+            }
+            XPathObject::Number(value) => *value,
+            XPathObject::String(value) => string_to_number(value),
+        }
+    }
+
+    ///
+    /// Convert this object to a string, as if by a call to the `string` function; a node-set is
+    /// the string-value of its first node in document order, or the empty string if it is empty,
+    /// and a number is converted by [`number_to_string`](evaluate/fn.number_to_string.html).
+    ///
+    pub fn to_string_value(&self) -> String {
+        match self {
+            XPathObject::NodeSet(node_set) => match node_set.iter().next() {
+                None => String::new(),
+                Some(node) => string_value(node),
+            },
+            XPathObject::Boolean(value) => value.to_string(),
+            XPathObject::Number(value) => number_to_string(*value),
+            XPathObject::String(value) => value.clone(),
+        }
+    }
+
+    ///
+    /// Return a reference to the node-set, if this object is a node-set, otherwise `None`.
+    ///
+    pub fn as_node_set(&self) -> Option<&NodeSet> {
+        match self {
+            XPathObject::NodeSet(node_set) => Some(node_set),
+            _ => None,
+        }
+    }
+
+    ///
+    /// Return the node-set, if this object is a node-set, otherwise `None`.
+    ///
+    pub fn into_node_set(self) -> Option<NodeSet> {
+        match self {
+            XPathObject::NodeSet(node_set) => Some(node_set),
+            _ => None,
+        }
+    }
+}
+
 // ------------------------------------------------------------------------------------------------
 // Private Types
 // ------------------------------------------------------------------------------------------------
@@ -132,12 +370,156 @@ impl From<EvaluationError> for Error {
 // Private Functions
 // ------------------------------------------------------------------------------------------------
 
+fn make_context(context_nodes: &[RefNode]) -> evaluate::EvaluationContext {
+    use std::iter::FromIterator;
+
+    evaluate::EvaluationContext::from(NodeSet::from_iter(context_nodes.iter().cloned()))
+}
+
 // ------------------------------------------------------------------------------------------------
 // Modules
 // ------------------------------------------------------------------------------------------------
 
+mod compiled;
+pub use compiled::XPath;
+
 pub mod evaluate;
 
 pub mod model;
 
 pub mod parser;
+
+// ------------------------------------------------------------------------------------------------
+// Unit Tests
+// ------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::iter::FromIterator;
+    use xml_dom::level2::{Element, Node};
+    use xml_dom::parser::read_xml;
+
+    #[test]
+    fn test_to_boolean() {
+        assert!(XPathObject::from(true).to_boolean());
+        assert!(!XPathObject::from(false).to_boolean());
+        assert!(XPathObject::from(-1.0).to_boolean());
+        assert!(!XPathObject::from(0.0).to_boolean());
+        assert!(!XPathObject::from(-0.0).to_boolean());
+        assert!(!XPathObject::from(f64::NAN).to_boolean());
+        assert!(XPathObject::from("false".to_string()).to_boolean());
+        assert!(!XPathObject::from(String::new()).to_boolean());
+        assert!(!XPathObject::from(NodeSet::default()).to_boolean());
+    }
+
+    #[test]
+    fn test_to_number() {
+        assert_eq!(XPathObject::from(true).to_number(), 1.0);
+        assert_eq!(XPathObject::from(false).to_number(), 0.0);
+        assert_eq!(XPathObject::from(" -2.5 ".to_string()).to_number(), -2.5);
+        assert!(XPathObject::from("1e3".to_string()).to_number().is_nan());
+        assert!(XPathObject::from(NodeSet::default()).to_number().is_nan());
+    }
+
+    #[test]
+    fn test_to_string_value() {
+        assert_eq!(XPathObject::from(true).to_string_value(), "true");
+        assert_eq!(XPathObject::from(false).to_string_value(), "false");
+        assert_eq!(XPathObject::from(-0.0).to_string_value(), "0");
+        assert_eq!(XPathObject::from(3.0).to_string_value(), "3");
+        assert_eq!(XPathObject::from(f64::NAN).to_string(), "NaN");
+        assert_eq!(XPathObject::from(NodeSet::default()).to_string_value(), "");
+    }
+
+    #[test]
+    fn test_node_set_conversions() {
+        let document_node = read_xml("<a><b>12</b><b>34</b></a>").unwrap();
+        let element = document_node.first_child().unwrap();
+        let value = XPathObject::from(NodeSet::from_iter(element.child_nodes()));
+
+        assert_eq!(value.to_string_value(), "12");
+        assert_eq!(value.to_number(), 12.0);
+        assert!(value.to_boolean());
+        assert_eq!(value.as_node_set().map(|node_set| node_set.len()), Some(2));
+        assert_eq!(
+            value.clone().into_node_set().map(|node_set| node_set.len()),
+            Some(2)
+        );
+        assert_eq!(value, value.clone());
+
+        assert!(XPathObject::from(1.0).as_node_set().is_none());
+        assert!(XPathObject::from(true).into_node_set().is_none());
+    }
+
+    const ORDER_XML: &str = r#"<?xml version="1.0"?>
+<order id="ord-1001">
+    <item sku="a" quantity="2"><price>1.25</price></item>
+    <item sku="b" quantity="1"><price>10</price></item>
+</order>"#;
+
+    #[test]
+    fn test_typed_evaluation() {
+        let document_node = read_xml(ORDER_XML).unwrap();
+        let context = [document_node];
+
+        assert_eq!(evaluate_string("/order/@id", &context).unwrap(), "ord-1001");
+        assert_eq!(evaluate_string("//item/@sku", &context).unwrap(), "a");
+        assert_eq!(evaluate_string("count(//item)", &context).unwrap(), "2");
+        assert_eq!(evaluate_string("//missing", &context).unwrap(), "");
+        assert_eq!(evaluate_number("sum(//price)", &context).unwrap(), 11.25);
+        assert_eq!(evaluate_number("//item/@quantity", &context).unwrap(), 2.0);
+        assert!(evaluate_number("/order/@id", &context).unwrap().is_nan());
+        assert!(evaluate_boolean("//item[@sku = 'b']", &context).unwrap());
+        assert!(!evaluate_boolean("//item[@sku = 'c']", &context).unwrap());
+        assert!(evaluate_boolean("'false'", &context).unwrap());
+    }
+
+    #[test]
+    fn test_select_nodes() {
+        let document_node = read_xml(ORDER_XML).unwrap();
+        let context = [document_node];
+
+        let node_set = select_nodes("//item/@sku | /order", &context).unwrap();
+        assert_eq!(node_set.len(), 3);
+
+        let first = select_first("//item[price > 5]", &context)
+            .unwrap()
+            .unwrap();
+        assert_eq!(first.get_attribute("sku").as_deref(), Some("b"));
+
+        assert!(select_first("//item[price > 50]", &context)
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_select_not_a_node_set() {
+        let document_node = read_xml(ORDER_XML).unwrap();
+        let context = [document_node];
+
+        assert_eq!(
+            select_nodes("'item'", &context).err(),
+            Some(Error::Evaluate(EvaluationError::TypeError {
+                expr: "'item'".to_string(),
+                expected: DataType::NodeSet,
+                found: DataType::String,
+            }))
+        );
+        assert!(matches!(
+            select_first("1 +", &context).err(),
+            Some(Error::Parse(_))
+        ));
+        assert!(matches!(
+            evaluate_string("$undefined", &context).err(),
+            Some(Error::Evaluate(EvaluationError::UnboundVariable(_)))
+        ));
+    }
+
+    #[test]
+    fn test_equality() {
+        assert_eq!(XPathObject::from(1.0), XPathObject::Number(1.0));
+        assert_ne!(XPathObject::from(f64::NAN), XPathObject::Number(f64::NAN));
+        assert_ne!(XPathObject::from(1.0), XPathObject::from("1".to_string()));
+    }
+}
@@ -0,0 +1,112 @@
+use crate::xpath1::model::path::LocationPath;
+use crate::xpath1::model::predicate::{FunctionCall, Predicate};
+use crate::xpath1::model::span::Span;
+use crate::xpath1::model::ToAbbrString;
+use std::fmt::{Display, Formatter, Result};
+
+// ------------------------------------------------------------------------------------------------
+// Public Types
+// ------------------------------------------------------------------------------------------------
+
+///
+/// This models a complete XPath expression, the primary syntactic construct in XPath. Where a
+/// [`LocationPath`](struct.LocationPath.html) always selects a set of nodes, an expression may
+/// evaluate to any of the four basic types; a node-set, a boolean, a number, or a string.
+///
+/// The body of the expression is a tree of [`Predicate`](enum.Predicate.html) values, the same
+/// type used to model the predicates of a `Step`.
+///
+/// Corresponds to the BNF production `Expr` (14).
+///
+#[derive(Clone, Debug)]
+pub struct Expr {
+    root: Predicate,
+    span: Option<Span>,
+}
+
+// ------------------------------------------------------------------------------------------------
+// Implementations
+// ------------------------------------------------------------------------------------------------
+
+impl Display for Expr {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(f, "{}", self.root)
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+
+impl ToAbbrString for Expr {
+    fn to_abbr_string(&self) -> String {
+        self.root.to_abbr_string()
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+
+impl From<Predicate> for Expr {
+    fn from(root: Predicate) -> Self {
+        Self { root, span: None }
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+
+impl From<LocationPath> for Expr {
+    fn from(path: LocationPath) -> Self {
+        Self {
+            root: Predicate::path(path),
+            span: None,
+        }
+    }
+}
+
+impl PartialEq for Expr {
+    fn eq(&self, other: &Self) -> bool {
+        self.root == other.root
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+
+impl Expr {
+    ///
+    /// Construct a new expression with `root` as the body of the expression.
+    ///
+    pub fn with(root: Predicate) -> Self {
+        Self { root, span: None }
+    }
+
+    ///
+    /// Return the body of this expression.
+    ///
+    pub fn root(&self) -> &Predicate {
+        &self.root
+    }
+
+    ///
+    /// Return all the function calls within this expression, in the order they appear.
+    ///
+    pub fn function_calls(&self) -> Vec<&FunctionCall> {
+        self.root.function_calls()
+    }
+
+    ///
+    /// Return all the namespace prefixes used within this expression, in the order they appear.
+    ///
+    pub fn prefixes(&self) -> Vec<String> {
+        self.root.prefixes()
+    }
+
+    ///
+    /// Return the span of the query text this expression was parsed from, if it was parsed.
+    ///
+    pub fn span(&self) -> Option<Span> {
+        self.span
+    }
+
+    pub(crate) fn set_span(&mut self, span: Span) -> &mut Self {
+        self.span = Some(span);
+        self
+    }
+}
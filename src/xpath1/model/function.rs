@@ -1,50 +1,67 @@
+/*!
+The types `Function`, `Argument`, and `DataType` describe the signature of a function callable
+from an expression; the signatures of the core function library are defined here.
+*/
+
 use std::collections::HashMap;
 use std::fmt::{Display, Formatter, Result};
-use std::mem;
-use std::sync::Once;
+use std::slice::Iter;
 
 // ------------------------------------------------------------------------------------------------
 // Public Types
 // ------------------------------------------------------------------------------------------------
 
+///
+/// The type of a function argument, or result.
+///
 #[derive(Clone, Debug, PartialEq)]
 pub enum DataType {
+    /// The value is converted as if by a call to `boolean()`.
     Bool,
+    /// The value is converted as if by a call to `number()`.
     Number,
+    /// The value is converted as if by a call to `string()`.
     String,
+    /// The value is passed without conversion.
     Object,
+    /// The value must be a node-set, there is no conversion to a node-set.
     NodeSet,
 }
 
+///
+/// A single argument in a function signature.
+///
 #[derive(Clone, Debug)]
 pub struct Argument {
     name: String,
     data_type: DataType,
     required: bool,
+    context_default: bool,
 }
 
+///
+/// The signature of a function; its name, arguments, and result type. An omitted optional argument
+/// is not passed to the function, unless it was constructed with
+/// [`Argument::with_context_default`](struct.Argument.html#method.with_context_default), as is
+/// the case for core functions such as `string()`.
+///
 #[derive(Clone, Debug)]
 pub struct Function {
     name: String,
     arguments: Vec<Argument>,
     result_type: DataType,
+    variadic: bool,
 }
 
 // ------------------------------------------------------------------------------------------------
 // Public Functions
 // ------------------------------------------------------------------------------------------------
 
-pub fn is_function(name: &str) -> bool {
-    function_map().contains_key(name)
-}
-
-#[allow(dead_code)]
 pub fn get_function(name: &str) -> Option<Function> {
     let functions = function_map();
-    functions.get(name).map(|f| f.clone())
+    functions.get(name).cloned()
 }
 
-#[allow(dead_code)]
 pub fn required_functions() -> Vec<Function> {
     let functions = function_map();
     functions.values().cloned().collect()
@@ -97,13 +114,46 @@ impl Argument {
     //     Self::new_from(name, data_type, false)
     // }
 
-    pub(crate) fn with(name: &str, data_type: DataType, required: bool) -> Self {
+    /// Construct a new argument named `name` of type `data_type`.
+    pub fn with(name: &str, data_type: DataType, required: bool) -> Self {
         Self {
             name: name.to_string(),
             data_type,
             required,
+            context_default: false,
+        }
+    }
+
+    ///
+    /// Construct a new optional argument named `name` of type `data_type` which, if omitted,
+    /// defaults to a node-set containing just the context node converted to `data_type`.
+    ///
+    pub fn with_context_default(name: &str, data_type: DataType) -> Self {
+        Self {
+            context_default: true,
+            ..Self::with(name, data_type, false)
         }
     }
+
+    /// Return the name of this argument.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Return the type of this argument.
+    pub fn data_type(&self) -> &DataType {
+        &self.data_type
+    }
+
+    /// Returns `true` if this argument must be provided.
+    pub fn is_required(&self) -> bool {
+        self.required
+    }
+
+    /// Returns `true` if this argument defaults to the context node when omitted.
+    pub fn defaults_to_context_node(&self) -> bool {
+        self.context_default
+    }
 }
 
 // ------------------------------------------------------------------------------------------------
@@ -113,13 +163,14 @@ impl Display for Function {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result {
         write!(
             f,
-            "fn {}({}) -> {};",
+            "fn {}({}{}) -> {};",
             self.name,
             self.arguments
                 .iter()
                 .map(|a| a.to_string())
                 .collect::<Vec<String>>()
                 .join(", "),
+            if self.variadic { ", .." } else { "" },
             self.result_type
         )
     }
@@ -128,13 +179,16 @@ impl Display for Function {
 // ------------------------------------------------------------------------------------------------
 
 impl Function {
+    /// Construct a new function signature.
     pub fn with(name: &str, arguments: &[Argument], result_type: DataType) -> Self {
         Self {
             name: name.to_string(),
             arguments: arguments.to_vec(),
             result_type,
+            variadic: false,
         }
     }
+
     pub(crate) fn from_components(
         name: &str,
         arguments: &[(&str, DataType, bool)],
@@ -147,20 +201,65 @@ impl Function {
                 .map(|(n, t, r)| Argument::with(n, t.clone(), *r))
                 .collect(),
             result_type,
+            variadic: false,
         }
     }
+
+    /// Construct a new function signature where the last argument may be repeated any number of
+    /// times.
+    pub fn variadic(name: &str, arguments: &[Argument], result_type: DataType) -> Self {
+        let mut function = Self::with(name, arguments, result_type);
+        function.variadic = true;
+        function
+    }
+
+    ///
+    /// As for `from_components`, however the last argument may be repeated any number of times.
+    ///
+    pub(crate) fn from_variadic_components(
+        name: &str,
+        arguments: &[(&str, DataType, bool)],
+        result_type: DataType,
+    ) -> Self {
+        let mut function = Self::from_components(name, arguments, result_type);
+        function.variadic = true;
+        function
+    }
+
+    /// Return the name of this function.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Return an iterator over the arguments of this function.
+    pub fn arguments(&self) -> Iter<'_, Argument> {
+        self.arguments.iter()
+    }
+
+    /// Return the type of the result of this function.
+    pub fn result_type(&self) -> &DataType {
+        &self.result_type
+    }
+
+    /// Returns `true` if the last argument of this function may be repeated.
+    pub fn is_variadic(&self) -> bool {
+        self.variadic
+    }
+
+    /// Returns `true` if a call to this function with `count` arguments is valid.
+    pub fn accepts_arguments(&self, count: usize) -> bool {
+        let required = self.arguments.iter().filter(|a| a.required).count();
+        count >= required && (self.variadic || count <= self.arguments.len())
+    }
 }
 
 // ------------------------------------------------------------------------------------------------
 // Private Functions
 // ------------------------------------------------------------------------------------------------
 
-fn function_map() -> HashMap<String, Function> {
-    static mut FUNCTIONS: *const HashMap<String, Function> = 0 as *const HashMap<String, Function>;
-    static INIT: Once = Once::new();
-
-    unsafe {
-        INIT.call_once(|| {
+fn function_map() -> &'static HashMap<String, Function> {
+    lazy_static! {
+        static ref FUNCTIONS: HashMap<String, Function> = {
             let all_functions = vec![
                 // 4.1 Node Set Functions
                 Function::with("last", &[], DataType::Number),
@@ -175,28 +274,28 @@ fn function_map() -> HashMap<String, Function> {
                     &[("object", DataType::Object, true)],
                     DataType::NodeSet,
                 ),
-                Function::from_components(
+                Function::with(
                     "local-name",
-                    &[("node-set?", DataType::NodeSet, false)],
+                    &[Argument::with_context_default("node-set?", DataType::NodeSet)],
                     DataType::String,
                 ),
-                Function::from_components(
+                Function::with(
                     "namespace-uri",
-                    &[("node-set?", DataType::NodeSet, false)],
+                    &[Argument::with_context_default("node-set?", DataType::NodeSet)],
                     DataType::String,
                 ),
-                Function::from_components(
+                Function::with(
                     "name",
-                    &[("node-set?", DataType::NodeSet, false)],
+                    &[Argument::with_context_default("node-set?", DataType::NodeSet)],
                     DataType::String,
                 ),
                 // 4.2 String Functions
-                Function::from_components(
+                Function::with(
                     "string",
-                    &[("object", DataType::Object, false)],
+                    &[Argument::with_context_default("object", DataType::Object)],
                     DataType::String,
                 ),
-                Function::from_components(
+                Function::from_variadic_components(
                     "concat",
                     &[
                         ("string-1", DataType::String, true),
@@ -210,7 +309,7 @@ fn function_map() -> HashMap<String, Function> {
                         ("string", DataType::String, true),
                         ("test-prefix", DataType::String, true),
                     ],
-                    DataType::String,
+                    DataType::Bool,
                 ),
                 Function::from_components(
                     "contains",
@@ -218,7 +317,7 @@ fn function_map() -> HashMap<String, Function> {
                         ("string", DataType::String, true),
                         ("test-in", DataType::String, true),
                     ],
-                    DataType::String,
+                    DataType::Bool,
                 ),
                 Function::from_components(
                     "substring-before",
@@ -245,14 +344,14 @@ fn function_map() -> HashMap<String, Function> {
                     ],
                     DataType::String,
                 ),
-                Function::from_components(
+                Function::with(
                     "string-length",
-                    &[("string", DataType::String, false)],
-                    DataType::String,
+                    &[Argument::with_context_default("string", DataType::String)],
+                    DataType::Number,
                 ),
-                Function::from_components(
+                Function::with(
                     "normalize-space",
-                    &[("string", DataType::String, false)],
+                    &[Argument::with_context_default("string", DataType::String)],
                     DataType::String,
                 ),
                 Function::from_components(
@@ -283,9 +382,9 @@ fn function_map() -> HashMap<String, Function> {
                     DataType::Bool,
                 ),
                 // 4.4 Number Functions
-                Function::from_components(
+                Function::with(
                     "number",
-                    &[("object", DataType::Object, true)],
+                    &[Argument::with_context_default("object", DataType::Object)],
                     DataType::Number,
                 ),
                 Function::from_components(
@@ -309,14 +408,13 @@ fn function_map() -> HashMap<String, Function> {
                     DataType::Number,
                 ),
             ];
-            let all_functions: HashMap<String, Function> = all_functions
+            all_functions
                 .iter()
                 .map(|f| (f.name.clone(), f.clone()))
-                .collect();
-            FUNCTIONS = mem::transmute(Box::new(all_functions));
-        });
-        (*FUNCTIONS).clone()
+                .collect()
+        };
     }
+    &FUNCTIONS
 }
 
 // ------------------------------------------------------------------------------------------------
@@ -334,4 +432,22 @@ mod tests {
             println!("{}", function);
         }
     }
+
+    #[test]
+    fn test_accepts_arguments() {
+        let function = get_function("position").unwrap();
+        assert!(function.accepts_arguments(0));
+        assert!(!function.accepts_arguments(1));
+
+        let function = get_function("substring").unwrap();
+        assert!(!function.accepts_arguments(1));
+        assert!(function.accepts_arguments(2));
+        assert!(function.accepts_arguments(3));
+        assert!(!function.accepts_arguments(4));
+
+        let function = get_function("concat").unwrap();
+        assert!(!function.accepts_arguments(1));
+        assert!(function.accepts_arguments(2));
+        assert!(function.accepts_arguments(12));
+    }
 }
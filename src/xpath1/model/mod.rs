@@ -54,7 +54,10 @@ mod select;
 pub use select::{AxisSpecifier, NodeTest, Select};
 
 mod predicate;
-pub use predicate::{ExprNode, FunctionCall, Predicate, Terminal};
+pub use predicate::{ExprNode, FilterExpr, FunctionCall, Predicate, Terminal};
+
+mod qname;
+pub use qname::QName;
 
 mod step;
 pub use step::Step;
@@ -62,4 +65,12 @@ pub use step::Step;
 mod path;
 pub use path::LocationPath;
 
+mod expr;
+pub use expr::Expr;
+
+mod span;
+pub use span::Span;
+
 mod function;
+pub(crate) use function::{get_function, required_functions};
+pub use function::{Argument, DataType, Function};
@@ -1,3 +1,5 @@
+use crate::xpath1::model::predicate::{FunctionCall, Predicate};
+use crate::xpath1::model::span::Span;
 use crate::xpath1::model::step::Step;
 use crate::xpath1::model::ToAbbrString;
 use std::fmt::{Display, Formatter, Result};
@@ -15,10 +17,11 @@ use std::slice::Iter;
 ///
 /// Corresponds to the BNF production `LocationPath` (1).
 ///
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Default)]
 pub struct LocationPath {
     root: bool,
     steps: Vec<Step>,
+    span: Option<Span>,
 }
 
 // ------------------------------------------------------------------------------------------------
@@ -50,17 +53,6 @@ macro_rules! path_fn {
 // Implementations
 // ------------------------------------------------------------------------------------------------
 
-impl Default for LocationPath {
-    fn default() -> Self {
-        Self {
-            root: false,
-            steps: Vec::default(),
-        }
-    }
-}
-
-// ------------------------------------------------------------------------------------------------
-
 impl Display for LocationPath {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result {
         write!(
@@ -80,18 +72,37 @@ impl Display for LocationPath {
 
 impl ToAbbrString for LocationPath {
     fn to_abbr_string(&self) -> String {
+        let last = self.steps.len().saturating_sub(1);
         format!(
             "{}{}",
             if self.root { "/" } else { "" },
             self.steps
                 .iter()
-                .map(|s| s.to_abbr_string())
+                .enumerate()
+                .map(|(i, s)| {
+                    // `/descendant-or-self::node()/` is abbreviated to `//`, so the step itself
+                    // disappears between the two separators.
+                    let abbr = s.to_abbr_string();
+                    if abbr == "//" && (self.root || i > 0) && i < last {
+                        String::new()
+                    } else if abbr == "//" {
+                        s.to_string()
+                    } else {
+                        abbr
+                    }
+                })
                 .collect::<Vec<String>>()
                 .join("/")
         )
     }
 }
 
+impl PartialEq for LocationPath {
+    fn eq(&self, other: &Self) -> bool {
+        self.root == other.root && self.steps == other.steps
+    }
+}
+
 // ------------------------------------------------------------------------------------------------
 
 impl LocationPath {
@@ -102,6 +113,7 @@ impl LocationPath {
         Self {
             root: false,
             steps: vec![step],
+            span: None,
         }
     }
 
@@ -112,6 +124,7 @@ impl LocationPath {
         Self {
             root: true,
             steps: Vec::default(),
+            span: None,
         }
     }
 
@@ -122,6 +135,7 @@ impl LocationPath {
         Self {
             root: true,
             steps: vec![step],
+            span: None,
         }
     }
 
@@ -150,10 +164,55 @@ impl LocationPath {
     ///
     /// Return an iterator over the steps in this path.
     ///
-    pub fn steps(&self) -> Iter<Step> {
+    pub fn steps(&self) -> Iter<'_, Step> {
         self.steps.iter()
     }
 
+    ///
+    /// Return all the function calls within the predicates of this path, in the order they
+    /// appear.
+    ///
+    pub fn function_calls(&self) -> Vec<&FunctionCall> {
+        self.steps
+            .iter()
+            .flat_map(|step| step.predicate_exprs())
+            .flat_map(Predicate::function_calls)
+            .collect()
+    }
+
+    ///
+    /// Return all the namespace prefixes used within the node tests and predicates of this path,
+    /// in the order they appear.
+    ///
+    pub fn prefixes(&self) -> Vec<String> {
+        self.steps
+            .iter()
+            .flat_map(|step| {
+                let mut prefixes: Vec<String> = step
+                    .select_expr()
+                    .node_test()
+                    .prefix()
+                    .map(str::to_string)
+                    .into_iter()
+                    .collect();
+                prefixes.extend(step.predicate_exprs().flat_map(Predicate::prefixes));
+                prefixes
+            })
+            .collect()
+    }
+
+    ///
+    /// Return the span of the query text this path was parsed from, if it was parsed.
+    ///
+    pub fn span(&self) -> Option<Span> {
+        self.span
+    }
+
+    pub(crate) fn set_span(&mut self, span: Span) -> &mut Self {
+        self.span = Some(span);
+        self
+    }
+
     path_fn!(all_ancestors);
     path_fn!(all_ancestor_elements);
     path_fn!(all_ancestor_text);
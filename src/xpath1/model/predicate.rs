@@ -1,8 +1,11 @@
-use crate::xpath1::model::function::is_function;
+use crate::xpath1::model::path::LocationPath;
 use crate::xpath1::model::select::Select;
-use crate::xpath1::model::{AxisSpecifier, NodeTest, ToAbbrString};
+use crate::xpath1::model::span::Span;
+use crate::xpath1::model::{AxisSpecifier, NodeTest, QName, ToAbbrString};
 use std::borrow::Borrow;
 use std::fmt::{Display, Formatter, Result};
+use std::mem::discriminant;
+use std::slice::Iter;
 
 // ------------------------------------------------------------------------------------------------
 // Public Types
@@ -13,7 +16,7 @@ use std::fmt::{Display, Formatter, Result};
 ///
 /// Corresponds to the BNF production `Predicate` (8).
 ///
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum Predicate {
     /// An expression
     Expr(ExprNode),
@@ -21,11 +24,14 @@ pub enum Predicate {
     Terminal(Terminal),
     /// A function call.
     Function(FunctionCall),
+    /// A filter expression.
+    Filter(FilterExpr),
 }
 
 ///
 /// This models the set of binary (and one unary) expressions. Note that we use the parser to
-/// determine precedence so that the tree build using these nodes is precedence-unaware.
+/// determine precedence so that the tree build using these nodes is precedence-unaware; when
+/// formatted, operands are parenthesized where needed to preserve the shape of the tree.
 ///
 /// Corresponds to the BNF productions 14, 18-27.
 ///
@@ -35,74 +41,97 @@ pub enum ExprNode {
     And {
         left: Box<Predicate>,
         right: Box<Predicate>,
+        span: Option<Span>,
     },
     /// Predicate `"or"` Predicate
     Or {
         left: Box<Predicate>,
         right: Box<Predicate>,
+        span: Option<Span>,
     },
     /// Predicate `"="` Predicate
     Equals {
         left: Box<Predicate>,
         right: Box<Predicate>,
+        span: Option<Span>,
     },
     /// Predicate `"!="` Predicate
     NotEquals {
         left: Box<Predicate>,
         right: Box<Predicate>,
+        span: Option<Span>,
     },
     /// Predicate `"<"` Predicate
     LessThan {
         left: Box<Predicate>,
         right: Box<Predicate>,
+        span: Option<Span>,
     },
     /// Predicate `"<="` Predicate
     LessThanOrEqual {
         left: Box<Predicate>,
         right: Box<Predicate>,
+        span: Option<Span>,
     },
     /// Predicate `">"` Predicate
     GreaterThan {
         left: Box<Predicate>,
         right: Box<Predicate>,
+        span: Option<Span>,
     },
     /// Predicate `">="` Predicate
     GreaterThanOrEqual {
         left: Box<Predicate>,
         right: Box<Predicate>,
+        span: Option<Span>,
     },
     /// Predicate `"+"` Predicate
     Add {
         left: Box<Predicate>,
         right: Box<Predicate>,
+        span: Option<Span>,
     },
     /// Predicate `"-"` Predicate
     Subtract {
         left: Box<Predicate>,
         right: Box<Predicate>,
+        span: Option<Span>,
     },
     /// Predicate `"*"` Predicate
     Multiply {
         left: Box<Predicate>,
         right: Box<Predicate>,
+        span: Option<Span>,
     },
     /// Predicate `"/"` Predicate
     Divide {
         left: Box<Predicate>,
         right: Box<Predicate>,
+        span: Option<Span>,
     },
     /// Predicate `"mod"` Predicate
     Modulus {
         left: Box<Predicate>,
         right: Box<Predicate>,
+        span: Option<Span>,
     },
     /// Predicate `"div"` Predicate
     FPDiv {
         left: Box<Predicate>,
         right: Box<Predicate>,
+        span: Option<Span>,
     },
     /// `"-"` Predicate
-    UnaryMinus { value: Box<Predicate> },
+    UnaryMinus {
+        value: Box<Predicate>,
+        span: Option<Span>,
+    },
+    /// Predicate `"|"` Predicate
+    Union {
+        left: Box<Predicate>,
+        right: Box<Predicate>,
+        span: Option<Span>,
+    },
 }
 
 ///
@@ -112,31 +141,62 @@ pub enum ExprNode {
 ///
 #[derive(Clone, Debug)]
 pub enum Terminal {
-    /// A variable reference
-    Variable(String),
-    /// A String literal
-    Literal(String),
-    /// A Number value, note that the specification makes these all floats
-    Number(f64),
+    /// A variable reference, and the span it was parsed from
+    Variable(QName, Option<Span>),
+    /// A String literal, and the span it was parsed from
+    Literal(String, Option<Span>),
+    /// A Number value, note that the specification makes these all floats, and the span it was
+    /// parsed from
+    Number(f64, Option<Span>),
     /// A Select expression
     Select(Select),
+    /// A location path that is not simply a single `Select` expression
+    Path(LocationPath),
 }
 
 ///
-/// This models a call to one of the set of pre-defined functions.
+/// This models a call to a function, either one of the set of pre-defined functions in the core
+/// library or an extension function.
 ///
 /// Corresponds to the BNF production `FunctionCall` (16).
 ///
 #[derive(Clone, Debug)]
 pub struct FunctionCall {
-    name: String,
+    name: QName,
     arguments: Vec<Predicate>,
+    span: Option<Span>,
+}
+
+///
+/// This models a filter expression, a primary expression followed by zero or more predicates and
+/// optionally a relative location path evaluated from the resulting node-set.
+///
+/// Corresponds to the BNF productions `PathExpr` (19) and `FilterExpr` (20).
+///
+#[derive(Clone, Debug)]
+pub struct FilterExpr {
+    primary: Box<Predicate>,
+    predicates: Vec<Predicate>,
+    path: Option<LocationPath>,
+    span: Option<Span>,
 }
 
 // ------------------------------------------------------------------------------------------------
 // Public Functions
 // ------------------------------------------------------------------------------------------------
 
+///
+/// Format `value` as a `Literal` (29), using double quotes where the value contains a single
+/// quote. A value containing both cannot be written as a literal, and is single quoted.
+///
+pub(crate) fn quote_literal(value: &str) -> String {
+    if value.contains('\'') && !value.contains('"') {
+        format!("\"{}\"", value)
+    } else {
+        format!("'{}'", value)
+    }
+}
+
 // ------------------------------------------------------------------------------------------------
 // Macros
 // ------------------------------------------------------------------------------------------------
@@ -148,6 +208,7 @@ macro_rules! predicate_fn {
             Predicate::Expr(ExprNode::$expr_t {
                 left: Box::new(left),
                 right: Box::new(right),
+                span: None,
             })
         }
     };
@@ -166,6 +227,7 @@ impl Display for Predicate {
                 Predicate::Expr(v) => v.to_string(),
                 Predicate::Terminal(v) => v.to_string(),
                 Predicate::Function(v) => v.to_string(),
+                Predicate::Filter(v) => v.to_string(),
             }
         )
     }
@@ -178,33 +240,36 @@ impl ToAbbrString for Predicate {
         match self {
             Predicate::Expr(v) => v.to_abbr_string(),
             Predicate::Terminal(Terminal::Select(v)) => v.to_abbr_string(),
+            Predicate::Terminal(Terminal::Path(v)) => v.to_abbr_string(),
             Predicate::Terminal(v) => v.to_string(),
             Predicate::Function(v) => v.to_string(),
+            Predicate::Filter(v) => v.to_abbr_string(),
         }
     }
 }
 
 // ------------------------------------------------------------------------------------------------
 
+#[allow(clippy::should_implement_trait)]
 impl Predicate {
     /// Construct a new Predicate as simply a string literal value.
     pub fn literal(value: &str) -> Self {
-        Predicate::Terminal(Terminal::Literal(value.to_string()))
+        Predicate::Terminal(Terminal::Literal(value.to_string(), None))
     }
 
     /// Construct a new Predicate as simply a number value.
     pub fn number(value: f64) -> Self {
-        Predicate::Terminal(Terminal::Number(value))
+        Predicate::Terminal(Terminal::Number(value, None))
     }
 
     /// Construct a new Predicate as simply a number value (casting from integer to float).
     pub fn integer(value: i32) -> Self {
-        Predicate::Terminal(Terminal::Number(value as f64))
+        Predicate::Terminal(Terminal::Number(value as f64, None))
     }
 
     /// Construct a new Predicate as simply a variable reference.
     pub fn variable(named: &str) -> Self {
-        Predicate::Terminal(Terminal::Variable(named.to_string()))
+        Predicate::Terminal(Terminal::Variable(QName::from(named), None))
     }
 
     /// Construct a new Predicate as simply a function call (no arguments).
@@ -222,6 +287,16 @@ impl Predicate {
         Predicate::Terminal(Terminal::Select(Select::with(axis, node_test)))
     }
 
+    /// Construct a new Predicate as simply a location path.
+    pub fn path(path: LocationPath) -> Self {
+        Predicate::Terminal(Terminal::Path(path))
+    }
+
+    /// Construct a new Predicate as a filter expression.
+    pub fn filter(filter: FilterExpr) -> Self {
+        Predicate::Filter(filter)
+    }
+
     predicate_fn!(and, And);
     predicate_fn!(or, Or);
     predicate_fn!(eq, Equals);
@@ -236,13 +311,106 @@ impl Predicate {
     predicate_fn!(divide, Divide);
     predicate_fn!(a_mod, Modulus);
     predicate_fn!(div, FPDiv);
+    predicate_fn!(union, Union);
 
     /// Construct a unary minus predicate with the value provided.
     pub fn minus(value: Predicate) -> Self {
         Predicate::Expr(ExprNode::UnaryMinus {
             value: Box::new(value),
+            span: None,
         })
     }
+
+    /// Return all the function calls within this expression, in the order they appear.
+    pub fn function_calls(&self) -> Vec<&FunctionCall> {
+        match self {
+            Predicate::Expr(expr) => expr
+                .operands()
+                .into_iter()
+                .flat_map(Predicate::function_calls)
+                .collect(),
+            Predicate::Terminal(Terminal::Path(path)) => path.function_calls(),
+            Predicate::Terminal(_) => Vec::new(),
+            Predicate::Function(call) => {
+                let mut calls = vec![call];
+                calls.extend(call.arguments().flat_map(Predicate::function_calls));
+                calls
+            }
+            Predicate::Filter(filter) => {
+                let mut calls = filter.primary_expr().function_calls();
+                calls.extend(filter.predicate_exprs().flat_map(Predicate::function_calls));
+                if let Some(path) = filter.path() {
+                    calls.extend(path.function_calls());
+                }
+                calls
+            }
+        }
+    }
+
+    ///
+    /// Return all the namespace prefixes used within this expression, by node tests, variable
+    /// references, and function names, in the order they appear.
+    ///
+    pub fn prefixes(&self) -> Vec<String> {
+        let prefix_of = |name: &QName| name.prefix().map(str::to_string);
+        match self {
+            Predicate::Expr(expr) => expr
+                .operands()
+                .into_iter()
+                .flat_map(Predicate::prefixes)
+                .collect(),
+            Predicate::Terminal(Terminal::Variable(name, _)) => {
+                prefix_of(name).into_iter().collect()
+            }
+            Predicate::Terminal(Terminal::Select(select)) => select
+                .node_test()
+                .prefix()
+                .map(str::to_string)
+                .into_iter()
+                .collect(),
+            Predicate::Terminal(Terminal::Path(path)) => path.prefixes(),
+            Predicate::Terminal(_) => Vec::new(),
+            Predicate::Function(call) => {
+                let mut prefixes: Vec<String> = prefix_of(call.name()).into_iter().collect();
+                prefixes.extend(call.arguments().flat_map(Predicate::prefixes));
+                prefixes
+            }
+            Predicate::Filter(filter) => {
+                let mut prefixes = filter.primary_expr().prefixes();
+                prefixes.extend(filter.predicate_exprs().flat_map(Predicate::prefixes));
+                if let Some(path) = filter.path() {
+                    prefixes.extend(path.prefixes());
+                }
+                prefixes
+            }
+        }
+    }
+
+    ///
+    /// Return the span of the query text this expression was parsed from, if it was parsed.
+    ///
+    pub fn span(&self) -> Option<Span> {
+        match self {
+            Predicate::Expr(v) => v.span(),
+            Predicate::Terminal(v) => v.span(),
+            Predicate::Function(v) => v.span(),
+            Predicate::Filter(v) => v.span(),
+        }
+    }
+
+    pub(crate) fn with_span(mut self, span: Span) -> Self {
+        match &mut self {
+            Predicate::Expr(v) => v.set_span(span),
+            Predicate::Terminal(v) => v.set_span(span),
+            Predicate::Function(v) => {
+                let _ = v.set_span(span);
+            }
+            Predicate::Filter(v) => {
+                let _ = v.set_span(span);
+            }
+        }
+        self
+    }
 }
 
 // ------------------------------------------------------------------------------------------------
@@ -264,64 +432,159 @@ impl ToAbbrString for ExprNode {
 
 // ------------------------------------------------------------------------------------------------
 
+impl PartialEq for ExprNode {
+    fn eq(&self, other: &Self) -> bool {
+        discriminant(self) == discriminant(other) && self.operands() == other.operands()
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+
 impl ExprNode {
+    /// Return the span of the query text this expression was parsed from, if it was parsed.
+    pub fn span(&self) -> Option<Span> {
+        match self {
+            ExprNode::And { span, .. }
+            | ExprNode::Or { span, .. }
+            | ExprNode::Equals { span, .. }
+            | ExprNode::NotEquals { span, .. }
+            | ExprNode::LessThan { span, .. }
+            | ExprNode::LessThanOrEqual { span, .. }
+            | ExprNode::GreaterThan { span, .. }
+            | ExprNode::GreaterThanOrEqual { span, .. }
+            | ExprNode::Add { span, .. }
+            | ExprNode::Subtract { span, .. }
+            | ExprNode::Multiply { span, .. }
+            | ExprNode::Divide { span, .. }
+            | ExprNode::Modulus { span, .. }
+            | ExprNode::FPDiv { span, .. }
+            | ExprNode::UnaryMinus { span, .. }
+            | ExprNode::Union { span, .. } => *span,
+        }
+    }
+
+    pub(crate) fn set_span(&mut self, new_span: Span) {
+        match self {
+            ExprNode::And { span, .. }
+            | ExprNode::Or { span, .. }
+            | ExprNode::Equals { span, .. }
+            | ExprNode::NotEquals { span, .. }
+            | ExprNode::LessThan { span, .. }
+            | ExprNode::LessThanOrEqual { span, .. }
+            | ExprNode::GreaterThan { span, .. }
+            | ExprNode::GreaterThanOrEqual { span, .. }
+            | ExprNode::Add { span, .. }
+            | ExprNode::Subtract { span, .. }
+            | ExprNode::Multiply { span, .. }
+            | ExprNode::Divide { span, .. }
+            | ExprNode::Modulus { span, .. }
+            | ExprNode::FPDiv { span, .. }
+            | ExprNode::UnaryMinus { span, .. }
+            | ExprNode::Union { span, .. } => *span = Some(new_span),
+        }
+    }
+
+    /// Return the operands of this expression, left before right.
+    pub fn operands(&self) -> Vec<&Predicate> {
+        match self {
+            ExprNode::And { left, right, .. }
+            | ExprNode::Or { left, right, .. }
+            | ExprNode::Equals { left, right, .. }
+            | ExprNode::NotEquals { left, right, .. }
+            | ExprNode::LessThan { left, right, .. }
+            | ExprNode::LessThanOrEqual { left, right, .. }
+            | ExprNode::GreaterThan { left, right, .. }
+            | ExprNode::GreaterThanOrEqual { left, right, .. }
+            | ExprNode::Add { left, right, .. }
+            | ExprNode::Subtract { left, right, .. }
+            | ExprNode::Multiply { left, right, .. }
+            | ExprNode::Divide { left, right, .. }
+            | ExprNode::Modulus { left, right, .. }
+            | ExprNode::FPDiv { left, right, .. }
+            | ExprNode::Union { left, right, .. } => vec![left, right],
+            ExprNode::UnaryMinus { value, .. } => vec![value],
+        }
+    }
+
     pub(crate) fn to_some_string(&self, abbr: bool) -> String {
-        let format_fn = if abbr {
-            Predicate::to_abbr_string
-        } else {
-            Predicate::to_string
+        // Operands that bind less tightly than this expression are parenthesized, as are right
+        // operands at the same level, so that the string parses back to the same tree.
+        let precedence = self.precedence();
+        let format_fn = |operand: &Predicate, wrap_at: u8| {
+            let operand_str = if abbr {
+                operand.to_abbr_string()
+            } else {
+                operand.to_string()
+            };
+            match operand {
+                Predicate::Expr(expr) if expr.precedence() < wrap_at => {
+                    format!("({})", operand_str)
+                }
+                _ => operand_str,
+            }
+        };
+        let binary = |left: &Predicate, op: &str, right: &Predicate| {
+            format!(
+                "{} {} {}",
+                format_fn(left, precedence),
+                op,
+                format_fn(right, precedence + 1)
+            )
         };
         match self {
-            ExprNode::And { left, right } => {
-                format!("{} and {}", format_fn(left), format_fn(right))
-            }
-            ExprNode::Or { left, right } => format!("{} or {}", format_fn(left), format_fn(right)),
-            ExprNode::Equals { left, right } => match (abbr, left.borrow(), right.borrow()) {
+            ExprNode::And { left, right, .. } => binary(left, "and", right),
+            ExprNode::Or { left, right, .. } => binary(left, "or", right),
+            ExprNode::Equals { left, right, .. } => match (abbr, left.borrow(), right.borrow()) {
                 (
                     true,
-                    Predicate::Function(FunctionCall { name, arguments }),
-                    Predicate::Terminal(Terminal::Number(n)),
+                    Predicate::Function(FunctionCall {
+                        name, arguments, ..
+                    }),
+                    Predicate::Terminal(Terminal::Number(n, _)),
                 ) => {
-                    if name == &"position".to_string() && arguments.is_empty() {
+                    if name == &QName::with("position") && arguments.is_empty() {
                         format!("{}", n)
                     } else {
-                        format!("{} = {}", format_fn(left), format_fn(right))
+                        binary(left, "=", right)
                     }
                 }
-                _ => format!("{} = {}", format_fn(left), format_fn(right)),
+                _ => binary(left, "=", right),
             },
-            ExprNode::NotEquals { left, right } => {
-                format!("{} != {}", format_fn(left), format_fn(right))
-            }
-            ExprNode::LessThan { left, right } => {
-                format!("{} < {}", format_fn(left), format_fn(right))
-            }
-            ExprNode::LessThanOrEqual { left, right } => {
-                format!("{} <= {}", format_fn(left), format_fn(right))
-            }
-            ExprNode::GreaterThan { left, right } => {
-                format!("{} > {}", format_fn(left), format_fn(right))
-            }
-            ExprNode::GreaterThanOrEqual { left, right } => {
-                format!("{} >= {}", format_fn(left), format_fn(right))
-            }
-            ExprNode::Add { left, right } => format!("{} + {}", format_fn(left), format_fn(right)),
-            ExprNode::Subtract { left, right } => {
-                format!("{} - {}", format_fn(left), format_fn(right))
-            }
-            ExprNode::Multiply { left, right } => {
-                format!("{} * {}", format_fn(left), format_fn(right))
-            }
-            ExprNode::Divide { left, right } => {
-                format!("{} / {}", format_fn(left), format_fn(right))
-            }
-            ExprNode::Modulus { left, right } => {
-                format!("{} mod {}", format_fn(left), format_fn(right))
-            }
-            ExprNode::FPDiv { left, right } => {
-                format!("{} div {}", format_fn(left), format_fn(right))
-            }
-            ExprNode::UnaryMinus { value } => format!("- {}", format_fn(value)),
+            ExprNode::NotEquals { left, right, .. } => binary(left, "!=", right),
+            ExprNode::LessThan { left, right, .. } => binary(left, "<", right),
+            ExprNode::LessThanOrEqual { left, right, .. } => binary(left, "<=", right),
+            ExprNode::GreaterThan { left, right, .. } => binary(left, ">", right),
+            ExprNode::GreaterThanOrEqual { left, right, .. } => binary(left, ">=", right),
+            ExprNode::Add { left, right, .. } => binary(left, "+", right),
+            ExprNode::Subtract { left, right, .. } => binary(left, "-", right),
+            ExprNode::Multiply { left, right, .. } => binary(left, "*", right),
+            ExprNode::Divide { left, right, .. } => binary(left, "/", right),
+            ExprNode::Modulus { left, right, .. } => binary(left, "mod", right),
+            ExprNode::FPDiv { left, right, .. } => binary(left, "div", right),
+            ExprNode::UnaryMinus { value, .. } => format!("- {}", format_fn(value, precedence)),
+            ExprNode::Union { left, right, .. } => binary(left, "|", right),
+        }
+    }
+
+    //
+    // How tightly each operator binds, from `or` (production 21) up to `|` (production 18).
+    //
+    fn precedence(&self) -> u8 {
+        match self {
+            ExprNode::Or { .. } => 1,
+            ExprNode::And { .. } => 2,
+            ExprNode::Equals { .. } | ExprNode::NotEquals { .. } => 3,
+            ExprNode::LessThan { .. }
+            | ExprNode::LessThanOrEqual { .. }
+            | ExprNode::GreaterThan { .. }
+            | ExprNode::GreaterThanOrEqual { .. } => 4,
+            ExprNode::Add { .. } | ExprNode::Subtract { .. } => 5,
+            ExprNode::Multiply { .. }
+            | ExprNode::Divide { .. }
+            | ExprNode::Modulus { .. }
+            | ExprNode::FPDiv { .. } => 6,
+            ExprNode::UnaryMinus { .. } => 7,
+            ExprNode::Union { .. } => 8,
         }
     }
 }
@@ -335,10 +598,11 @@ impl Display for Terminal {
             f,
             "{}",
             match self {
-                Terminal::Variable(v) => format!("${}", v),
-                Terminal::Literal(v) => format!("'{}'", v),
-                Terminal::Number(v) => format!("{}", v),
+                Terminal::Variable(v, _) => format!("${}", v),
+                Terminal::Literal(v, _) => quote_literal(v),
+                Terminal::Number(v, _) => format!("{}", v),
                 Terminal::Select(v) => format!("{}", v),
+                Terminal::Path(v) => format!("{}", v),
             }
         )
     }
@@ -348,6 +612,50 @@ impl Display for Terminal {
 
 impl ToAbbrString for Terminal {}
 
+// ------------------------------------------------------------------------------------------------
+
+impl PartialEq for Terminal {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Terminal::Variable(lhs, _), Terminal::Variable(rhs, _)) => lhs == rhs,
+            (Terminal::Literal(lhs, _), Terminal::Literal(rhs, _)) => lhs == rhs,
+            (Terminal::Number(lhs, _), Terminal::Number(rhs, _)) => lhs == rhs,
+            (Terminal::Select(lhs), Terminal::Select(rhs)) => lhs == rhs,
+            (Terminal::Path(lhs), Terminal::Path(rhs)) => lhs == rhs,
+            _ => false,
+        }
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+
+impl Terminal {
+    /// Return the span of the query text this value was parsed from, if it was parsed.
+    pub fn span(&self) -> Option<Span> {
+        match self {
+            Terminal::Variable(_, span)
+            | Terminal::Literal(_, span)
+            | Terminal::Number(_, span) => *span,
+            Terminal::Select(select) => select.span(),
+            Terminal::Path(path) => path.span(),
+        }
+    }
+
+    pub(crate) fn set_span(&mut self, new_span: Span) {
+        match self {
+            Terminal::Variable(_, span)
+            | Terminal::Literal(_, span)
+            | Terminal::Number(_, span) => *span = Some(new_span),
+            Terminal::Select(select) => {
+                let _ = select.set_span(new_span);
+            }
+            Terminal::Path(path) => {
+                let _ = path.set_span(new_span);
+            }
+        }
+    }
+}
+
 // ------------------------------------------------------------------------------------------------
 // ------------------------------------------------------------------------------------------------
 
@@ -372,6 +680,14 @@ impl ToAbbrString for FunctionCall {}
 
 // ------------------------------------------------------------------------------------------------
 
+impl PartialEq for FunctionCall {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name && self.arguments == other.arguments
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+
 impl FunctionCall {
     /// Construct a new function call to the function named `name`.
     pub fn with(name: &str) -> Self {
@@ -379,12 +695,21 @@ impl FunctionCall {
     }
 
     /// Construct a new function call to the function named `name` with the provided `arguments`.
+    ///
+    /// Note that neither the name nor the number of arguments are validated here, a call is
+    /// checked against the signatures in a
+    /// [`FunctionRegistry`](../evaluate/struct.FunctionRegistry.html) when parsed or evaluated.
     pub fn with_both(name: &str, arguments: &[Predicate]) -> Self {
-        assert!(is_function(name));
-        // TODO: validate arg count
+        Self::with_qname(QName::from(name), arguments)
+    }
+
+    /// Construct a new function call to the function with the qualified name `name` with the
+    /// provided `arguments`.
+    pub fn with_qname(name: QName, arguments: &[Predicate]) -> Self {
         FunctionCall {
-            name: name.to_string(),
+            name,
             arguments: arguments.to_vec(),
+            span: None,
         }
     }
 
@@ -392,4 +717,139 @@ impl FunctionCall {
     pub fn append(&mut self, argument: Predicate) {
         self.arguments.push(argument);
     }
+
+    /// Return the name of the function being called.
+    pub fn name(&self) -> &QName {
+        &self.name
+    }
+
+    /// Return an iterator over the arguments made to this function.
+    pub fn arguments(&self) -> Iter<'_, Predicate> {
+        self.arguments.iter()
+    }
+
+    /// Return the span of the query text this function call was parsed from, if it was parsed.
+    pub fn span(&self) -> Option<Span> {
+        self.span
+    }
+
+    pub(crate) fn set_span(&mut self, span: Span) -> &mut Self {
+        self.span = Some(span);
+        self
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+// ------------------------------------------------------------------------------------------------
+
+impl Display for FilterExpr {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(f, "{}", self.to_some_string(false))
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+
+impl ToAbbrString for FilterExpr {
+    fn to_abbr_string(&self) -> String {
+        self.to_some_string(true)
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+
+impl PartialEq for FilterExpr {
+    fn eq(&self, other: &Self) -> bool {
+        self.primary == other.primary
+            && self.predicates == other.predicates
+            && self.path == other.path
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+
+impl FilterExpr {
+    /// Construct a new filter expression with the provided `primary` expression.
+    pub fn with(primary: Predicate) -> Self {
+        Self {
+            primary: Box::new(primary),
+            predicates: Default::default(),
+            path: None,
+            span: None,
+        }
+    }
+
+    /// Append `predicate` to the list of `Predicate`s applied to the primary expression.
+    pub fn append(&mut self, predicate: Predicate) -> &mut Self {
+        self.predicates.push(predicate);
+        self
+    }
+
+    /// Set the relative location path evaluated from the filtered node-set.
+    pub fn set_path(&mut self, path: LocationPath) -> &mut Self {
+        self.path = Some(path);
+        self
+    }
+
+    /// Return the primary expression.
+    pub fn primary_expr(&self) -> &Predicate {
+        &self.primary
+    }
+
+    /// Return an iterator over the `Predicate`s applied to the primary expression.
+    pub fn predicate_exprs(&self) -> Iter<'_, Predicate> {
+        self.predicates.iter()
+    }
+
+    /// Return the relative location path evaluated from the filtered node-set, if present.
+    pub fn path(&self) -> Option<&LocationPath> {
+        self.path.as_ref()
+    }
+
+    /// Return the span of the query text this filter expression was parsed from, if it was parsed.
+    pub fn span(&self) -> Option<Span> {
+        self.span
+    }
+
+    pub(crate) fn set_span(&mut self, span: Span) -> &mut Self {
+        self.span = Some(span);
+        self
+    }
+
+    fn to_some_string(&self, abbr: bool) -> String {
+        let format_fn = if abbr {
+            Predicate::to_abbr_string
+        } else {
+            Predicate::to_string
+        };
+        // Only a variable reference, literal, or function call can be followed directly by a
+        // predicate or path, anything else, including a location path, has to be parenthesized
+        // so that the predicates and path are not read as part of it.
+        let primary = match self.primary.borrow() {
+            Predicate::Terminal(Terminal::Variable(_, _))
+            | Predicate::Terminal(Terminal::Literal(_, _))
+            | Predicate::Function(_) => format_fn(&self.primary),
+            _ => format!("({})", format_fn(&self.primary)),
+        };
+        let predicates = self
+            .predicates
+            .iter()
+            .map(|p| format!("[{}]", format_fn(p)))
+            .collect::<Vec<String>>()
+            .join("");
+        let path = match &self.path {
+            None => String::new(),
+            Some(path) if abbr => {
+                // Displaying the steps as an absolute path allows the abbreviation of a leading
+                // `descendant-or-self::node()` step to `//`.
+                let mut absolute = LocationPath::absolute();
+                for step in path.steps() {
+                    absolute.append(step.clone());
+                }
+                absolute.to_abbr_string()
+            }
+            Some(path) => format!("/{}", path),
+        };
+        format!("{}{}{}", primary, predicates, path)
+    }
 }
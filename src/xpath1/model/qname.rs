@@ -0,0 +1,145 @@
+/*!
+The type `QName` models a qualified name, as used for variables, functions, and name tests.
+*/
+
+use crate::xpath1::parser::ParseError;
+use std::fmt::{Display, Formatter, Result};
+use std::str::FromStr;
+
+// ------------------------------------------------------------------------------------------------
+// Public Types
+// ------------------------------------------------------------------------------------------------
+
+///
+/// A qualified name, consisting of an optional namespace prefix and a local part.
+///
+/// Corresponds to the production `QName` (7) in
+/// [Namespaces in XML 1.0](https://www.w3.org/TR/REC-xml-names/#ns-qualnames).
+///
+/// # Example
+///
+/// ```rust
+/// use xml_dom_xpath::xpath1::model::QName;
+/// use std::str::FromStr;
+///
+/// let name = QName::from_str("soap:Body").unwrap();
+/// assert_eq!(name.prefix(), Some("soap"));
+/// assert_eq!(name.local_name(), "Body");
+/// assert_eq!(name, QName::with_prefix("soap", "Body"));
+/// ```
+///
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct QName {
+    prefix: Option<String>,
+    local_name: String,
+}
+
+// ------------------------------------------------------------------------------------------------
+// Implementations
+// ------------------------------------------------------------------------------------------------
+
+impl Display for QName {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match &self.prefix {
+            None => write!(f, "{}", self.local_name),
+            Some(prefix) => write!(f, "{}:{}", prefix, self.local_name),
+        }
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+
+impl FromStr for QName {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        if s.is_empty() {
+            return Err(ParseError::EmptyString);
+        }
+        crate::xpath1::parser::parse_qname(s)
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+
+impl From<&str> for QName {
+    ///
+    /// Construct a new name from `s`, where any prefix is separated from the local part by the
+    /// first `':'`. Unlike `from_str` neither part is validated.
+    ///
+    fn from(s: &str) -> Self {
+        match s.find(':') {
+            None => Self::with(s),
+            Some(index) => Self::with_prefix(&s[..index], &s[index + 1..]),
+        }
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+
+impl QName {
+    /// Construct a new name with only the local part `local_name`.
+    pub fn with(local_name: &str) -> Self {
+        Self {
+            prefix: None,
+            local_name: local_name.to_string(),
+        }
+    }
+
+    /// Construct a new name with both a `prefix` and the local part `local_name`.
+    pub fn with_prefix(prefix: &str, local_name: &str) -> Self {
+        Self {
+            prefix: Some(prefix.to_string()),
+            local_name: local_name.to_string(),
+        }
+    }
+
+    /// Return the namespace prefix of this name, if present.
+    pub fn prefix(&self) -> Option<&str> {
+        self.prefix.as_deref()
+    }
+
+    /// Return the local part of this name.
+    pub fn local_name(&self) -> &str {
+        &self.local_name
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+// Unit Tests
+// ------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unprefixed() {
+        let name = QName::from_str("order_id").unwrap();
+        assert_eq!(name, QName::with("order_id"));
+        assert_eq!(name.prefix(), None);
+        assert_eq!(name.to_string(), "order_id");
+    }
+
+    #[test]
+    fn test_prefixed() {
+        let name = QName::from_str("my:normalize-sku").unwrap();
+        assert_eq!(name, QName::with_prefix("my", "normalize-sku"));
+        assert_eq!(name.to_string(), "my:normalize-sku");
+    }
+
+    #[test]
+    fn test_from_str_ref() {
+        assert_eq!(QName::from("position"), QName::with("position"));
+        assert_eq!(QName::from("soap:Body"), QName::with_prefix("soap", "Body"));
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert_eq!(QName::from_str(""), Err(ParseError::EmptyString));
+        assert!(QName::from_str("1st").is_err());
+        assert!(QName::from_str("a:b:c").is_err());
+        assert!(QName::from_str(":b").is_err());
+        assert!(QName::from_str("a b").is_err());
+    }
+}
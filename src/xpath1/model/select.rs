@@ -1,4 +1,6 @@
-use crate::xpath1::model::ToAbbrString;
+use crate::xpath1::model::predicate::quote_literal;
+use crate::xpath1::model::span::Span;
+use crate::xpath1::model::{QName, ToAbbrString};
 use std::fmt::{Display, Formatter, Result};
 
 // ------------------------------------------------------------------------------------------------
@@ -10,7 +12,7 @@ use std::fmt::{Display, Formatter, Result};
 ///
 /// Corresponds to the BNF production `AxisSpecifier` (5).
 ///
-#[derive(Copy, Clone, Debug, PartialEq)]
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
 pub enum AxisSpecifier {
     /// the `ancestor` axis contains the ancestors of the context node; the ancestors of the context
     /// node consist of the parent of context node and the parent's parent and so on; thus, the
@@ -23,6 +25,7 @@ pub enum AxisSpecifier {
     /// unless the context node is an element
     Attribute,
     /// the `child` axis contains the children of the context node
+    #[default]
     Child,
     /// the `descendant` axis contains the descendants of the context node; a descendant is a child
     /// or a child of a child and so on; thus the descendant axis never contains attribute or
@@ -59,12 +62,17 @@ pub enum AxisSpecifier {
 ///
 /// Corresponds to the BNF production `NodeTest` (7).
 ///
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, Default, PartialEq)]
 pub enum NodeTest {
     /// All Nodes of the principal type.
+    #[default]
     All,
-    /// All Nodes of the principal type where `node_name` matches.
-    Named(String),
+    /// All Nodes of the principal type where the expanded name matches that of the qualified
+    /// name.
+    Named(QName),
+    /// All Nodes of the principal type where the namespace URI of the expanded name matches that
+    /// declared for the prefix, regardless of local name.
+    NamespaceWildcard(String),
     /// All `Comment` nodes.
     Comment,
     /// All `Text` nodes.
@@ -78,10 +86,11 @@ pub enum NodeTest {
 ///
 /// A container for an `AxisSpecifier` and a `NodeTest`.
 ///
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Default)]
 pub struct Select {
     axis: AxisSpecifier,
     test: NodeTest,
+    span: Option<Span>,
 }
 
 // ------------------------------------------------------------------------------------------------
@@ -102,7 +111,7 @@ macro_rules! select_fn {
     ($fn_name:ident, $axis:ident) => {
         /// Create a new `Select` using the corresponding axis specifier and node test.
         pub fn $fn_name(named: &str) -> Self {
-            Self::with(AxisSpecifier::$axis, NodeTest::Named(named.to_string()))
+            Self::with(AxisSpecifier::$axis, NodeTest::Named(QName::from(named)))
         }
     };
 }
@@ -111,21 +120,12 @@ macro_rules! select_fn {
 // Implementations
 // ------------------------------------------------------------------------------------------------
 
-impl Default for AxisSpecifier {
-    fn default() -> Self {
-        AxisSpecifier::Child
-    }
-}
-
-// ------------------------------------------------------------------------------------------------
-
 impl Display for AxisSpecifier {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result {
         write!(
             f,
             "{}",
             if f.alternate() {
-                println!("ALTER");
                 match self {
                     AxisSpecifier::Ancestor => "ancestor::",
                     AxisSpecifier::AncestorOrSelf => "ancestor-or-self::",
@@ -184,15 +184,26 @@ impl ToAbbrString for AxisSpecifier {
     }
 }
 
-// ------------------------------------------------------------------------------------------------
 // ------------------------------------------------------------------------------------------------
 
-impl Default for NodeTest {
-    fn default() -> Self {
-        NodeTest::All
+impl AxisSpecifier {
+    ///
+    /// Returns `true` if this is a reverse axis, one that only contains the context node or nodes
+    /// that are before the context node in document order; for a reverse axis the proximity
+    /// position of a node is its position in reverse document order.
+    ///
+    pub fn is_reverse(&self) -> bool {
+        matches!(
+            self,
+            AxisSpecifier::Ancestor
+                | AxisSpecifier::AncestorOrSelf
+                | AxisSpecifier::Preceding
+                | AxisSpecifier::PrecedingSibling
+        )
     }
 }
 
+// ------------------------------------------------------------------------------------------------
 // ------------------------------------------------------------------------------------------------
 
 impl Display for NodeTest {
@@ -203,11 +214,12 @@ impl Display for NodeTest {
             match self {
                 NodeTest::All => "*".to_string(),
                 NodeTest::Named(name) => name.to_string(),
+                NodeTest::NamespaceWildcard(prefix) => format!("{}:*", prefix),
                 NodeTest::Comment => "comment()".to_string(),
                 NodeTest::Text => "text()".to_string(),
                 NodeTest::ProcessingInstruction(None) => "processing-instruction()".to_string(),
                 NodeTest::ProcessingInstruction(Some(literal)) =>
-                    format!("processing-instruction({})", literal),
+                    format!("processing-instruction({})", quote_literal(literal)),
                 NodeTest::Node => "node()".to_string(),
             }
         )
@@ -218,18 +230,23 @@ impl Display for NodeTest {
 
 impl ToAbbrString for NodeTest {}
 
-// ------------------------------------------------------------------------------------------------
 // ------------------------------------------------------------------------------------------------
 
-impl Default for Select {
-    fn default() -> Self {
-        Self {
-            axis: Default::default(),
-            test: Default::default(),
+impl NodeTest {
+    ///
+    /// Return the namespace prefix used in this node test, if it is a qualified name or a
+    /// namespace wildcard with a prefix.
+    ///
+    pub fn prefix(&self) -> Option<&str> {
+        match self {
+            NodeTest::Named(name) => name.prefix(),
+            NodeTest::NamespaceWildcard(prefix) => Some(prefix.as_str()),
+            _ => None,
         }
     }
 }
 
+// ------------------------------------------------------------------------------------------------
 // ------------------------------------------------------------------------------------------------
 
 impl Display for Select {
@@ -254,6 +271,16 @@ impl ToAbbrString for Select {
     }
 }
 
+// ------------------------------------------------------------------------------------------------
+
+impl PartialEq for Select {
+    fn eq(&self, other: &Self) -> bool {
+        self.axis == other.axis && self.test == other.test
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+
 impl Select {
     ///
     /// Construct a new `Select` component from the provided `axis` and `node_test`.
@@ -262,6 +289,7 @@ impl Select {
         Self {
             axis,
             test: node_test,
+            span: None,
         }
     }
 
@@ -279,6 +307,18 @@ impl Select {
         self.test.clone()
     }
 
+    ///
+    /// Return the span of the query text this axis specifier and node test was parsed from, if it was parsed.
+    ///
+    pub fn span(&self) -> Option<Span> {
+        self.span
+    }
+
+    pub(crate) fn set_span(&mut self, span: Span) -> &mut Self {
+        self.span = Some(span);
+        self
+    }
+
     select_fn!(all_ancestors, Ancestor, Node);
     select_fn!(all_ancestor_elements, Ancestor, All);
     select_fn!(all_ancestor_text, Ancestor, Text);
@@ -0,0 +1,103 @@
+use std::fmt::{Display, Formatter, Result};
+use std::ops::Range;
+
+// ------------------------------------------------------------------------------------------------
+// Public Types
+// ------------------------------------------------------------------------------------------------
+
+///
+/// The byte range within the original query text that a model value was parsed from. Values
+/// constructed directly, rather than parsed, have no span.
+///
+/// Spans are not part of the value of a model node; two nodes that differ only in their spans
+/// are equal.
+///
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Span {
+    start: usize,
+    end: usize,
+}
+
+// ------------------------------------------------------------------------------------------------
+// Implementations
+// ------------------------------------------------------------------------------------------------
+
+impl Display for Span {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(f, "{}..{}", self.start, self.end)
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+
+impl From<Range<usize>> for Span {
+    fn from(range: Range<usize>) -> Self {
+        Self::new(range.start, range.end)
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+
+impl From<Span> for Range<usize> {
+    fn from(span: Span) -> Self {
+        span.start..span.end
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+
+impl Span {
+    /// Construct a new span covering the bytes from `start` up to, but not including, `end`.
+    pub fn new(start: usize, end: usize) -> Self {
+        assert!(
+            start <= end,
+            "span start {} is after its end {}",
+            start,
+            end
+        );
+        Self { start, end }
+    }
+
+    /// The offset of the first byte in the span.
+    pub fn start(&self) -> usize {
+        self.start
+    }
+
+    /// The offset of the byte following the span.
+    pub fn end(&self) -> usize {
+        self.end
+    }
+
+    /// The smallest span covering both this span and `other`.
+    pub fn join(&self, other: Span) -> Self {
+        Self::new(self.start.min(other.start), self.end.max(other.end))
+    }
+
+    ///
+    /// Return the text covered by this span within `source`, the string the value was parsed
+    /// from, or `None` if the span does not lie on character boundaries within `source`.
+    ///
+    pub fn slice<'a>(&self, source: &'a str) -> Option<&'a str> {
+        source.get(self.start..self.end)
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+// Unit Tests
+// ------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_slice_and_join() {
+        let source = "count(para) > 1";
+        let call = Span::new(0, 11);
+        assert_eq!(call.slice(source), Some("count(para)"));
+        assert_eq!(call.to_string(), "0..11");
+        assert_eq!(call.join(Span::from(14..15)).slice(source), Some(source));
+        assert_eq!(Range::from(Span::new(6, 10)), 6..10);
+        assert_eq!(Span::new(20, 21).slice(source), None);
+    }
+}
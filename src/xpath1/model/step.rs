@@ -1,5 +1,6 @@
 use crate::xpath1::model::predicate::Predicate;
 use crate::xpath1::model::select::{AxisSpecifier, NodeTest, Select};
+use crate::xpath1::model::span::Span;
 use crate::xpath1::model::ToAbbrString;
 use std::fmt::{Display, Formatter, Result};
 use std::slice::Iter;
@@ -14,10 +15,11 @@ use std::slice::Iter;
 ///
 /// Corresponds to the BNF production `Step` (4).
 ///
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Default)]
 pub struct Step {
     select: Select,
     predicates: Vec<Predicate>,
+    span: Option<Span>,
 }
 
 // ------------------------------------------------------------------------------------------------
@@ -35,6 +37,7 @@ macro_rules! step_fn {
             Self {
                 select: Select::$fn_name(),
                 predicates: Vec::default(),
+                span: None,
             }
         }
     };
@@ -44,6 +47,7 @@ macro_rules! step_fn {
             Self {
                 select: Select::$fn_name(named),
                 predicates: Vec::default(),
+                span: None,
             }
         }
     };
@@ -53,17 +57,6 @@ macro_rules! step_fn {
 // Implementations
 // ------------------------------------------------------------------------------------------------
 
-impl Default for Step {
-    fn default() -> Self {
-        Self {
-            select: Default::default(),
-            predicates: Default::default(),
-        }
-    }
-}
-
-// ------------------------------------------------------------------------------------------------
-
 impl Display for Step {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result {
         write!(
@@ -95,6 +88,12 @@ impl ToAbbrString for Step {
     }
 }
 
+impl PartialEq for Step {
+    fn eq(&self, other: &Self) -> bool {
+        self.select == other.select && self.predicates == other.predicates
+    }
+}
+
 // ------------------------------------------------------------------------------------------------
 
 impl Step {
@@ -105,6 +104,7 @@ impl Step {
         Self {
             select,
             predicates: Default::default(),
+            span: None,
         }
     }
 
@@ -115,6 +115,7 @@ impl Step {
         Self {
             select: Select::with(axis, node_test),
             predicates: Default::default(),
+            span: None,
         }
     }
 
@@ -136,10 +137,22 @@ impl Step {
     ///
     /// Return an iterator over the `Predicate`s of this `Step`.
     ///
-    pub fn predicate_exprs(&self) -> Iter<Predicate> {
+    pub fn predicate_exprs(&self) -> Iter<'_, Predicate> {
         self.predicates.iter()
     }
 
+    ///
+    /// Return the span of the query text this step was parsed from, if it was parsed.
+    ///
+    pub fn span(&self) -> Option<Span> {
+        self.span
+    }
+
+    pub(crate) fn set_span(&mut self, span: Span) -> &mut Self {
+        self.span = Some(span);
+        self
+    }
+
     step_fn!(all_ancestors);
     step_fn!(all_ancestor_elements);
     step_fn!(all_ancestor_text);
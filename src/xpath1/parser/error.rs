@@ -0,0 +1,536 @@
+/*!
+The description of a string that does not match the XPath grammar, in terms of the query text
+rather than the rules of the underlying parser.
+*/
+
+use crate::xpath1::parser::pest_parser::Rule;
+use pest::error::{Error, ErrorVariant, InputLocation};
+use std::fmt::{Display, Formatter};
+use std::slice::Iter;
+
+// ------------------------------------------------------------------------------------------------
+// Public Types
+// ------------------------------------------------------------------------------------------------
+
+///
+/// Describes where, and if possible why, a query failed to match the XPath grammar.
+///
+/// The `Display` form of this error is the message followed by the line of the query containing
+/// the error, with a caret under the position of the error, and any hint, for example:
+///
+/// ```text
+/// Syntax error at line 1, column 7; expected '/', '[' or an operator, but found the end of the query
+///  1 | para[1
+///    |       ^
+/// hint: the '[' at line 1, column 5 is not closed
+/// ```
+///
+#[derive(Clone, Debug, PartialEq)]
+pub struct SyntaxError {
+    query: String,
+    offset: usize,
+    line: usize,
+    column: usize,
+    expected: Vec<String>,
+    found: String,
+    hint: Option<String>,
+}
+
+// ------------------------------------------------------------------------------------------------
+// Implementations
+// ------------------------------------------------------------------------------------------------
+
+impl Display for SyntaxError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Syntax error at line {}, column {}; ",
+            self.line, self.column
+        )?;
+        if self.expected.is_empty() {
+            write!(f, "found {}", self.found)?;
+        } else {
+            write!(
+                f,
+                "expected {}, but found {}",
+                join_alternatives(&self.expected),
+                self.found
+            )?;
+        }
+        write!(f, "\n{}", self.render())
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+
+impl std::error::Error for SyntaxError {}
+
+// ------------------------------------------------------------------------------------------------
+
+impl SyntaxError {
+    pub(crate) fn from_pest(query: &str, err: &Error<Rule>) -> Self {
+        let offset = match err.location {
+            InputLocation::Pos(offset) => offset,
+            InputLocation::Span((start, _)) => start,
+        };
+        let mut expected: Vec<String> = Vec::new();
+        if let ErrorVariant::ParsingError { positives, .. } = &err.variant {
+            for term in positives.iter().map(describe_rule) {
+                if !expected.iter().any(|existing| existing == term) {
+                    expected.push(term.to_string());
+                }
+            }
+        }
+        let (line, column) = line_and_column(query, offset);
+        Self {
+            query: query.to_string(),
+            offset,
+            line,
+            column,
+            expected,
+            found: describe_found(query, offset),
+            hint: hint_for(query),
+        }
+    }
+
+    /// The query text that failed to parse.
+    pub fn query(&self) -> &str {
+        &self.query
+    }
+
+    /// The byte offset within the query at which the error was detected.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// The line, starting from 1, on which the error was detected.
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    /// The column, in characters and starting from 1, at which the error was detected.
+    pub fn column(&self) -> usize {
+        self.column
+    }
+
+    /// Descriptions of what could have appeared at the position of the error.
+    pub fn expected(&self) -> Iter<'_, String> {
+        self.expected.iter()
+    }
+
+    /// A description of what was found at the position of the error.
+    pub fn found(&self) -> &str {
+        &self.found
+    }
+
+    /// A suggestion for fixing a common mistake, if one was recognized in the query.
+    pub fn hint(&self) -> Option<&str> {
+        self.hint.as_deref()
+    }
+
+    ///
+    /// Return the line of the query containing the error, numbered, with a caret under the
+    /// position of the error on the following line, and any hint on the line after that.
+    ///
+    pub fn render(&self) -> String {
+        let line_text = self.query.lines().nth(self.line - 1).unwrap_or_default();
+        let number = self.line.to_string();
+        let margin = " ".repeat(number.len());
+        // Tabs are kept so that the caret lines up however the tab is displayed.
+        let indent: String = line_text
+            .chars()
+            .take(self.column - 1)
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect();
+        let mut rendered = format!(" {} | {}\n {} | {}^", number, line_text, margin, indent);
+        if let Some(hint) = &self.hint {
+            rendered.push_str("\nhint: ");
+            rendered.push_str(hint);
+        }
+        rendered
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+// Private Functions
+// ------------------------------------------------------------------------------------------------
+
+//
+// The names of all axes, used both to hint at a missing '::' and to suggest the closest axis name.
+//
+pub(crate) const AXIS_NAMES: &[&str] = &[
+    "ancestor",
+    "ancestor-or-self",
+    "attribute",
+    "child",
+    "descendant",
+    "descendant-or-self",
+    "following",
+    "following-sibling",
+    "namespace",
+    "parent",
+    "preceding",
+    "preceding-sibling",
+    "self",
+];
+
+//
+// The term used in messages for each rule the parser may report as expected; several rules are
+// only ever reported as the start of a larger construct and so share a description.
+//
+fn describe_rule(rule: &Rule) -> &'static str {
+    match rule {
+        Rule::EOI => "the end of the query",
+        Rule::XPathLocationPath
+        | Rule::LocationPath
+        | Rule::AbsoluteLocationPath
+        | Rule::RelativeLocationPath
+        | Rule::AbbreviatedAbsoluteLocationPath
+        | Rule::Step
+        | Rule::AbbreviatedStep
+        | Rule::AxisSpecifier
+        | Rule::AbbreviatedAxisSpecifier => "a location step",
+        Rule::NodeTest | Rule::NameTest => "a node test",
+        Rule::AxisName => "an axis name",
+        Rule::NodeType => "a node type",
+        Rule::StepSeparator => "'/'",
+        Rule::Predicate => "'['",
+        Rule::XPathExpr
+        | Rule::Expr
+        | Rule::Argument
+        | Rule::UnaryExpr
+        | Rule::UnaryMinus
+        | Rule::UnionExpr
+        | Rule::PathExpr
+        | Rule::FilterExpr
+        | Rule::PrimaryExpr => "an expression",
+        Rule::FunctionCall | Rule::FunctionName => "a function call",
+        Rule::VariableReference => "a variable reference",
+        Rule::Literal | Rule::dquote_value | Rule::squote_value => "a string literal",
+        Rule::Number | Rule::Digits => "a number",
+        Rule::and
+        | Rule::or
+        | Rule::modulus
+        | Rule::div
+        | Rule::add
+        | Rule::subtract
+        | Rule::multiply
+        | Rule::eq
+        | Rule::neq
+        | Rule::lt
+        | Rule::lteq
+        | Rule::gt
+        | Rule::gteq
+        | Rule::Operator => "an operator",
+        Rule::XPathQName
+        | Rule::QName
+        | Rule::PrefixedName
+        | Rule::UnprefixedName
+        | Rule::NCName
+        | Rule::Name
+        | Rule::Prefix
+        | Rule::LocalPart
+        | Rule::NameStartChar
+        | Rule::NameChar => "a name",
+        Rule::WHITESPACE => "whitespace",
+    }
+}
+
+fn join_alternatives(terms: &[String]) -> String {
+    match terms.split_last() {
+        None => String::new(),
+        Some((last, [])) => last.clone(),
+        Some((last, rest)) => format!("{} or {}", rest.join(", "), last),
+    }
+}
+
+fn line_and_column(query: &str, offset: usize) -> (usize, usize) {
+    let before = &query[..offset];
+    let line = before.matches('\n').count() + 1;
+    let line_start = before.rfind('\n').map(|i| i + 1).unwrap_or(0);
+    (line, before[line_start..].chars().count() + 1)
+}
+
+fn is_name_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '-' || c == '.'
+}
+
+//
+// Describe the token at `offset`; a whole name rather than its first character, and two
+// character operators rather than half of them.
+//
+fn describe_found(query: &str, offset: usize) -> String {
+    let rest = &query[offset..];
+    match rest.chars().next() {
+        None => "the end of the query".to_string(),
+        Some(c) if c.is_alphabetic() || c == '_' => {
+            let name: String = rest.chars().take_while(|c| is_name_char(*c)).collect();
+            format!("'{}'", name)
+        }
+        Some(c) => match ["==", "!=", "<=", ">=", "::", "//", ".."]
+            .iter()
+            .find(|op| rest.starts_with(*op))
+        {
+            Some(op) => format!("'{}'", op),
+            None => format!("'{}'", c),
+        },
+    }
+}
+
+//
+// The characters of `query` that are not within string literals, with their byte offsets, and
+// the offset of the opening quote of an unterminated literal, if any.
+//
+fn outside_literals(query: &str) -> (Vec<(usize, char)>, Option<usize>) {
+    let mut chars = Vec::new();
+    let mut open_quote: Option<(usize, char)> = None;
+    for (offset, c) in query.char_indices() {
+        match open_quote {
+            Some((_, quote)) if c == quote => open_quote = None,
+            Some(_) => {}
+            None if c == '"' || c == '\'' => open_quote = Some((offset, c)),
+            None => chars.push((offset, c)),
+        }
+    }
+    (chars, open_quote.map(|(offset, _)| offset))
+}
+
+fn position(query: &str, offset: usize) -> String {
+    let (line, column) = line_and_column(query, offset);
+    format!("line {}, column {}", line, column)
+}
+
+//
+// Look for the common mistakes that are easier to explain than the resulting parse failure.
+//
+fn hint_for(query: &str) -> Option<String> {
+    let (chars, unterminated) = outside_literals(query);
+    if let Some(offset) = unterminated {
+        return Some(format!(
+            "the string literal starting at {} is not closed",
+            position(query, offset)
+        ));
+    }
+    let char_at = |index: usize| chars.get(index).map(|(_, c)| *c);
+
+    if let Some(pair) = chars
+        .windows(2)
+        .find(|pair| pair[0].1 == '=' && pair[1].1 == '=')
+    {
+        return Some(format!(
+            "values are compared with '=' rather than '==', at {}",
+            position(query, pair[0].0)
+        ));
+    }
+
+    // Names followed by one or two colons, other than a prefix of a qualified name.
+    let mut index = 0;
+    while index < chars.len() {
+        let c = chars[index].1;
+        let starts_name =
+            (c.is_alphabetic() || c == '_') && (index == 0 || !is_name_char(chars[index - 1].1));
+        if !starts_name {
+            index += 1;
+            continue;
+        }
+        let start = index;
+        while char_at(index).map(is_name_char).unwrap_or(false) {
+            index += 1;
+        }
+        let name: String = chars[start..index].iter().map(|(_, c)| *c).collect();
+        let is_axis = AXIS_NAMES.contains(&name.as_str());
+        match (char_at(index), char_at(index + 1)) {
+            (Some(':'), Some(':')) if !is_axis => {
+                return Some(match closest_axis_name(&name) {
+                    Some(axis) => {
+                        format!("'{}' is not an axis name, did you mean '{}'?", name, axis)
+                    }
+                    None => format!(
+                        "'{}' is not an axis name, the axes are {}",
+                        name,
+                        AXIS_NAMES.join(", ")
+                    ),
+                });
+            }
+            (Some(':'), next) if is_axis && next != Some(':') => {
+                return Some(format!(
+                    "an axis name is followed by '::', did you mean '{}::'?",
+                    name
+                ));
+            }
+            _ => {}
+        }
+    }
+
+    unbalanced_bracket(query, &chars)
+}
+
+fn unbalanced_bracket(query: &str, chars: &[(usize, char)]) -> Option<String> {
+    let mut open: Vec<(usize, char)> = Vec::new();
+    for (offset, c) in chars {
+        match c {
+            '(' | '[' => open.push((*offset, *c)),
+            ')' | ']' => {
+                let matching = if *c == ')' { '(' } else { '[' };
+                match open.pop() {
+                    None => {
+                        return Some(format!(
+                            "the '{}' at {} has no matching '{}'",
+                            c,
+                            position(query, *offset),
+                            matching
+                        ))
+                    }
+                    Some((open_offset, open_c)) if open_c != matching => {
+                        return Some(format!(
+                            "the '{}' at {} is closed by the '{}' at {}",
+                            open_c,
+                            position(query, open_offset),
+                            c,
+                            position(query, *offset)
+                        ))
+                    }
+                    Some(_) => {}
+                }
+            }
+            _ => {}
+        }
+    }
+    open.pop()
+        .map(|(offset, c)| format!("the '{}' at {} is not closed", c, position(query, offset)))
+}
+
+fn closest_axis_name(name: &str) -> Option<&'static str> {
+    AXIS_NAMES
+        .iter()
+        .map(|axis| (edit_distance(name, axis), *axis))
+        .filter(|(distance, axis)| *distance <= 2.max(axis.len() / 4))
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, axis)| axis)
+}
+
+fn edit_distance(lhs: &str, rhs: &str) -> usize {
+    let rhs: Vec<char> = rhs.chars().collect();
+    let mut previous: Vec<usize> = (0..=rhs.len()).collect();
+    for (i, l) in lhs.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, r) in rhs.iter().enumerate() {
+            let substitution = previous[j] + if l == *r { 0 } else { 1 };
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[rhs.len()]
+}
+
+// ------------------------------------------------------------------------------------------------
+// Unit Tests
+// ------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use crate::xpath1::parser::{read_expr, read_str, ParseError, SyntaxError};
+
+    fn syntax_error(result: Result<impl std::fmt::Debug, ParseError>) -> SyntaxError {
+        match result {
+            Err(ParseError::Syntax(err)) => err,
+            other => panic!("Expecting a syntax error, not {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_position_and_expected() {
+        let err = syntax_error(read_str("para]"));
+        assert_eq!(err.offset(), 4);
+        assert_eq!((err.line(), err.column()), (1, 5));
+        assert_eq!(
+            err.expected().cloned().collect::<Vec<String>>(),
+            vec!["the end of the query", "'/'", "'['"]
+        );
+        assert_eq!(err.found(), "']'");
+        assert_eq!(
+            err.hint(),
+            Some("the ']' at line 1, column 5 has no matching '['")
+        );
+
+        let err = syntax_error(read_expr("count(para)\n  and\n  \t$x ="));
+        assert_eq!((err.line(), err.column()), (3, 8));
+        assert_eq!(err.expected().collect::<Vec<_>>(), vec!["an expression"]);
+        assert_eq!(err.found(), "the end of the query");
+    }
+
+    #[test]
+    fn test_display() {
+        let err = syntax_error(read_str("para[1"));
+        assert_eq!(
+            err.to_string(),
+            "Syntax error at line 1, column 7; expected '/', '[' or an operator, but found the end of the query
+ 1 | para[1
+   |       ^
+hint: the '[' at line 1, column 5 is not closed"
+        );
+        let err = syntax_error(read_expr("1 +\n\t$x $y"));
+        assert_eq!(err.render(), " 2 | \t$x $y\n   | \t   ^");
+        assert!(!ParseError::Syntax(err).to_string().contains("Expr"));
+    }
+
+    #[test]
+    fn test_hint_double_equals() {
+        let err = syntax_error(read_str("para[@type == 'warning']"));
+        assert_eq!(
+            err.hint(),
+            Some("values are compared with '=' rather than '==', at line 1, column 12")
+        );
+        // Characters within literals are ignored.
+        let err = syntax_error(read_expr("'a == b' = 'c' ="));
+        assert_eq!(err.hint(), None);
+    }
+
+    #[test]
+    fn test_hint_single_colon_axis() {
+        let err = syntax_error(read_str("chapter/child:text()"));
+        assert_eq!(
+            err.hint(),
+            Some("an axis name is followed by '::', did you mean 'child::'?")
+        );
+    }
+
+    #[test]
+    fn test_hint_unknown_axis() {
+        let err = syntax_error(read_str("chlid::para"));
+        assert_eq!(
+            err.hint(),
+            Some("'chlid' is not an axis name, did you mean 'child'?")
+        );
+        let err = syntax_error(read_str("following-siblings::para"));
+        assert_eq!(
+            err.hint(),
+            Some("'following-siblings' is not an axis name, did you mean 'following-sibling'?")
+        );
+        let err = syntax_error(read_str("sideways::para"));
+        assert!(err
+            .hint()
+            .unwrap()
+            .starts_with("'sideways' is not an axis name, the axes are ancestor, "));
+    }
+
+    #[test]
+    fn test_hint_brackets() {
+        assert_eq!(
+            syntax_error(read_expr("count(para")).hint(),
+            Some("the '(' at line 1, column 6 is not closed")
+        );
+        assert_eq!(
+            syntax_error(read_expr("(1 + 2))")).hint(),
+            Some("the ')' at line 1, column 8 has no matching '('")
+        );
+        assert_eq!(
+            syntax_error(read_str("para[count(x])")).hint(),
+            Some("the '(' at line 1, column 11 is closed by the ']' at line 1, column 13")
+        );
+        assert_eq!(
+            syntax_error(read_expr("concat('a', 'b)")).hint(),
+            Some("the string literal starting at line 1, column 13 is not closed")
+        );
+    }
+}
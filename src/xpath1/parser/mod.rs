@@ -1,6 +1,6 @@
 /*!
 Parse an XPath string into it's model form. The primary API is the [`read_str`](fn.read_str.html)
-function.
+function for location paths, and [`read_expr`](fn.read_expr.html) for any expression.
 
 # Specification
 
@@ -129,35 +129,129 @@ function.
 
 */
 
-use crate::xpath1::model::LocationPath;
-use pest::error::Error;
-use pest::Parser;
-use pest_parser::{Rule, XPathParser};
+use crate::xpath1::evaluate::FunctionRegistry;
+use crate::xpath1::model::{Expr, FunctionCall, LocationPath};
 use std::fmt::{Display, Formatter};
 
 // ------------------------------------------------------------------------------------------------
 // Public Types
 // ------------------------------------------------------------------------------------------------
 
+///
+/// Potential errors returned by [`read_str`](fn.read_str.html).
+///
 #[derive(Clone, Debug, PartialEq)]
 pub enum ParseError {
+    /// The string to parse was empty.
     EmptyString,
-    Parser(Error<Rule>),
+    /// The string does not match the XPath grammar, see [`SyntaxError`](struct.SyntaxError.html)
+    /// for the position of the error and what was expected there.
+    Syntax(SyntaxError),
+    /// The expression calls a function that is not known.
+    UnknownFunction(String),
+    /// The expression calls a function with the wrong number of arguments.
+    WrongArity(String),
+    /// The string is longer, in bytes, than the limit in
+    /// [`ParserOptions`](struct.ParserOptions.html).
+    LengthLimitExceeded { length: usize, limit: usize },
+    /// The expression is nested more deeply than the limit in
+    /// [`ParserOptions`](struct.ParserOptions.html).
+    DepthLimitExceeded { depth: usize, limit: usize },
+    /// The expression contains more location steps than the limit in
+    /// [`ParserOptions`](struct.ParserOptions.html).
+    StepLimitExceeded { steps: usize, limit: usize },
+    /// The expression contains more predicates than the limit in
+    /// [`ParserOptions`](struct.ParserOptions.html).
+    PredicateLimitExceeded { predicates: usize, limit: usize },
+    /// The expression contains more operators than the limit in
+    /// [`ParserOptions`](struct.ParserOptions.html).
+    OperatorLimitExceeded { operators: usize, limit: usize },
 }
 
 // ------------------------------------------------------------------------------------------------
 // Public Functions
 // ------------------------------------------------------------------------------------------------
 
+///
+/// Parse the string `xpath_str` into a [`LocationPath`](../model/struct.LocationPath.html); the
+/// entire string must match the production `LocationPath` (1).
+///
+/// Abbreviated steps and axis specifiers are expanded during parsing, so that `//para[1]` results
+/// in the same value as `/descendant-or-self::node()/child::para[position() = 1]`.
+///
+/// Calls to unprefixed function names must be to functions in the core function library, and the
+/// string must be within the default [`ParserOptions`](struct.ParserOptions.html) limits.
+///
 pub fn read_str(xpath_str: &str) -> Result<LocationPath, ParseError> {
-    if xpath_str.is_empty() {
-        return Err(ParseError::EmptyString);
-    }
-    let _path = XPathParser::parse(Rule::LocationPath, xpath_str)?
-        .next()
-        .unwrap();
+    read_str_with_functions(xpath_str, &FunctionRegistry::default())
+}
+
+///
+/// Parse the string `xpath_str` into a [`LocationPath`](../model/struct.LocationPath.html), as
+/// for [`read_str`](fn.read_str.html), where calls to unprefixed function names must be to
+/// functions in `functions`.
+///
+pub fn read_str_with_functions(
+    xpath_str: &str,
+    functions: &FunctionRegistry,
+) -> Result<LocationPath, ParseError> {
+    read_str_with_options(xpath_str, functions, &ParserOptions::default())
+}
+
+///
+/// Parse the string `xpath_str` into a [`LocationPath`](../model/struct.LocationPath.html), as
+/// for [`read_str_with_functions`](fn.read_str_with_functions.html), where the string must be
+/// within the limits in `options` rather than the default limits.
+///
+pub fn read_str_with_options(
+    xpath_str: &str,
+    functions: &FunctionRegistry,
+    options: &ParserOptions,
+) -> Result<LocationPath, ParseError> {
+    check_length(xpath_str, options)?;
+    let path = pest_parser::parse_location_path(xpath_str, options)?;
+    check_function_calls(path.function_calls(), functions)?;
+    Ok(path)
+}
+
+///
+/// Parse the string `xpath_str` into an [`Expr`](../model/struct.Expr.html); the entire string
+/// must match the production `Expr` (14). Any location paths within the expression are parsed
+/// as described for [`read_str`](fn.read_str.html).
+///
+/// Calls to unprefixed function names must be to functions in the core function library, and the
+/// string must be within the default [`ParserOptions`](struct.ParserOptions.html) limits.
+///
+pub fn read_expr(xpath_str: &str) -> Result<Expr, ParseError> {
+    read_expr_with_functions(xpath_str, &FunctionRegistry::default())
+}
+
+///
+/// Parse the string `xpath_str` into an [`Expr`](../model/struct.Expr.html), as for
+/// [`read_expr`](fn.read_expr.html), where calls to unprefixed function names must be to
+/// functions in `functions`.
+///
+pub fn read_expr_with_functions(
+    xpath_str: &str,
+    functions: &FunctionRegistry,
+) -> Result<Expr, ParseError> {
+    read_expr_with_options(xpath_str, functions, &ParserOptions::default())
+}
 
-    Err(ParseError::EmptyString)
+///
+/// Parse the string `xpath_str` into an [`Expr`](../model/struct.Expr.html), as for
+/// [`read_expr_with_functions`](fn.read_expr_with_functions.html), where the string must be
+/// within the limits in `options` rather than the default limits.
+///
+pub fn read_expr_with_options(
+    xpath_str: &str,
+    functions: &FunctionRegistry,
+    options: &ParserOptions,
+) -> Result<Expr, ParseError> {
+    check_length(xpath_str, options)?;
+    let expr = pest_parser::parse_expr(xpath_str, options)?;
+    check_function_calls(expr.function_calls(), functions)?;
+    Ok(expr)
 }
 
 // ------------------------------------------------------------------------------------------------
@@ -171,7 +265,33 @@ impl Display for ParseError {
             "{}",
             match self {
                 ParseError::EmptyString => "The path string is empty".to_string(),
-                ParseError::Parser(err) => format!("The parser failed. {:?}", err),
+                ParseError::Syntax(err) => err.to_string(),
+                ParseError::UnknownFunction(name) =>
+                    format!("The function '{}' is not known", name),
+                ParseError::WrongArity(call) => format!(
+                    "The function call '{}' has the wrong number of arguments",
+                    call
+                ),
+                ParseError::LengthLimitExceeded { length, limit } => format!(
+                    "The path string is {} bytes long, the limit is {}",
+                    length, limit
+                ),
+                ParseError::DepthLimitExceeded { depth, limit } => format!(
+                    "The expression is nested {} levels deep, the limit is {}",
+                    depth, limit
+                ),
+                ParseError::StepLimitExceeded { steps, limit } => format!(
+                    "The expression has {} location steps, the limit is {}",
+                    steps, limit
+                ),
+                ParseError::PredicateLimitExceeded { predicates, limit } => format!(
+                    "The expression has {} predicates, the limit is {}",
+                    predicates, limit
+                ),
+                ParseError::OperatorLimitExceeded { operators, limit } => format!(
+                    "The expression has {} operators, the limit is {}",
+                    operators, limit
+                ),
             }
         )
     }
@@ -183,9 +303,9 @@ impl std::error::Error for ParseError {}
 
 // ------------------------------------------------------------------------------------------------
 
-impl From<Error<Rule>> for ParseError {
-    fn from(err: Error<Rule>) -> Self {
-        Self::Parser(err)
+impl From<SyntaxError> for ParseError {
+    fn from(err: SyntaxError) -> Self {
+        Self::Syntax(err)
     }
 }
 
@@ -197,8 +317,52 @@ impl From<Error<Rule>> for ParseError {
 // Private Functions
 // ------------------------------------------------------------------------------------------------
 
+fn check_length(xpath_str: &str, options: &ParserOptions) -> Result<(), ParseError> {
+    if xpath_str.is_empty() {
+        return Err(ParseError::EmptyString);
+    }
+    match options.max_length() {
+        Some(limit) if xpath_str.len() > limit => Err(ParseError::LengthLimitExceeded {
+            length: xpath_str.len(),
+            limit,
+        }),
+        _ => Ok(()),
+    }
+}
+
+//
+// Only calls to unprefixed function names can be checked here, a prefix can only be resolved to a
+// namespace URI given the namespace declarations in the evaluation context.
+//
+fn check_function_calls(
+    calls: Vec<&FunctionCall>,
+    functions: &FunctionRegistry,
+) -> Result<(), ParseError> {
+    for call in calls {
+        let name = call.name();
+        if name.prefix().is_none() {
+            match functions.signature(None, name.local_name()) {
+                None => return Err(ParseError::UnknownFunction(name.to_string())),
+                Some(signature) if !signature.accepts_arguments(call.arguments().count()) => {
+                    return Err(ParseError::WrongArity(call.to_string()))
+                }
+                Some(_) => {}
+            }
+        }
+    }
+    Ok(())
+}
+
 // ------------------------------------------------------------------------------------------------
 // Modules
 // ------------------------------------------------------------------------------------------------
 
+mod error;
+pub use error::SyntaxError;
+pub(crate) use error::AXIS_NAMES;
+
+mod options;
+pub use options::ParserOptions;
+
 mod pest_parser;
+pub(crate) use pest_parser::parse_qname;
@@ -0,0 +1,143 @@
+/*!
+The type `ParserOptions` carries the limits placed on a query before it is parsed.
+*/
+
+// ------------------------------------------------------------------------------------------------
+// Public Types
+// ------------------------------------------------------------------------------------------------
+
+///
+/// Limits on the size and shape of a query, so that a query from an untrusted source cannot
+/// exhaust the stack, or memory, while it is parsed, displayed, or evaluated. A query that
+/// exceeds any limit is rejected with the corresponding [`ParseError`](enum.ParseError.html)
+/// variant before a model is constructed from it.
+///
+/// * _length_; the length of the query, in bytes.
+/// * _depth_; the number of levels of nested parentheses, predicates, function arguments and
+///   operators. A chain of operators such as `1 + 2 + 3` is a single level, however deep the
+///   chain is in the model.
+/// * _steps_; the number of location steps in all the paths in the query, where the
+///   abbreviation `//` counts as a step.
+/// * _predicates_; the number of predicates in the query, whether on a step or a filter
+///   expression.
+/// * _operators_; the number of unary and binary operators, including `|`, in the query. As a
+///   chain such as `1 + 2 + 3` is parsed as `(1 + 2) + 3`, this limits the depth of the model
+///   where the nesting depth does not.
+///
+/// The default options, a length of 16KiB, a depth of 64, 1024 each of steps and predicates, and
+/// 256 operators, are sufficient for any reasonable hand-written query.
+///
+/// # Example
+///
+/// ```rust
+/// use xml_dom_xpath::xpath1::parser::{read_str_with_options, ParseError, ParserOptions};
+/// use xml_dom_xpath::xpath1::evaluate::FunctionRegistry;
+///
+/// let mut options = ParserOptions::default();
+/// options.set_max_steps(2);
+///
+/// let functions = FunctionRegistry::default();
+/// assert!(read_str_with_options("chapter/para", &functions, &options).is_ok());
+/// assert_eq!(
+///     read_str_with_options("chapter//para", &functions, &options),
+///     Err(ParseError::StepLimitExceeded { steps: 3, limit: 2 })
+/// );
+/// ```
+///
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParserOptions {
+    max_length: Option<usize>,
+    max_depth: Option<usize>,
+    max_steps: Option<usize>,
+    max_predicates: Option<usize>,
+    max_operators: Option<usize>,
+}
+
+// ------------------------------------------------------------------------------------------------
+// Implementations
+// ------------------------------------------------------------------------------------------------
+
+impl Default for ParserOptions {
+    fn default() -> Self {
+        Self {
+            max_length: Some(16 * 1024),
+            max_depth: Some(64),
+            max_steps: Some(1024),
+            max_predicates: Some(1024),
+            max_operators: Some(256),
+        }
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+
+impl ParserOptions {
+    ///
+    /// Options with no limits at all; only suitable for queries from a trusted source, as a
+    /// deeply nested query will overflow the stack.
+    ///
+    pub fn unlimited() -> Self {
+        Self {
+            max_length: None,
+            max_depth: None,
+            max_steps: None,
+            max_predicates: None,
+            max_operators: None,
+        }
+    }
+
+    /// Set the maximum length of a query, in bytes.
+    pub fn set_max_length(&mut self, limit: usize) -> &mut Self {
+        self.max_length = Some(limit);
+        self
+    }
+
+    /// Return the maximum length of a query, in bytes, if limited.
+    pub fn max_length(&self) -> Option<usize> {
+        self.max_length
+    }
+
+    /// Set the maximum depth of a query.
+    pub fn set_max_depth(&mut self, limit: usize) -> &mut Self {
+        self.max_depth = Some(limit);
+        self
+    }
+
+    /// Return the maximum depth of a query, if limited.
+    pub fn max_depth(&self) -> Option<usize> {
+        self.max_depth
+    }
+
+    /// Set the maximum number of location steps in a query.
+    pub fn set_max_steps(&mut self, limit: usize) -> &mut Self {
+        self.max_steps = Some(limit);
+        self
+    }
+
+    /// Return the maximum number of location steps in a query, if limited.
+    pub fn max_steps(&self) -> Option<usize> {
+        self.max_steps
+    }
+
+    /// Set the maximum number of predicates in a query.
+    pub fn set_max_predicates(&mut self, limit: usize) -> &mut Self {
+        self.max_predicates = Some(limit);
+        self
+    }
+
+    /// Return the maximum number of predicates in a query, if limited.
+    pub fn max_predicates(&self) -> Option<usize> {
+        self.max_predicates
+    }
+
+    /// Set the maximum number of operators in a query.
+    pub fn set_max_operators(&mut self, limit: usize) -> &mut Self {
+        self.max_operators = Some(limit);
+        self
+    }
+
+    /// Return the maximum number of operators in a query, if limited.
+    pub fn max_operators(&self) -> Option<usize> {
+        self.max_operators
+    }
+}
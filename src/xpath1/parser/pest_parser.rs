@@ -1,12 +1,16 @@
 /*!
-One-line description.
-
-More detailed description, with
-
-# Example
-
+The [pest](https://pest.rs) generated parser for the grammar in `xpath.pest`, and the functions
+that walk the resulting parse tree to construct the corresponding model values.
 */
 
+use crate::xpath1::model::{
+    AxisSpecifier, Expr, FilterExpr, FunctionCall, LocationPath, NodeTest, Predicate, QName,
+    Select, Span, Step, Terminal,
+};
+use crate::xpath1::parser::{ParseError, ParserOptions, SyntaxError};
+use pest::iterators::Pair;
+use pest::Parser;
+
 // ------------------------------------------------------------------------------------------------
 // Public Types
 // ------------------------------------------------------------------------------------------------
@@ -19,38 +23,514 @@ pub struct XPathParser;
 // Public Functions
 // ------------------------------------------------------------------------------------------------
 
-// ------------------------------------------------------------------------------------------------
-// Implementations
-// ------------------------------------------------------------------------------------------------
+///
+/// Parse the complete string `xpath_str` as a location path, within the limits in `options`.
+///
+pub(crate) fn parse_location_path(
+    xpath_str: &str,
+    options: &ParserOptions,
+) -> Result<LocationPath, ParseError> {
+    check_bracket_depth(xpath_str, options)?;
+    let top_pair = XPathParser::parse(Rule::XPathLocationPath, xpath_str)
+        .map_err(|err| SyntaxError::from_pest(xpath_str, &err))?
+        .next()
+        .unwrap();
+    check_limits(&top_pair, options)?;
+    location_path(top_pair.into_inner().next().unwrap())
+}
+
+///
+/// Parse the complete string `xpath_str` as an expression, within the limits in `options`.
+///
+pub(crate) fn parse_expr(xpath_str: &str, options: &ParserOptions) -> Result<Expr, ParseError> {
+    check_bracket_depth(xpath_str, options)?;
+    let top_pair = XPathParser::parse(Rule::XPathExpr, xpath_str)
+        .map_err(|err| SyntaxError::from_pest(xpath_str, &err))?
+        .next()
+        .unwrap();
+    check_limits(&top_pair, options)?;
+    let expr_pair = top_pair.into_inner().next().unwrap();
+    let span = span_of(&expr_pair);
+    let mut value = Expr::with(expr(expr_pair)?);
+    let _ = value.set_span(span);
+    Ok(value)
+}
+
+///
+/// Parse the complete string `name_str` as a qualified name.
+///
+pub(crate) fn parse_qname(name_str: &str) -> Result<QName, ParseError> {
+    let top_pair = XPathParser::parse(Rule::XPathQName, name_str)
+        .map_err(|err| SyntaxError::from_pest(name_str, &err))?
+        .next()
+        .unwrap();
+    Ok(qname(top_pair.into_inner().next().unwrap()))
+}
 
 // ------------------------------------------------------------------------------------------------
 // Private Types
 // ------------------------------------------------------------------------------------------------
 
+//
+// The size of a parse tree, as limited by `ParserOptions`.
+//
+#[derive(Debug, Default)]
+struct Measure {
+    depth: usize,
+    steps: usize,
+    predicates: usize,
+    operators: usize,
+}
+
 // ------------------------------------------------------------------------------------------------
 // Private Functions
 // ------------------------------------------------------------------------------------------------
 
+//
+// The generated parser recurses for every level of brackets, and will overflow the stack long
+// before the parse tree can be measured, so the nesting of brackets outside of literals is checked
+// first. Every bracket adds a level to the measured depth, so this never rejects a query that
+// `check_limits` would accept.
+//
+fn check_bracket_depth(xpath_str: &str, options: &ParserOptions) -> Result<(), ParseError> {
+    if let Some(limit) = options.max_depth() {
+        let mut depth: usize = 0;
+        let mut quote = None;
+        for c in xpath_str.chars() {
+            match (quote, c) {
+                (Some(q), c) if q == c => quote = None,
+                (Some(_), _) => {}
+                (None, '"') | (None, '\'') => quote = Some(c),
+                (None, '(') | (None, '[') => {
+                    depth += 1;
+                    if depth > limit {
+                        return Err(ParseError::DepthLimitExceeded { depth, limit });
+                    }
+                }
+                (None, ')') | (None, ']') => depth = depth.saturating_sub(1),
+                _ => {}
+            }
+        }
+    }
+    Ok(())
+}
+
+fn check_limits(pair: &Pair<'_, Rule>, options: &ParserOptions) -> Result<(), ParseError> {
+    let measure = measure(pair);
+    match (
+        options.max_depth(),
+        options.max_steps(),
+        options.max_predicates(),
+        options.max_operators(),
+    ) {
+        (Some(limit), _, _, _) if measure.depth > limit => Err(ParseError::DepthLimitExceeded {
+            depth: measure.depth,
+            limit,
+        }),
+        (_, Some(limit), _, _) if measure.steps > limit => Err(ParseError::StepLimitExceeded {
+            steps: measure.steps,
+            limit,
+        }),
+        (_, _, Some(limit), _) if measure.predicates > limit => {
+            Err(ParseError::PredicateLimitExceeded {
+                predicates: measure.predicates,
+                limit,
+            })
+        }
+        (_, _, _, Some(limit)) if measure.operators > limit => {
+            Err(ParseError::OperatorLimitExceeded {
+                operators: measure.operators,
+                limit,
+            })
+        }
+        _ => Ok(()),
+    }
+}
+
+//
+// A chain of operators, and each bracketed construct, adds one level of depth; the operators in a
+// chain are counted separately, as each operand is nested within the following operator in the
+// model. A `//` is expanded to a step of its own.
+//
+fn measure(pair: &Pair<'_, Rule>) -> Measure {
+    let mut result = Measure::default();
+    let mut children = 0;
+    for inner in pair.clone().into_inner() {
+        children += 1;
+        let inner_measure = measure(&inner);
+        result.depth = result.depth.max(inner_measure.depth);
+        result.steps += inner_measure.steps;
+        result.predicates += inner_measure.predicates;
+        result.operators += inner_measure.operators;
+    }
+    match pair.as_rule() {
+        Rule::Expr if children > 1 => {
+            result.depth += 1;
+            result.operators += children / 2;
+        }
+        Rule::UnaryExpr | Rule::UnionExpr if children > 1 => {
+            result.depth += 1;
+            result.operators += children - 1;
+        }
+        Rule::Predicate => {
+            result.depth += 1;
+            result.predicates += 1;
+        }
+        Rule::FunctionCall => result.depth += 1,
+        Rule::PrimaryExpr if pair.as_str().starts_with('(') => result.depth += 1,
+        Rule::NodeTest if pair.as_str().ends_with(')') => result.depth += 1,
+        Rule::Step | Rule::AbbreviatedAbsoluteLocationPath => result.steps += 1,
+        Rule::StepSeparator if pair.as_str() == "//" => result.steps += 1,
+        _ => {}
+    }
+    result
+}
+
+#[allow(deprecated)]
 use pest::prec_climber::PrecClimber;
 
-lazy_static! {
-    static ref PREC_CLIMBER: PrecClimber<Rule> = {
-        use pest::prec_climber::Assoc::*;
-        use pest::prec_climber::Operator;
-        use Rule::*;
-
-        PrecClimber::new(vec![
-            Operator::new(descendant, Left) | Operator::new(descendant_or_self, Left),
-            Operator::new(multiply, Left) | Operator::new(modulus, Left) | Operator::new(div, Left),
-            Operator::new(add, Left) | Operator::new(subtract, Left),
-            Operator::new(lt, Left)
-                | Operator::new(lteq, Left)
-                | Operator::new(gt, Left)
-                | Operator::new(gteq, Left),
-            Operator::new(eq, Left) | Operator::new(neq, Left),
-            Operator::new(and, Left) | Operator::new(or, Left),
-        ])
+#[allow(deprecated)]
+fn prec_climber() -> &'static PrecClimber<Rule> {
+    lazy_static! {
+        static ref PREC_CLIMBER: PrecClimber<Rule> = {
+            use pest::prec_climber::Assoc::*;
+            use pest::prec_climber::Operator;
+            use Rule::*;
+
+            // Lowest to highest precedence, as given by productions [21] to [26].
+            PrecClimber::new(vec![
+                Operator::new(or, Left),
+                Operator::new(and, Left),
+                Operator::new(eq, Left) | Operator::new(neq, Left),
+                Operator::new(lt, Left)
+                    | Operator::new(lteq, Left)
+                    | Operator::new(gt, Left)
+                    | Operator::new(gteq, Left),
+                Operator::new(add, Left) | Operator::new(subtract, Left),
+                Operator::new(multiply, Left)
+                    | Operator::new(modulus, Left)
+                    | Operator::new(div, Left),
+            ])
+        };
+    }
+    &PREC_CLIMBER
+}
+
+fn location_path(pair: Pair<'_, Rule>) -> Result<LocationPath, ParseError> {
+    let span = span_of(&pair);
+    let inner = pair.into_inner().next().unwrap();
+    let mut path = match inner.as_rule() {
+        Rule::AbsoluteLocationPath => {
+            let mut path = LocationPath::absolute();
+            if let Some(inner) = inner.into_inner().next() {
+                match inner.as_rule() {
+                    Rule::AbbreviatedAbsoluteLocationPath => {
+                        let start = inner.as_span().start();
+                        path.append(descendants_step(Span::new(start, start + 2)));
+                        relative_location_path(inner.into_inner().next().unwrap(), &mut path)?;
+                    }
+                    Rule::RelativeLocationPath => relative_location_path(inner, &mut path)?,
+                    _ => unreachable!(),
+                }
+            }
+            path
+        }
+        Rule::RelativeLocationPath => {
+            let mut path = LocationPath::default();
+            relative_location_path(inner, &mut path)?;
+            path
+        }
+        _ => unreachable!(),
+    };
+    let _ = path.set_span(span);
+    Ok(path)
+}
+
+fn relative_location_path(pair: Pair<'_, Rule>, path: &mut LocationPath) -> Result<(), ParseError> {
+    for inner in pair.into_inner() {
+        match inner.as_rule() {
+            Rule::Step => {
+                path.append(step(inner)?);
+            }
+            Rule::StepSeparator => {
+                if inner.as_str() == "//" {
+                    path.append(descendants_step(span_of(&inner)));
+                }
+            }
+            _ => unreachable!(),
+        }
+    }
+    Ok(())
+}
+
+//
+// The step `descendant-or-self::node()` abbreviated by the separator `//` at `span`.
+//
+fn descendants_step(span: Span) -> Step {
+    let mut select = Select::all_descendants_or_self();
+    let _ = select.set_span(span);
+    let mut step = Step::with(select);
+    let _ = step.set_span(span);
+    step
+}
+
+fn step(pair: Pair<'_, Rule>) -> Result<Step, ParseError> {
+    let span = span_of(&pair);
+    let mut inner = pair.into_inner();
+    let first = inner.next().unwrap();
+    let mut select = if first.as_rule() == Rule::AbbreviatedStep {
+        if first.as_str() == ".." {
+            Select::all_parent()
+        } else {
+            Select::all_self()
+        }
+    } else {
+        let test_pair = inner.next().unwrap();
+        let select_span = Span::new(first.as_span().start(), span_of(&test_pair).end());
+        let mut select = Select::with(axis_specifier(first), node_test(test_pair));
+        let _ = select.set_span(select_span);
+        select
+    };
+    if select.span().is_none() {
+        let _ = select.set_span(span);
+    }
+    let mut step = Step::with(select);
+    for predicate_pair in inner {
+        step.append(predicate(predicate_pair)?);
+    }
+    let _ = step.set_span(span);
+    Ok(step)
+}
+
+fn axis_specifier(pair: Pair<'_, Rule>) -> AxisSpecifier {
+    let inner = pair.into_inner().next().unwrap();
+    match (inner.as_rule(), inner.as_str()) {
+        (Rule::AbbreviatedAxisSpecifier, "@") => AxisSpecifier::Attribute,
+        (Rule::AbbreviatedAxisSpecifier, _) => AxisSpecifier::Child,
+        (Rule::AxisName, "ancestor") => AxisSpecifier::Ancestor,
+        (Rule::AxisName, "ancestor-or-self") => AxisSpecifier::AncestorOrSelf,
+        (Rule::AxisName, "attribute") => AxisSpecifier::Attribute,
+        (Rule::AxisName, "child") => AxisSpecifier::Child,
+        (Rule::AxisName, "descendant") => AxisSpecifier::Descendant,
+        (Rule::AxisName, "descendant-or-self") => AxisSpecifier::DescendantOrSelf,
+        (Rule::AxisName, "following") => AxisSpecifier::Following,
+        (Rule::AxisName, "following-sibling") => AxisSpecifier::FollowingSibling,
+        (Rule::AxisName, "namespace") => AxisSpecifier::Namespace,
+        (Rule::AxisName, "parent") => AxisSpecifier::Parent,
+        (Rule::AxisName, "preceding") => AxisSpecifier::Preceding,
+        (Rule::AxisName, "preceding-sibling") => AxisSpecifier::PrecedingSibling,
+        (Rule::AxisName, "self") => AxisSpecifier::SelfNode,
+        _ => unreachable!(),
+    }
+}
+
+fn node_test(pair: Pair<'_, Rule>) -> NodeTest {
+    let inner = pair.into_inner().next().unwrap();
+    match inner.as_rule() {
+        Rule::NodeType => match inner.as_str() {
+            "comment" => NodeTest::Comment,
+            "text" => NodeTest::Text,
+            "processing-instruction" => NodeTest::ProcessingInstruction(None),
+            "node" => NodeTest::Node,
+            _ => unreachable!(),
+        },
+        Rule::Literal => NodeTest::ProcessingInstruction(Some(literal(inner))),
+        Rule::NameTest => match inner.into_inner().next() {
+            None => NodeTest::All,
+            Some(name) if name.as_rule() == Rule::NCName => {
+                NodeTest::NamespaceWildcard(name.as_str().to_string())
+            }
+            Some(name) => NodeTest::Named(qname(name)),
+        },
+        _ => unreachable!(),
+    }
+}
+
+fn predicate(pair: Pair<'_, Rule>) -> Result<Predicate, ParseError> {
+    let value = expr(pair.into_inner().next().unwrap())?;
+    // The abbreviation `[n]` is equivalent to `[position() = n]`, this is stored in the latter
+    // form so that it round-trips via `Display` and `ToAbbrString`.
+    Ok(match value {
+        Predicate::Terminal(Terminal::Number(_, Some(span))) => {
+            Predicate::eq(Predicate::function("position").with_span(span), value).with_span(span)
+        }
+        _ => value,
+    })
+}
+
+#[allow(deprecated)]
+fn expr(pair: Pair<'_, Rule>) -> Result<Predicate, ParseError> {
+    prec_climber().climb(
+        pair.into_inner(),
+        unary_expr,
+        |left: Result<Predicate, ParseError>,
+         op: Pair<'_, Rule>,
+         right: Result<Predicate, ParseError>| {
+            let (left, right) = (left?, right?);
+            let span = joined_span(&left, &right);
+            let value = match op.as_rule() {
+                Rule::and => Predicate::and(left, right),
+                Rule::or => Predicate::or(left, right),
+                Rule::eq => Predicate::eq(left, right),
+                Rule::neq => Predicate::neq(left, right),
+                Rule::lt => Predicate::lt(left, right),
+                Rule::lteq => Predicate::lteq(left, right),
+                Rule::gt => Predicate::gt(left, right),
+                Rule::gteq => Predicate::gteq(left, right),
+                Rule::add => Predicate::add(left, right),
+                Rule::subtract => Predicate::subtract(left, right),
+                Rule::multiply => Predicate::multiply(left, right),
+                Rule::div => Predicate::div(left, right),
+                Rule::modulus => Predicate::a_mod(left, right),
+                _ => unreachable!(),
+            };
+            Ok(value.with_span(span))
+        },
+    )
+}
+
+fn unary_expr(pair: Pair<'_, Rule>) -> Result<Predicate, ParseError> {
+    let end = span_of(&pair).end();
+    let mut minus_starts = Vec::new();
+    let mut value = None;
+    for inner in pair.into_inner() {
+        match inner.as_rule() {
+            Rule::UnaryMinus => minus_starts.push(inner.as_span().start()),
+            Rule::UnionExpr => value = Some(union_expr(inner)?),
+            _ => unreachable!(),
+        }
+    }
+    let mut value = value.unwrap();
+    for start in minus_starts.into_iter().rev() {
+        value = Predicate::minus(value).with_span(Span::new(start, end));
+    }
+    Ok(value)
+}
+
+fn union_expr(pair: Pair<'_, Rule>) -> Result<Predicate, ParseError> {
+    let mut inner = pair.into_inner();
+    let mut value = path_expr(inner.next().unwrap())?;
+    for path_pair in inner {
+        let right = path_expr(path_pair)?;
+        let span = joined_span(&value, &right);
+        value = Predicate::union(value, right).with_span(span);
+    }
+    Ok(value)
+}
+
+fn path_expr(pair: Pair<'_, Rule>) -> Result<Predicate, ParseError> {
+    let span = span_of(&pair);
+    let mut inner = pair.into_inner();
+    let first = inner.next().unwrap();
+    if first.as_rule() == Rule::LocationPath {
+        return Ok(path_terminal(location_path(first)?));
+    }
+    let mut filter_pairs = first.into_inner();
+    let primary = primary_expr(filter_pairs.next().unwrap())?;
+    let predicates = filter_pairs
+        .map(predicate)
+        .collect::<Result<Vec<Predicate>, ParseError>>()?;
+    let path = match inner.next() {
+        None => None,
+        Some(separator) => {
+            let mut path = LocationPath::default();
+            if separator.as_str() == "//" {
+                path.append(descendants_step(span_of(&separator)));
+            }
+            let _ = path.set_span(Span::new(separator.as_span().start(), span.end()));
+            relative_location_path(inner.next().unwrap(), &mut path)?;
+            Some(path)
+        }
     };
+    if predicates.is_empty() && path.is_none() {
+        Ok(primary)
+    } else {
+        let mut filter = FilterExpr::with(primary);
+        for predicate in predicates {
+            filter.append(predicate);
+        }
+        if let Some(path) = path {
+            filter.set_path(path);
+        }
+        Ok(Predicate::filter(filter).with_span(span))
+    }
+}
+
+fn primary_expr(pair: Pair<'_, Rule>) -> Result<Predicate, ParseError> {
+    let inner = pair.into_inner().next().unwrap();
+    let span = span_of(&inner);
+    match inner.as_rule() {
+        Rule::VariableReference => Ok(Predicate::Terminal(Terminal::Variable(
+            qname(inner.into_inner().next().unwrap()),
+            Some(span),
+        ))),
+        Rule::Expr => expr(inner),
+        Rule::Literal => Ok(Predicate::literal(&literal(inner)).with_span(span)),
+        Rule::Number => Ok(Predicate::number(inner.as_str().parse().unwrap()).with_span(span)),
+        Rule::FunctionCall => function_call(inner),
+        _ => unreachable!(),
+    }
+}
+
+fn function_call(pair: Pair<'_, Rule>) -> Result<Predicate, ParseError> {
+    let span = span_of(&pair);
+    let mut inner = pair.into_inner();
+    let name = qname(inner.next().unwrap().into_inner().next().unwrap());
+    let mut function = FunctionCall::with_qname(name, &[]);
+    for argument in inner {
+        function.append(expr(argument.into_inner().next().unwrap())?);
+    }
+    let _ = function.set_span(span);
+    Ok(Predicate::Function(function))
+}
+
+fn qname(pair: Pair<'_, Rule>) -> QName {
+    let inner = pair.into_inner().next().unwrap();
+    match inner.as_rule() {
+        Rule::PrefixedName => {
+            let mut inner = inner.into_inner();
+            let prefix = inner.next().unwrap().as_str();
+            QName::with_prefix(prefix, inner.next().unwrap().as_str())
+        }
+        Rule::UnprefixedName => QName::with(inner.as_str()),
+        _ => unreachable!(),
+    }
+}
+
+//
+// The span of `pair`, excluding any trailing whitespace matched by a repetition within the rule.
+//
+fn span_of(pair: &Pair<'_, Rule>) -> Span {
+    let start = pair.as_span().start();
+    Span::new(start, start + pair.as_str().trim_end().len())
+}
+
+//
+// The span covering both operands of a binary expression.
+//
+fn joined_span(left: &Predicate, right: &Predicate) -> Span {
+    match (left.span(), right.span()) {
+        (Some(left), Some(right)) => left.join(right),
+        _ => unreachable!(),
+    }
+}
+
+fn literal(pair: Pair<'_, Rule>) -> String {
+    pair.into_inner().next().unwrap().as_str().to_string()
+}
+
+///
+/// A relative path consisting of a single step without predicates is a `Select` expression,
+/// anything else is kept as a complete `LocationPath`.
+///
+fn path_terminal(path: LocationPath) -> Predicate {
+    let mut steps = path.steps();
+    match (path.is_absolute(), steps.next(), steps.next()) {
+        (false, Some(step), None) if step.predicate_exprs().next().is_none() => {
+            Predicate::Terminal(Terminal::Select(step.select_expr()))
+        }
+        _ => Predicate::path(path),
+    }
 }
 
 // ------------------------------------------------------------------------------------------------
@@ -64,12 +544,16 @@ lazy_static! {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use pest::Parser;
+    use crate::xpath1::evaluate::FunctionRegistry;
+    use crate::xpath1::model::ToAbbrString;
+    use crate::xpath1::parser::{
+        read_expr, read_expr_with_options, read_str, read_str_with_options,
+    };
 
     fn test_spec_example(example: &str, description: &str, dump: bool) {
         println!("* {}", description);
         println!("  XPath: '{}'", example);
-        let result = XPathParser::parse(Rule::LocationPath, example);
+        let result = XPathParser::parse(Rule::XPathLocationPath, example);
         if dump {
             println!("  Parsed: {:#?}", result);
         } else if let Err(err) = &result {
@@ -316,4 +800,1047 @@ mod tests {
             false,
         );
     }
+
+    // --------------------------------------------------------------------------------------------
+
+    #[test]
+    fn test_abbreviated_descendant_or_self() {
+        let mut expected = LocationPath::absolute();
+        expected.all_descendants_or_self().child_elements("para");
+        assert_eq!(
+            parse_location_path("//para", &ParserOptions::default()).unwrap(),
+            expected
+        );
+
+        let mut expected = LocationPath::default();
+        expected
+            .child_elements("chapter")
+            .all_descendants_or_self()
+            .child_elements("para");
+        assert_eq!(
+            parse_location_path("chapter//para", &ParserOptions::default()).unwrap(),
+            expected
+        );
+        assert_eq!(expected.to_abbr_string(), "chapter//para");
+    }
+
+    #[test]
+    fn test_abbreviated_steps() {
+        let mut expected = LocationPath::default();
+        expected.all_self().all_parent().attributes("lang");
+        assert_eq!(
+            parse_location_path("./../@lang", &ParserOptions::default()).unwrap(),
+            expected
+        );
+        assert_eq!(expected.to_abbr_string(), "./../@lang");
+    }
+
+    #[test]
+    fn test_path_in_predicate() {
+        let mut inner = LocationPath::default();
+        inner.child_elements("chapter").child_elements("title");
+        let mut step = Step::child_elements("book");
+        step.append(Predicate::path(inner));
+        assert_eq!(
+            parse_location_path("book[chapter/title]", &ParserOptions::default()).unwrap(),
+            LocationPath::with(step)
+        );
+    }
+
+    #[test]
+    fn test_processing_instruction_literal() {
+        let expected = LocationPath::with(Step::from(
+            AxisSpecifier::Child,
+            NodeTest::ProcessingInstruction(Some("xml-stylesheet".to_string())),
+        ));
+        assert_eq!(
+            parse_location_path(
+                "processing-instruction(\"xml-stylesheet\")",
+                &ParserOptions::default()
+            )
+            .unwrap(),
+            expected
+        );
+        assert_eq!(
+            parse_location_path(&expected.to_string(), &ParserOptions::default()).unwrap(),
+            expected
+        );
+
+        let expected = LocationPath::with(Step::from(
+            AxisSpecifier::Child,
+            NodeTest::ProcessingInstruction(Some("it's".to_string())),
+        ));
+        assert_eq!(
+            parse_location_path(&expected.to_string(), &ParserOptions::default()).unwrap(),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_unknown_function() {
+        assert_eq!(
+            read_str("para[no-such-function()]"),
+            Err(ParseError::UnknownFunction("no-such-function".to_string()))
+        );
+        assert_eq!(
+            read_expr("count(para, 2)"),
+            Err(ParseError::WrongArity("count(child::para, 2)".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_prefixed_function() {
+        // Prefixed function names are checked at evaluation, once the prefix can be resolved.
+        check_expr_round_trip(
+            "my:normalize-sku(@sku)",
+            &Expr::with(Predicate::function_with(
+                "my:normalize-sku",
+                &[Predicate::select(
+                    AxisSpecifier::Attribute,
+                    NodeTest::Named(QName::with("sku")),
+                )],
+            )),
+        );
+    }
+
+    #[test]
+    fn test_qualified_name_tests() {
+        let expected = LocationPath::with(Step::from(
+            AxisSpecifier::Child,
+            NodeTest::Named(QName::with_prefix("soap", "Body")),
+        ));
+        assert_eq!(
+            parse_location_path("soap:Body", &ParserOptions::default()).unwrap(),
+            expected
+        );
+        assert_eq!(expected.to_string(), "child::soap:Body");
+
+        let expected = LocationPath::with(Step::from(
+            AxisSpecifier::Attribute,
+            NodeTest::NamespaceWildcard("soap".to_string()),
+        ));
+        assert_eq!(
+            parse_location_path("@soap:*", &ParserOptions::default()).unwrap(),
+            expected
+        );
+        assert_eq!(expected.to_string(), "attribute::soap:*");
+        assert_eq!(expected.to_abbr_string(), "@soap:*");
+        assert_eq!(
+            parse_location_path(&expected.to_string(), &ParserOptions::default()).unwrap(),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_qualified_variable_and_function_names() {
+        let expr = parse_expr("my:normalize-sku($ord:sku)", &ParserOptions::default()).unwrap();
+        match expr.root() {
+            Predicate::Function(call) => {
+                assert_eq!(call.name(), &QName::with_prefix("my", "normalize-sku"));
+                assert_eq!(
+                    call.arguments().next(),
+                    Some(&Predicate::Terminal(Terminal::Variable(
+                        QName::with_prefix("ord", "sku"),
+                        None
+                    )))
+                );
+            }
+            _ => panic!("Expecting a function call!"),
+        }
+    }
+
+    #[test]
+    fn test_trailing_input() {
+        assert!(parse_location_path("para]", &ParserOptions::default()).is_err());
+        assert!(parse_location_path("para para", &ParserOptions::default()).is_err());
+    }
+
+    // --------------------------------------------------------------------------------------------
+
+    fn check_expr_round_trip(expr_str: &str, expected: &Expr) {
+        let parsed = parse_expr(expr_str, &ParserOptions::default()).unwrap();
+        assert_eq!(&parsed, expected);
+        assert_eq!(
+            &parse_expr(&parsed.to_string(), &ParserOptions::default()).unwrap(),
+            expected
+        );
+        assert_eq!(
+            &parse_expr(&parsed.to_abbr_string(), &ParserOptions::default()).unwrap(),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_expr_literals() {
+        check_expr_round_trip("\"abc\"", &Expr::with(Predicate::literal("abc")));
+        check_expr_round_trip("'abc'", &Expr::with(Predicate::literal("abc")));
+        check_expr_round_trip("\"it's\"", &Expr::with(Predicate::literal("it's")));
+        check_expr_round_trip(
+            "'say \"hi\"'",
+            &Expr::with(Predicate::literal("say \"hi\"")),
+        );
+        assert_eq!(Predicate::literal("it's").to_string(), "\"it's\"");
+        assert_eq!(Predicate::literal("abc").to_string(), "'abc'");
+        check_expr_round_trip("12.5", &Expr::with(Predicate::number(12.5)));
+        check_expr_round_trip(".5", &Expr::with(Predicate::number(0.5)));
+    }
+
+    #[test]
+    fn test_expr_function_of_path() {
+        let mut path = LocationPath::absolute();
+        path.all_descendants_or_self().child_elements("para");
+        check_expr_round_trip(
+            "count(//para) > 3",
+            &Expr::with(Predicate::gt(
+                Predicate::function_with("count", &[Predicate::path(path)]),
+                Predicate::integer(3),
+            )),
+        );
+    }
+
+    #[test]
+    fn test_expr_variable() {
+        check_expr_round_trip(
+            "$x + 1",
+            &Expr::with(Predicate::add(
+                Predicate::variable("x"),
+                Predicate::integer(1),
+            )),
+        );
+    }
+
+    #[test]
+    fn test_expr_union() {
+        check_expr_round_trip(
+            "para | title | @lang",
+            &Expr::with(Predicate::union(
+                Predicate::union(
+                    Predicate::select(AxisSpecifier::Child, NodeTest::Named(QName::with("para"))),
+                    Predicate::select(AxisSpecifier::Child, NodeTest::Named(QName::with("title"))),
+                ),
+                Predicate::select(
+                    AxisSpecifier::Attribute,
+                    NodeTest::Named(QName::with("lang")),
+                ),
+            )),
+        );
+    }
+
+    #[test]
+    fn test_expr_filter() {
+        let mut path = LocationPath::default();
+        path.all_descendants_or_self().child_elements("title");
+        let mut filter = FilterExpr::with(Predicate::union(
+            Predicate::select(AxisSpecifier::Child, NodeTest::Named(QName::with("a"))),
+            Predicate::select(AxisSpecifier::Child, NodeTest::Named(QName::with("b"))),
+        ));
+        filter
+            .append(Predicate::eq(
+                Predicate::function("position"),
+                Predicate::integer(1),
+            ))
+            .append(Predicate::variable("include"))
+            .set_path(path);
+        check_expr_round_trip(
+            "(a | b)[1][$include]//title",
+            &Expr::with(Predicate::filter(filter)),
+        );
+    }
+
+    #[test]
+    fn test_expr_filter_parenthesized_primary() {
+        for (expr_str, expected) in &[
+            (
+                "(//a)[1]",
+                "(/descendant-or-self::node()/child::a)[position() = 1]",
+            ),
+            (
+                "(//a)[1]/b",
+                "(/descendant-or-self::node()/child::a)[position() = 1]/child::b",
+            ),
+            ("(a)//b", "(child::a)/descendant-or-self::node()/child::b"),
+            ("(1)[1]", "(1)[position() = 1]"),
+            ("$x[1]", "$x[position() = 1]"),
+        ] {
+            let parsed = parse_expr(expr_str, &ParserOptions::default()).unwrap();
+            assert_eq!(&parsed.to_string(), expected);
+            assert_eq!(&parsed.to_abbr_string(), expr_str);
+            check_expr_round_trip(expr_str, &parsed);
+        }
+    }
+
+    #[test]
+    fn test_expr_not_a_path() {
+        assert!(parse_location_path("1 + 1", &ParserOptions::default()).is_err());
+        assert!(parse_location_path("$x", &ParserOptions::default()).is_err());
+    }
+
+    #[test]
+    fn test_location_path_spans() {
+        let source = "/doc//para[2]/..";
+        let path = parse_location_path(source, &ParserOptions::default()).unwrap();
+        let slice = |span: Option<Span>| span.unwrap().slice(source).unwrap();
+        assert_eq!(slice(path.span()), source);
+        let steps: Vec<&Step> = path.steps().collect();
+        assert_eq!(
+            steps
+                .iter()
+                .map(|step| slice(step.span()))
+                .collect::<Vec<_>>(),
+            vec!["doc", "//", "para[2]", ".."]
+        );
+        assert_eq!(slice(steps[2].select_expr().span()), "para");
+        let predicate = steps[2].predicate_exprs().next().unwrap();
+        assert_eq!(slice(predicate.span()), "2");
+        assert_eq!(slice(steps[3].select_expr().span()), "..");
+
+        // Spans are not part of the value of a node.
+        let mut para = Step::child_elements("para");
+        para.append(Predicate::eq(
+            Predicate::function("position"),
+            Predicate::integer(2),
+        ));
+        let mut expected = LocationPath::absolute();
+        expected
+            .child_elements("doc")
+            .all_descendants_or_self()
+            .append(para)
+            .all_parent();
+        assert_eq!(path, expected);
+        assert_eq!(expected.span(), None);
+    }
+
+    #[test]
+    fn test_expr_spans() {
+        let source = " count($items[@type = 'a']/b) > - ( 1 + 2 ) ";
+        let expr = parse_expr(source, &ParserOptions::default()).unwrap();
+        let slice = |span: Option<Span>| span.unwrap().slice(source).unwrap();
+        assert_eq!(slice(expr.span()), source.trim());
+        assert_eq!(slice(expr.root().span()), source.trim());
+        let (call, minus) = match expr.root() {
+            Predicate::Expr(node) => {
+                let operands = node.operands();
+                (operands[0], operands[1])
+            }
+            _ => panic!("Expecting a binary expression!"),
+        };
+        assert_eq!(slice(call.span()), "count($items[@type = 'a']/b)");
+        assert_eq!(slice(minus.span()), "- ( 1 + 2 )");
+        let filter = match call {
+            Predicate::Function(call) => call.arguments().next().unwrap(),
+            _ => panic!("Expecting a function call!"),
+        };
+        assert_eq!(slice(filter.span()), "$items[@type = 'a']/b");
+        match filter {
+            Predicate::Filter(filter) => {
+                assert_eq!(slice(filter.primary_expr().span()), "$items");
+                let predicate = filter.predicate_exprs().next().unwrap();
+                assert_eq!(slice(predicate.span()), "@type = 'a'");
+                if let Predicate::Expr(node) = predicate {
+                    assert_eq!(slice(node.operands()[0].span()), "@type");
+                    assert_eq!(slice(node.operands()[1].span()), "'a'");
+                }
+                assert_eq!(slice(filter.path().unwrap().span()), "/b");
+            }
+            _ => panic!("Expecting a filter expression!"),
+        }
+        match minus {
+            Predicate::Expr(node) => assert_eq!(slice(node.operands()[0].span()), "1 + 2"),
+            _ => panic!("Expecting a unary minus!"),
+        }
+    }
+
+    #[test]
+    fn test_expr_left_associative() {
+        check_expr_round_trip(
+            "1 - 2 - 3",
+            &Expr::with(Predicate::subtract(
+                Predicate::subtract(Predicate::integer(1), Predicate::integer(2)),
+                Predicate::integer(3),
+            )),
+        );
+        check_expr_round_trip(
+            "8 div 4 * 2 mod 3",
+            &Expr::with(Predicate::a_mod(
+                Predicate::multiply(
+                    Predicate::div(Predicate::integer(8), Predicate::integer(4)),
+                    Predicate::integer(2),
+                ),
+                Predicate::integer(3),
+            )),
+        );
+        check_expr_round_trip(
+            "1 - (2 - 3)",
+            &Expr::with(Predicate::subtract(
+                Predicate::integer(1),
+                Predicate::subtract(Predicate::integer(2), Predicate::integer(3)),
+            )),
+        );
+    }
+
+    #[test]
+    fn test_expr_and_binds_tighter_than_or() {
+        let expected = Expr::with(Predicate::or(
+            Predicate::variable("a"),
+            Predicate::and(Predicate::variable("b"), Predicate::variable("c")),
+        ));
+        check_expr_round_trip("$a or $b and $c", &expected);
+        check_expr_round_trip(
+            "$a and $b or $c",
+            &Expr::with(Predicate::or(
+                Predicate::and(Predicate::variable("a"), Predicate::variable("b")),
+                Predicate::variable("c"),
+            )),
+        );
+        check_expr_round_trip(
+            "($a or $b) and $c",
+            &Expr::with(Predicate::and(
+                Predicate::or(Predicate::variable("a"), Predicate::variable("b")),
+                Predicate::variable("c"),
+            )),
+        );
+    }
+
+    #[test]
+    fn test_expr_precedence_levels() {
+        // or < and < equality < relational < additive < multiplicative < unary minus
+        check_expr_round_trip(
+            "$a or $b and 1 = 2 < -3 + 4 * 5",
+            &Expr::with(Predicate::or(
+                Predicate::variable("a"),
+                Predicate::and(
+                    Predicate::variable("b"),
+                    Predicate::eq(
+                        Predicate::integer(1),
+                        Predicate::lt(
+                            Predicate::integer(2),
+                            Predicate::add(
+                                Predicate::minus(Predicate::integer(3)),
+                                Predicate::multiply(Predicate::integer(4), Predicate::integer(5)),
+                            ),
+                        ),
+                    ),
+                ),
+            )),
+        );
+        check_expr_round_trip(
+            "-(1 + 2)",
+            &Expr::with(Predicate::minus(Predicate::add(
+                Predicate::integer(1),
+                Predicate::integer(2),
+            ))),
+        );
+    }
+
+    // --------------------------------------------------------------------------------------------
+    // One test for each production of the grammar in the specification.
+    // --------------------------------------------------------------------------------------------
+
+    fn accepts(rule: Rule, input: &str) {
+        match XPathParser::parse(rule, input) {
+            Ok(mut pairs) => assert_eq!(
+                pairs.next().unwrap().as_str(),
+                input,
+                "{:?} did not match all of '{}'",
+                rule,
+                input
+            ),
+            Err(err) => panic!("{:?} failed to match '{}'\n{}", rule, input, err),
+        }
+    }
+
+    fn rejects(rule: Rule, input: &str) {
+        if let Ok(mut pairs) = XPathParser::parse(rule, input) {
+            assert_ne!(
+                pairs.next().unwrap().as_str(),
+                input,
+                "{:?} unexpectedly matched all of '{}'",
+                rule,
+                input
+            );
+        }
+    }
+
+    fn inner_rules(rule: Rule, input: &str) -> Vec<Rule> {
+        XPathParser::parse(rule, input)
+            .unwrap()
+            .next()
+            .unwrap()
+            .into_inner()
+            .map(|pair| pair.as_rule())
+            .collect()
+    }
+
+    #[test]
+    fn test_01_location_path() {
+        accepts(Rule::LocationPath, "para");
+        accepts(Rule::LocationPath, "/para");
+        assert_eq!(
+            inner_rules(Rule::LocationPath, "para"),
+            vec![Rule::RelativeLocationPath]
+        );
+        assert_eq!(
+            inner_rules(Rule::LocationPath, "/para"),
+            vec![Rule::AbsoluteLocationPath]
+        );
+    }
+
+    #[test]
+    fn test_02_absolute_location_path() {
+        accepts(Rule::AbsoluteLocationPath, "/");
+        accepts(Rule::AbsoluteLocationPath, "/doc/chapter");
+        accepts(Rule::AbsoluteLocationPath, "//para");
+        rejects(Rule::AbsoluteLocationPath, "doc");
+    }
+
+    #[test]
+    fn test_03_relative_location_path() {
+        accepts(Rule::RelativeLocationPath, "para");
+        accepts(Rule::RelativeLocationPath, "chapter/section/para");
+        accepts(Rule::RelativeLocationPath, "chapter//para");
+        rejects(Rule::RelativeLocationPath, "/para");
+    }
+
+    #[test]
+    fn test_04_step() {
+        accepts(Rule::Step, "child::para");
+        accepts(Rule::Step, "para[1][@type]");
+        accepts(Rule::Step, "..");
+        rejects(Rule::Step, "..[1]");
+    }
+
+    #[test]
+    fn test_05_axis_specifier() {
+        accepts(Rule::AxisSpecifier, "child::");
+        accepts(Rule::AxisSpecifier, "@");
+        accepts(Rule::AxisSpecifier, "");
+        rejects(Rule::Step, "no-such-axis::para");
+    }
+
+    #[test]
+    fn test_06_axis_name() {
+        for name in &[
+            "ancestor",
+            "ancestor-or-self",
+            "attribute",
+            "child",
+            "descendant",
+            "descendant-or-self",
+            "following",
+            "following-sibling",
+            "namespace",
+            "parent",
+            "preceding",
+            "preceding-sibling",
+            "self",
+        ] {
+            accepts(Rule::AxisName, name);
+            accepts(Rule::XPathLocationPath, &format!("{}::node()", name));
+        }
+        rejects(Rule::XPathLocationPath, "children::para");
+    }
+
+    #[test]
+    fn test_07_node_test() {
+        accepts(Rule::NodeTest, "para");
+        accepts(Rule::NodeTest, "text()");
+        accepts(Rule::NodeTest, "processing-instruction()");
+        accepts(Rule::NodeTest, "processing-instruction('xml-stylesheet')");
+        // Node type names are only node types when followed by "(".
+        accepts(Rule::XPathLocationPath, "comment/text/node");
+        accepts(Rule::XPathLocationPath, "node-set/textual");
+        rejects(Rule::NodeTest, "text('x')");
+    }
+
+    #[test]
+    fn test_08_predicate() {
+        accepts(Rule::Predicate, "[1]");
+        accepts(Rule::Predicate, "[ @type = 'warning' ]");
+        rejects(Rule::Predicate, "[]");
+        rejects(Rule::Predicate, "[1");
+    }
+
+    #[test]
+    fn test_09_predicate_expr() {
+        accepts(Rule::Predicate, "[position() = last() - 1 or self::para]");
+        accepts(Rule::Predicate, "[(a | b)[2]]");
+    }
+
+    #[test]
+    fn test_10_abbreviated_absolute_location_path() {
+        accepts(Rule::AbbreviatedAbsoluteLocationPath, "//para");
+        accepts(Rule::AbbreviatedAbsoluteLocationPath, "//olist/item");
+        rejects(Rule::AbbreviatedAbsoluteLocationPath, "//");
+    }
+
+    #[test]
+    fn test_11_abbreviated_relative_location_path() {
+        accepts(Rule::RelativeLocationPath, "chapter//para");
+        accepts(Rule::RelativeLocationPath, ".//para");
+        rejects(Rule::XPathLocationPath, "chapter//");
+        rejects(Rule::XPathLocationPath, "chapter///para");
+    }
+
+    #[test]
+    fn test_12_abbreviated_step() {
+        accepts(Rule::AbbreviatedStep, ".");
+        accepts(Rule::AbbreviatedStep, "..");
+        accepts(Rule::XPathLocationPath, "./../..");
+        rejects(Rule::XPathLocationPath, "...");
+    }
+
+    #[test]
+    fn test_13_abbreviated_axis_specifier() {
+        accepts(Rule::AbbreviatedAxisSpecifier, "@");
+        accepts(Rule::AbbreviatedAxisSpecifier, "");
+        accepts(Rule::XPathLocationPath, "@*");
+        rejects(Rule::XPathLocationPath, "@@lang");
+    }
+
+    #[test]
+    fn test_14_expr() {
+        accepts(Rule::Expr, "1");
+        accepts(Rule::Expr, "$a or $b and $c = 1 + 2 * 3");
+        rejects(Rule::XPathExpr, "1 +");
+        rejects(Rule::XPathExpr, "1 2");
+    }
+
+    #[test]
+    fn test_15_primary_expr() {
+        accepts(Rule::PrimaryExpr, "$x");
+        accepts(Rule::PrimaryExpr, "(1 + 2)");
+        accepts(Rule::PrimaryExpr, "'abc'");
+        accepts(Rule::PrimaryExpr, "12.5");
+        accepts(Rule::PrimaryExpr, "count(para)");
+        rejects(Rule::PrimaryExpr, "para");
+    }
+
+    #[test]
+    fn test_16_function_call() {
+        accepts(Rule::FunctionCall, "last()");
+        accepts(Rule::FunctionCall, "concat('a', 'b', 'c')");
+        accepts(Rule::FunctionCall, "my:normalize-sku(@sku)");
+        rejects(Rule::FunctionCall, "concat('a',)");
+        rejects(Rule::FunctionCall, "concat(,'a')");
+    }
+
+    #[test]
+    fn test_17_argument() {
+        accepts(Rule::Argument, "1");
+        accepts(Rule::Argument, "//para[1] | $x");
+        rejects(Rule::Argument, ",");
+    }
+
+    #[test]
+    fn test_18_union_expr() {
+        accepts(Rule::UnionExpr, "para");
+        accepts(Rule::UnionExpr, "para | title | @lang");
+        rejects(Rule::XPathExpr, "para |");
+    }
+
+    #[test]
+    fn test_19_path_expr() {
+        accepts(Rule::PathExpr, "chapter/para");
+        accepts(Rule::PathExpr, "$chapters");
+        accepts(Rule::PathExpr, "$chapters/para");
+        accepts(Rule::PathExpr, "id('intro')//para");
+        rejects(Rule::XPathExpr, "$chapters/");
+    }
+
+    #[test]
+    fn test_20_filter_expr() {
+        accepts(Rule::FilterExpr, "$x");
+        accepts(Rule::FilterExpr, "$x[1]");
+        accepts(Rule::FilterExpr, "(a | b)[1][$include]");
+        rejects(Rule::FilterExpr, "para[1]");
+    }
+
+    #[test]
+    fn test_21_or_expr() {
+        accepts(Rule::Expr, "$a or $b");
+        accepts(Rule::Expr, "$a or $b or $c");
+        // "or" is an operator only where an operator may appear.
+        accepts(Rule::Expr, "or or or");
+        rejects(Rule::XPathExpr, "$a orange");
+    }
+
+    #[test]
+    fn test_22_and_expr() {
+        accepts(Rule::Expr, "$a and $b");
+        accepts(Rule::Expr, "and and and");
+        rejects(Rule::XPathExpr, "$a and-b");
+        rejects(Rule::XPathExpr, "$a and");
+    }
+
+    #[test]
+    fn test_23_equality_expr() {
+        accepts(Rule::Expr, "@type = 'warning'");
+        accepts(Rule::Expr, "@type != 'warning'");
+        rejects(Rule::XPathExpr, "@type == 'warning'");
+    }
+
+    #[test]
+    fn test_24_relational_expr() {
+        for op in &["<", ">", "<=", ">="] {
+            accepts(Rule::Expr, &format!("position() {} 3", op));
+        }
+        rejects(Rule::XPathExpr, "position() => 3");
+    }
+
+    #[test]
+    fn test_25_additive_expr() {
+        accepts(Rule::Expr, "1 + 2 - 3");
+        // A "-" following a name character is part of the name.
+        accepts(Rule::Expr, "a - b");
+        assert_eq!(inner_rules(Rule::Expr, "a-b"), vec![Rule::UnaryExpr]);
+        assert_eq!(
+            inner_rules(Rule::Expr, "a -b"),
+            vec![Rule::UnaryExpr, Rule::subtract, Rule::UnaryExpr]
+        );
+    }
+
+    #[test]
+    fn test_26_multiplicative_expr() {
+        accepts(Rule::Expr, "6 * 7");
+        accepts(Rule::Expr, "6 div 7 mod 8");
+        accepts(Rule::Expr, "div div div");
+        accepts(Rule::Expr, "mod mod mod");
+        rejects(Rule::XPathExpr, "6 divide 7");
+        rejects(Rule::XPathExpr, "6 mod-7");
+    }
+
+    #[test]
+    fn test_27_unary_expr() {
+        accepts(Rule::UnaryExpr, "-1");
+        accepts(Rule::UnaryExpr, "- - $x");
+        assert_eq!(
+            inner_rules(Rule::UnaryExpr, "--1"),
+            vec![Rule::UnaryMinus, Rule::UnaryMinus, Rule::UnionExpr]
+        );
+        rejects(Rule::UnaryExpr, "-");
+    }
+
+    #[test]
+    fn test_28_expr_token() {
+        // Every kind of token, separated by whitespace.
+        accepts(
+            Rule::XPathExpr,
+            "( child :: para [ @ type = 'a' ] | . | .. ) [ 1 ] / text ( ) | $v * - f ( 2 , ns:* )",
+        );
+    }
+
+    #[test]
+    fn test_29_literal() {
+        accepts(Rule::Literal, "\"it's\"");
+        accepts(Rule::Literal, "'say \"hi\"'");
+        accepts(Rule::Literal, "''");
+        rejects(Rule::Literal, "'abc\"");
+        rejects(Rule::Literal, "'abc");
+    }
+
+    #[test]
+    fn test_30_number() {
+        accepts(Rule::Number, "42");
+        accepts(Rule::Number, "42.");
+        accepts(Rule::Number, "42.125");
+        accepts(Rule::Number, ".5");
+        rejects(Rule::Number, ".");
+        rejects(Rule::Number, "1e3");
+        rejects(Rule::Number, "- 1");
+    }
+
+    #[test]
+    fn test_31_digits() {
+        accepts(Rule::Number, "0123456789");
+        rejects(Rule::Number, "1 2");
+    }
+
+    #[test]
+    fn test_32_operator() {
+        for op in &[
+            "and", "or", "mod", "div", "*", "|", "+", "-", "=", "!=", "<", "<=", ">", ">=",
+        ] {
+            accepts(Rule::XPathExpr, &format!("$a {} $b", op));
+        }
+        accepts(Rule::XPathExpr, "$a/b");
+        accepts(Rule::XPathExpr, "$a//b");
+        rejects(Rule::XPathExpr, "$a ! $b");
+    }
+
+    #[test]
+    fn test_33_operator_name() {
+        accepts(Rule::and, "and");
+        accepts(Rule::or, "or");
+        accepts(Rule::modulus, "mod");
+        accepts(Rule::div, "div");
+        rejects(Rule::or, "order");
+        rejects(Rule::div, "div.x");
+    }
+
+    #[test]
+    fn test_34_multiply_operator() {
+        accepts(Rule::multiply, "*");
+        assert_eq!(
+            inner_rules(Rule::Expr, "* * *"),
+            vec![Rule::UnaryExpr, Rule::multiply, Rule::UnaryExpr]
+        );
+        // The second "*" follows an operator, and so is a name test.
+        accepts(Rule::XPathExpr, "2**");
+        rejects(Rule::XPathExpr, "2 *");
+    }
+
+    #[test]
+    fn test_35_function_name() {
+        accepts(Rule::FunctionName, "count");
+        accepts(Rule::FunctionName, "my:count");
+        accepts(Rule::FunctionName, "node-set");
+        rejects(Rule::FunctionCall, "node()");
+        rejects(Rule::FunctionCall, "text ()");
+    }
+
+    #[test]
+    fn test_36_variable_reference() {
+        accepts(Rule::VariableReference, "$x");
+        accepts(Rule::VariableReference, "$ord:sku");
+        rejects(Rule::VariableReference, "$ x");
+        rejects(Rule::VariableReference, "$1");
+    }
+
+    #[test]
+    fn test_37_name_test() {
+        accepts(Rule::NameTest, "*");
+        accepts(Rule::NameTest, "soap:*");
+        accepts(Rule::NameTest, "soap:Body");
+        accepts(Rule::NameTest, "Body");
+        rejects(Rule::NameTest, "*:Body");
+        rejects(Rule::NameTest, "soap :Body");
+    }
+
+    #[test]
+    fn test_38_node_type() {
+        for node_type in &["comment", "text", "processing-instruction", "node"] {
+            accepts(Rule::NodeType, node_type);
+            accepts(Rule::XPathLocationPath, &format!("{}()", node_type));
+        }
+        rejects(Rule::NodeType, "element");
+    }
+
+    #[test]
+    fn test_39_expr_whitespace() {
+        accepts(Rule::XPathExpr, " \t\r\n1\n+\t2 ");
+        accepts(Rule::XPathLocationPath, "child :: para [ 1 ]");
+        rejects(Rule::XPathExpr, "1\u{A0}+ 2");
+    }
+
+    #[test]
+    fn test_name_characters() {
+        accepts(Rule::NCName, "_x\u{B7}y-z.0");
+        accepts(Rule::NCName, "\u{4E2D}\u{6587}");
+        accepts(Rule::NCName, "\u{D7FF}");
+        rejects(Rule::NCName, "\u{E000}");
+        rejects(Rule::NCName, "-x");
+        rejects(Rule::NCName, "x:y");
+    }
+
+    // --------------------------------------------------------------------------------------------
+    // Parser options
+    // --------------------------------------------------------------------------------------------
+
+    fn options_with(set: impl Fn(&mut ParserOptions) -> &mut ParserOptions) -> ParserOptions {
+        let mut options = ParserOptions::default();
+        let _ = set(&mut options);
+        options
+    }
+
+    #[test]
+    fn test_length_limit() {
+        let functions = FunctionRegistry::default();
+        let options = options_with(|o| o.set_max_length(8));
+        assert!(read_str_with_options("chapter", &functions, &options).is_ok());
+        assert_eq!(
+            read_str_with_options("chapter/para", &functions, &options),
+            Err(ParseError::LengthLimitExceeded {
+                length: 12,
+                limit: 8
+            })
+        );
+        assert_eq!(
+            read_str_with_options("", &functions, &options),
+            Err(ParseError::EmptyString)
+        );
+        assert!(matches!(
+            read_expr(&"1 + ".repeat(5_000)),
+            Err(ParseError::LengthLimitExceeded { .. })
+        ));
+    }
+
+    #[test]
+    fn test_depth_limit() {
+        let functions = FunctionRegistry::default();
+        let options = options_with(|o| o.set_max_depth(2));
+        for accepted in &[
+            "(1 + 2)",
+            "para[text()]",
+            "count(a[1])",
+            "--1",
+            "1 + 2 + 3 + 4",
+            "a | b | c | d",
+        ] {
+            assert!(
+                read_expr_with_options(accepted, &functions, &options).is_ok(),
+                "{}",
+                accepted
+            );
+        }
+        for (rejected, depth) in &[
+            ("(((1)))", 3),
+            ("para[text() = '((((']", 3),
+            ("((1 + 2))", 3),
+            ("-(-1)", 3),
+            ("a | b[c | d]", 3),
+            ("count(a[b[c]])", 3),
+        ] {
+            assert_eq!(
+                read_expr_with_options(rejected, &functions, &options),
+                Err(ParseError::DepthLimitExceeded {
+                    depth: *depth,
+                    limit: 2
+                }),
+                "{}",
+                rejected
+            );
+        }
+    }
+
+    #[test]
+    fn test_depth_limit_protects_stack() {
+        assert_eq!(
+            read_expr(&"(".repeat(10_000)),
+            Err(ParseError::DepthLimitExceeded {
+                depth: 65,
+                limit: 64
+            })
+        );
+        let nested = format!("{}b{}", "a[".repeat(64), "]".repeat(64));
+        let path = read_str(&nested).unwrap();
+        assert_eq!(read_str(&path.to_string()).unwrap(), path);
+        assert!(read_str(&format!("a[{}]", nested)).is_err());
+    }
+
+    #[test]
+    fn test_operator_limit() {
+        let functions = FunctionRegistry::default();
+        let options = options_with(|o| o.set_max_operators(3));
+        for accepted in &["1 + 2 * 3 - 4", "---1", "a | b | c | d", "-(1 + 2) = 3"] {
+            assert!(
+                read_expr_with_options(accepted, &functions, &options).is_ok(),
+                "{}",
+                accepted
+            );
+        }
+        for rejected in &[
+            "1 + 2 * 3 - 4 div 5",
+            "----1",
+            "a | b[c or d] | e | f",
+            "-(1 + 2) = -3",
+        ] {
+            assert_eq!(
+                read_expr_with_options(rejected, &functions, &options),
+                Err(ParseError::OperatorLimitExceeded {
+                    operators: 4,
+                    limit: 3
+                }),
+                "{}",
+                rejected
+            );
+        }
+    }
+
+    #[test]
+    fn test_operator_limit_protects_stack() {
+        assert_eq!(
+            read_expr(&format!("{}1", "1 + ".repeat(2_000))),
+            Err(ParseError::OperatorLimitExceeded {
+                operators: 2_000,
+                limit: 256
+            })
+        );
+        assert_eq!(
+            read_expr(&format!("{}1", "-".repeat(2_000))),
+            Err(ParseError::OperatorLimitExceeded {
+                operators: 2_000,
+                limit: 256
+            })
+        );
+
+        let long_union = vec!["para"; 200].join(" | ");
+        let expr = read_expr(&long_union).unwrap();
+        assert_eq!(read_expr(&expr.to_string()).unwrap(), expr);
+        let long_or = vec!["@lang = 'en'"; 100].join(" or ");
+        let path = read_str(&format!("para[{}]", long_or)).unwrap();
+        assert_eq!(read_str(&path.to_string()).unwrap(), path);
+
+        let deepest = format!(
+            "{}{}{}",
+            "(1 + ".repeat(31),
+            vec!["1"; 226].join(" + "),
+            ")".repeat(31)
+        );
+        let expr = read_expr(&deepest).unwrap();
+        assert_eq!(read_expr(&expr.to_string()).unwrap(), expr);
+    }
+
+    #[test]
+    fn test_step_limit() {
+        let functions = FunctionRegistry::default();
+        let options = options_with(|o| o.set_max_steps(3));
+        for accepted in &["/a/b/c", "//b/c", "$x//b", ".././c", "a[b/c]"] {
+            assert!(read_expr_with_options(accepted, &functions, &options).is_ok());
+        }
+        for rejected in &["a/b/c/d", "//b/c/d", "a//b/c", "a[b/c/d]", "a | b | c | d"] {
+            assert_eq!(
+                read_expr_with_options(rejected, &functions, &options),
+                Err(ParseError::StepLimitExceeded { steps: 4, limit: 3 }),
+                "{}",
+                rejected
+            );
+        }
+    }
+
+    #[test]
+    fn test_predicate_limit() {
+        let functions = FunctionRegistry::default();
+        let options = options_with(|o| o.set_max_predicates(2));
+        assert!(read_str_with_options("a[1][2]", &functions, &options).is_ok());
+        assert_eq!(
+            read_str_with_options("a[1]/b[c[2]]", &functions, &options),
+            Err(ParseError::PredicateLimitExceeded {
+                predicates: 3,
+                limit: 2
+            })
+        );
+        assert_eq!(
+            read_expr_with_options("$x[1][2][3]", &functions, &options),
+            Err(ParseError::PredicateLimitExceeded {
+                predicates: 3,
+                limit: 2
+            })
+        );
+    }
+
+    #[test]
+    fn test_unlimited_options() {
+        let functions = FunctionRegistry::default();
+        let long_path = vec!["para"; 2_000].join("/");
+        assert!(matches!(
+            read_str(&long_path),
+            Err(ParseError::StepLimitExceeded {
+                steps: 2_000,
+                limit: 1_024
+            })
+        ));
+        let path =
+            read_str_with_options(&long_path, &functions, &ParserOptions::unlimited()).unwrap();
+        assert_eq!(path.steps().count(), 2_000);
+    }
 }
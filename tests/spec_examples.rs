@@ -1,14 +1,20 @@
 use xml_dom_xpath::xpath1::model::*;
+use xml_dom_xpath::xpath1::parser::read_str;
 
 macro_rules! select {
     ($axis:ident, $test:ident) => {
         Predicate::select(AxisSpecifier::$axis, NodeTest::$test)
     };
     ($axis:ident named $name:expr) => {
-        Predicate::select(AxisSpecifier::$axis, NodeTest::Named($name.to_string()))
+        Predicate::select(AxisSpecifier::$axis, NodeTest::Named(QName::from($name)))
     };
 }
 
+fn check_parsed(path: &LocationPath) {
+    assert_eq!(&read_str(&path.to_string()).unwrap(), path);
+    assert_eq!(&read_str(&path.to_abbr_string()).unwrap(), path);
+}
+
 // ------------------------------------------------------------------------------------------------
 // From https://www.w3.org/TR/xpath-10/#location-paths
 // ------------------------------------------------------------------------------------------------
@@ -23,6 +29,10 @@ fn test_spec_location_path_examples_01() {
         Step::with(Select::child_elements("para")).to_abbr_string(),
         "para"
     );
+
+    check_parsed(&LocationPath::with(Step::with(Select::child_elements(
+        "para",
+    ))));
 }
 
 #[test]
@@ -35,6 +45,10 @@ fn test_spec_location_path_examples_02() {
         Step::with(Select::all_child_elements()).to_abbr_string(),
         "*"
     );
+
+    check_parsed(&LocationPath::with(
+        Step::with(Select::all_child_elements()),
+    ));
 }
 
 #[test]
@@ -47,6 +61,8 @@ fn test_spec_location_path_examples_03() {
         Step::with(Select::all_child_text()).to_abbr_string(),
         "text()"
     );
+
+    check_parsed(&LocationPath::with(Step::with(Select::all_child_text())));
 }
 
 #[test]
@@ -59,6 +75,8 @@ fn test_spec_location_path_examples_04() {
         Step::with(Select::all_children()).to_abbr_string(),
         "node()"
     );
+
+    check_parsed(&LocationPath::with(Step::with(Select::all_children())));
 }
 
 #[test]
@@ -71,6 +89,8 @@ fn test_spec_location_path_examples_05() {
         Step::with(Select::attributes("name")).to_abbr_string(),
         "@name"
     );
+
+    check_parsed(&LocationPath::with(Step::with(Select::attributes("name"))));
 }
 
 #[test]
@@ -80,6 +100,8 @@ fn test_spec_location_path_examples_06() {
         "attribute::*"
     );
     assert_eq!(Step::with(Select::all_attributes()).to_abbr_string(), "@*");
+
+    check_parsed(&LocationPath::with(Step::with(Select::all_attributes())));
 }
 
 #[test]
@@ -88,14 +110,23 @@ fn test_spec_location_path_examples_07() {
         Step::with(Select::descendant_elements("para")).to_string(),
         "descendant::para"
     );
+
+    check_parsed(&LocationPath::with(Step::with(
+        Select::descendant_elements("para"),
+    )));
 }
 
 #[test]
 fn test_spec_location_path_examples_08() {
     assert_eq!(
-        Step::from(AxisSpecifier::Ancestor, NodeTest::Named("div".to_string())).to_string(),
+        Step::from(AxisSpecifier::Ancestor, NodeTest::Named(QName::with("div"))).to_string(),
         "ancestor::div"
     );
+
+    check_parsed(&LocationPath::with(Step::from(
+        AxisSpecifier::Ancestor,
+        NodeTest::Named(QName::with("div")),
+    )));
 }
 
 #[test]
@@ -104,6 +135,10 @@ fn test_spec_location_path_examples_09() {
         Step::with(Select::ancestor_or_self_elements("div")).to_string(),
         "ancestor-or-self::div"
     );
+
+    check_parsed(&LocationPath::with(Step::with(
+        Select::ancestor_or_self_elements("div"),
+    )));
 }
 
 #[test]
@@ -112,6 +147,10 @@ fn test_spec_location_path_examples_10() {
         Step::with(Select::descendant_or_self_elements("para")).to_string(),
         "descendant-or-self::para"
     );
+
+    check_parsed(&LocationPath::with(Step::with(
+        Select::descendant_or_self_elements("para"),
+    )));
 }
 
 #[test]
@@ -120,6 +159,10 @@ fn test_spec_location_path_examples_11() {
         Step::with(Select::self_elements("para")).to_string(),
         "self::para"
     );
+
+    check_parsed(&LocationPath::with(Step::with(Select::self_elements(
+        "para",
+    ))));
 }
 
 #[test]
@@ -128,6 +171,7 @@ fn test_spec_location_path_examples_12() {
     path.child_elements("chapter").descendant_elements("para");
     assert_eq!(path.to_string(), "child::chapter/descendant::para");
     assert_eq!(path.to_abbr_string(), "chapter/descendant::para");
+    check_parsed(&path);
 }
 
 #[test]
@@ -136,12 +180,14 @@ fn test_spec_location_path_examples_13() {
     path.all_child_elements().child_elements("para");
     assert_eq!(path.to_string(), "child::*/child::para");
     assert_eq!(path.to_abbr_string(), "*/para");
+    check_parsed(&path);
 }
 
 #[test]
 fn test_spec_location_path_examples_14() {
     let path = LocationPath::absolute();
     assert_eq!(path.to_string(), "/");
+    check_parsed(&path);
 }
 
 #[test]
@@ -149,6 +195,7 @@ fn test_spec_location_path_examples_15() {
     let mut path = LocationPath::absolute();
     path.descendant_elements("para");
     assert_eq!(path.to_string(), "/descendant::para");
+    check_parsed(&path);
 }
 
 #[test]
@@ -157,6 +204,7 @@ fn test_spec_location_path_examples_16() {
     path.descendant_elements("olist").child_elements("item");
     assert_eq!(path.to_string(), "/descendant::olist/child::item");
     assert_eq!(path.to_abbr_string(), "/descendant::olist/item");
+    check_parsed(&path);
 }
 
 #[test]
@@ -172,6 +220,7 @@ fn test_spec_location_path_examples_17() {
     path.append(step);
     assert_eq!(path.to_string(), "child::para[position() = 1]");
     assert_eq!(path.to_abbr_string(), "para[1]");
+    check_parsed(&path);
 }
 
 #[test]
@@ -187,6 +236,7 @@ fn test_spec_location_path_examples_18() {
     path.append(step);
     assert_eq!(path.to_string(), "child::para[position() = last()]");
     assert_eq!(path.to_abbr_string(), "para[position() = last()]");
+    check_parsed(&path);
 }
 
 #[test]
@@ -202,6 +252,7 @@ fn test_spec_location_path_examples_19() {
     path.append(step);
     assert_eq!(path.to_string(), "child::para[position() = last() - 1]");
     assert_eq!(path.to_abbr_string(), "para[position() = last() - 1]");
+    check_parsed(&path);
 }
 
 #[test]
@@ -217,6 +268,7 @@ fn test_spec_location_path_examples_20() {
     path.append(step);
     assert_eq!(path.to_string(), "child::para[position() > 1]");
     assert_eq!(path.to_abbr_string(), "para[position() > 1]");
+    check_parsed(&path);
 }
 
 #[test]
@@ -236,6 +288,7 @@ fn test_spec_location_path_examples_21() {
         "following-sibling::chapter[position() = 1]"
     );
     assert_eq!(path.to_abbr_string(), "following-sibling::chapter[1]");
+    check_parsed(&path);
 }
 
 #[test]
@@ -255,6 +308,7 @@ fn test_spec_location_path_examples_22() {
         "preceding-sibling::chapter[position() = 1]"
     );
     assert_eq!(path.to_abbr_string(), "preceding-sibling::chapter[1]");
+    check_parsed(&path);
 }
 
 #[test]
@@ -271,6 +325,7 @@ fn test_spec_location_path_examples_23() {
     path.append(step);
     assert_eq!(path.to_string(), "/descendant::figure[position() = 42]");
     assert_eq!(path.to_abbr_string(), "/descendant::figure[42]");
+    check_parsed(&path);
 }
 
 #[test]
@@ -302,6 +357,7 @@ fn test_spec_location_path_examples_24() {
         "/child::doc/child::chapter[position() = 5]/child::section[position() = 2]"
     );
     assert_eq!(path.to_abbr_string(), "/doc/chapter[5]/section[2]");
+    check_parsed(&path);
 }
 
 #[test]
@@ -321,6 +377,7 @@ fn test_spec_location_path_examples_25() {
         "/child::para[attribute::type = 'warning']"
     );
     assert_eq!(path.to_abbr_string(), "/para[@type = 'warning']");
+    check_parsed(&path);
 }
 
 #[test]
@@ -344,6 +401,7 @@ fn test_spec_location_path_examples_26() {
         "child::para[attribute::type = 'warning'][position() = 5]"
     );
     assert_eq!(path.to_abbr_string(), "para[@type = 'warning'][5]");
+    check_parsed(&path);
 }
 
 #[test]
@@ -367,6 +425,7 @@ fn test_spec_location_path_examples_27() {
         "child::para[position() = 5][attribute::type = 'warning']"
     );
     assert_eq!(path.to_abbr_string(), "para[5][@type = 'warning']");
+    check_parsed(&path);
 }
 
 #[test]
@@ -386,6 +445,7 @@ fn test_spec_location_path_examples_28() {
         "child::chapter[child::title = 'Introduction']"
     );
     assert_eq!(path.to_abbr_string(), "chapter[title = 'Introduction']");
+    check_parsed(&path);
 }
 
 #[test]
@@ -399,6 +459,7 @@ fn test_spec_location_path_examples_29() {
     path.append(step);
     assert_eq!(path.to_string(), "child::chapter[child::title]");
     assert_eq!(path.to_abbr_string(), "chapter[title]");
+    check_parsed(&path);
 }
 
 #[test]
@@ -418,6 +479,7 @@ fn test_spec_location_path_examples_30() {
         "child::*[self::chapter or self::appendix]"
     );
     assert_eq!(path.to_abbr_string(), "*[self::chapter or self::appendix]");
+    check_parsed(&path);
 }
 
 #[test]
@@ -444,4 +506,5 @@ fn test_spec_location_path_examples_31() {
         path.to_abbr_string(),
         "*[self::chapter or self::appendix][position() = last()]"
     );
+    check_parsed(&path);
 }